@@ -0,0 +1,15 @@
+use bevy::{ecs as bevy_ecs, prelude::*};
+
+use crate::game::components::Penguin;
+
+use super::types::PenguinControlType;
+
+// stands in for a fallen deathmatch penguin until it's back on the map
+#[derive(Component)]
+pub struct Respawning {
+    pub timer: Timer,
+    pub penguin_control_type: PenguinControlType,
+}
+
+#[derive(Component)]
+pub struct DeathmatchScoreDisplay(pub Penguin);
@@ -1,2 +1,7 @@
 pub const ROUND_START_FREEZE_SECS: f32 = 0.5;
 pub const BATTLE_MODE_ROUND_DURATION_SECS: usize = 120;
+pub const GHOST_BOMB_THROWS: usize = 3;
+pub const GHOST_THROW_COOLDOWN_SECS: f32 = 1.0;
+pub const GHOST_ALPHA: f32 = 0.5;
+pub const DEATHMATCH_RESPAWN_DELAY_SECS: f32 = 3.0;
+pub const DEATHMATCH_OVERTIME_SECS: f32 = 30.0;
@@ -1,10 +1,11 @@
+mod components;
 mod constants;
 mod resources;
 mod systems;
 mod types;
 mod utils;
 
-use bevy::prelude::*;
+use bevy::{ecs::schedule::SystemConfigs, prelude::*};
 
 use crate::{
     game::{
@@ -15,11 +16,47 @@ use crate::{
         },
         Set,
     },
+    replay::ReplayPlayback,
     AppState,
 };
 
-pub use self::resources::BattleModeConfiguration;
-use self::{resources::LeaderboardTextures, systems::*};
+pub use self::{
+    constants::BATTLE_MODE_ROUND_DURATION_SECS,
+    resources::{
+        BattleModeConfiguration, BattleModeContext, BattleModeExitState, BattleModeState,
+        Leaderboard, RoundOutcome,
+    },
+    systems::{finish_round, on_death_item_pinata},
+    types::{BattleModeKind, PenguinControlType},
+    utils::{
+        get_battle_mode_map_size_fill, get_battle_mode_player_spawn_positions,
+        spawn_battle_mode_map, spawn_battle_mode_player, spawn_battle_mode_players,
+    },
+};
+use self::{
+    resources::{Deathmatch, LeaderboardTextures},
+    systems::*,
+};
+
+// shared with the tournament, the ghosts only rise if the rules allow them
+pub fn revenge_ghost_systems() -> SystemConfigs {
+    (
+        ghost_ai
+            .in_set(Set::Input)
+            .after(Set::TimeUpdate)
+            .run_if(not(resource_exists::<ReplayPlayback>())),
+        ghost_move.in_set(Set::PlayerMovement).after(Set::Input),
+        (ghost_bomb_throw, apply_deferred)
+            .chain()
+            .in_set(Set::BombSpawn)
+            .after(Set::Input),
+        (revenge_ghosts, apply_deferred)
+            .chain()
+            .after(Set::PlayerDeathEvent)
+            .before(finish_round),
+    )
+        .into_configs()
+}
 
 pub struct BattleModePlugin;
 
@@ -40,7 +77,15 @@ impl Plugin for BattleModePlugin {
                 Update,
                 battle_mode_manager.run_if(in_state(AppState::BattleModeManager)),
             )
-            .add_systems(OnEnter(AppState::RoundStartFreeze), setup_penguin_portraits)
+            .add_systems(
+                OnEnter(AppState::RoundStartFreeze),
+                (
+                    setup_penguin_portraits,
+                    apply_deferred,
+                    setup_deathmatch_scoreboard.run_if(resource_exists::<Deathmatch>()),
+                )
+                    .chain(),
+            )
             .add_systems(
                 Update,
                 finish_freeze.run_if(in_state(AppState::RoundStartFreeze)),
@@ -52,12 +97,23 @@ impl Plugin for BattleModePlugin {
             .add_systems(
                 Update,
                 leaderboard_display_update.run_if(in_state(AppState::LeaderboardDisplay)),
+            )
+            .add_systems(
+                OnEnter(AppState::MatchStatsDisplay),
+                (record_battle_results, setup_match_stats_display),
+            )
+            .add_systems(
+                Update,
+                match_stats_display_update
+                    .after(crate::common::Label::InputMapping)
+                    .run_if(in_state(AppState::MatchStatsDisplay)),
             );
 
         app.add_systems(
             Update,
             (
                 common_game_systems(),
+                revenge_ghost_systems(),
                 game_timer_tick.in_set(Set::TimeUpdate),
                 (wall_of_death_update, apply_deferred)
                     .chain()
@@ -68,9 +124,31 @@ impl Plugin for BattleModePlugin {
                     (on_death_item_pinata, apply_deferred)
                         .chain()
                         .in_set(Set::ItemSpawn),
-                    (finish_round, hud_update).after(Set::TimeUpdate),
+                    (
+                        finish_round.run_if(not(resource_exists::<Deathmatch>())),
+                        hud_update,
+                    )
+                        .after(Set::TimeUpdate),
+                    track_match_deaths,
                 )
                     .after(Set::PlayerDeathEvent),
+                track_match_stats.before(Set::TimeUpdate),
+                (
+                    (deathmatch_respawn, apply_deferred)
+                        .chain()
+                        .in_set(Set::PlayerSpawn)
+                        .after(Set::TimeUpdate),
+                    (
+                        (deathmatch_scoring, apply_deferred).chain(),
+                        (
+                            finish_deathmatch_round.after(Set::TimeUpdate),
+                            deathmatch_scoreboard_update,
+                        ),
+                    )
+                        .chain()
+                        .after(Set::PlayerDeathEvent),
+                )
+                    .run_if(resource_exists::<Deathmatch>()),
             )
                 .run_if(in_state(AppState::BattleModeInGame)),
         );
@@ -1,18 +1,40 @@
 use bevy::{ecs as bevy_ecs, prelude::*, utils::HashMap};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    game::{components::Penguin, types::BotDifficulty},
+    game::{
+        ascii_map::AsciiMap,
+        components::{Penguin, Position, TeamID},
+        map_generators::MapGeneratorKind,
+        rules::GameRules,
+        types::BotDifficulty,
+    },
     loading::resources::AssetsLoading,
+    AppState,
 };
 
-use super::types::PenguinControlType;
+use super::types::{BattleModeKind, PenguinControlType};
 
-#[derive(Resource)]
+#[derive(Clone, Serialize, Deserialize, Resource)]
 pub struct BattleModeConfiguration {
     pub amount_of_players: usize,
     pub amount_of_bots: usize,
     pub winning_score: usize,
     pub bot_difficulty: BotDifficulty,
+    // a handcrafted map, otherwise the map is generated each round
+    #[serde(default)]
+    pub map: Option<AsciiMap>,
+    // a random generator is picked each round if missing
+    #[serde(default)]
+    pub map_generator: Option<MapGeneratorKind>,
+    #[serde(default)]
+    pub rules: GameRules,
+    // the team of each penguin in a team battle, everyone is on their own otherwise
+    #[serde(default)]
+    pub teams: Option<Vec<usize>>,
+    #[serde(default)]
+    pub kind: BattleModeKind,
 }
 
 pub enum BattleModeState {
@@ -23,27 +45,134 @@ pub enum BattleModeState {
 }
 
 pub struct Leaderboard {
-    pub scores: HashMap<Penguin, usize>,
+    pub scores: HashMap<TeamID, usize>,
     pub winning_score: usize,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum RoundOutcome {
-    Winner(Penguin),
+    Winner(TeamID),
+    // teams sharing the win, e.g. the survivors of an infection round
+    Winners(Vec<TeamID>),
     Tie,
 }
 
+impl RoundOutcome {
+    pub fn winners(&self) -> &[TeamID] {
+        match self {
+            Self::Winner(team_id) => std::slice::from_ref(team_id),
+            Self::Winners(team_ids) => team_ids,
+            Self::Tie => &[],
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct BattleModeContext {
     pub state: BattleModeState,
-    pub players: Vec<(Penguin, PenguinControlType)>,
-    pub bot_difficulty: BotDifficulty,
+    pub players: Vec<(Penguin, PenguinControlType, TeamID)>,
     pub leaderboard: Leaderboard,
     pub round_outcome: Option<RoundOutcome>,
     // cache of the map block fill ratio
     pub percent_of_passable_positions_to_fill: f32,
+    pub map: Option<AsciiMap>,
+    pub map_generator: Option<MapGeneratorKind>,
+    pub kind: BattleModeKind,
+}
+
+impl BattleModeContext {
+    pub fn configuration(&self, game_rules: &GameRules) -> BattleModeConfiguration {
+        BattleModeConfiguration {
+            amount_of_players: self
+                .players
+                .iter()
+                .filter(|(_, pct, _)| matches!(pct, PenguinControlType::Human(_)))
+                .count(),
+            amount_of_bots: self
+                .players
+                .iter()
+                .filter(|(_, pct, _)| matches!(pct, PenguinControlType::Bot(_)))
+                .count(),
+            winning_score: self.leaderboard.winning_score,
+            bot_difficulty: self
+                .players
+                .iter()
+                .find_map(|(_, pct, _)| match pct {
+                    PenguinControlType::Bot(bot_difficulty) => Some(*bot_difficulty),
+                    PenguinControlType::Human(_) => None,
+                })
+                .unwrap_or(BotDifficulty::Medium),
+            map: self.map.clone(),
+            map_generator: self.map_generator,
+            rules: game_rules.clone(),
+            teams: if self.is_team_battle() {
+                Some(self.players.iter().map(|(_, _, t)| t.0).collect())
+            } else {
+                None
+            },
+            kind: self.kind,
+        }
+    }
+
+    pub fn is_team_battle(&self) -> bool {
+        self.players.iter().map(|(_, _, t)| t).unique().count() < self.players.len()
+    }
+
+    pub fn team_members(&self, team_id: TeamID) -> impl Iterator<Item = Penguin> + '_ {
+        self.players
+            .iter()
+            .filter(move |(_, _, t)| *t == team_id)
+            .map(|(p, _, _)| *p)
+    }
 }
 
+// the scores and respawns of a deathmatch round
+#[derive(Resource)]
+pub struct Deathmatch {
+    // kills minus self-kills
+    pub scores: HashMap<Penguin, isize>,
+    // where the fallen penguins can come back
+    pub spawn_positions: Vec<Position>,
+    // runs once the time is up with a tie at the top, there are no respawns during it
+    pub overtime: Option<Timer>,
+    // kept so that kills can be credited to players who already died
+    pub player_penguins: HashMap<Entity, (Penguin, TeamID)>,
+}
+
+impl Deathmatch {
+    pub fn new(penguins: impl Iterator<Item = Penguin>, spawn_positions: Vec<Position>) -> Self {
+        Self {
+            scores: penguins.map(|p| (p, 0)).collect(),
+            spawn_positions,
+            overtime: None,
+            player_penguins: HashMap::default(),
+        }
+    }
+
+    // the teams sharing the top score
+    pub fn leaders(&self, players: &[(Penguin, PenguinControlType, TeamID)]) -> Vec<TeamID> {
+        let team_scores = players
+            .iter()
+            .map(|(p, _, t)| (*t, self.scores.get(p).copied().unwrap_or(0)))
+            .into_group_map()
+            .into_iter()
+            .map(|(t, s)| (t, s.into_iter().sum::<isize>()))
+            .collect::<Vec<(TeamID, isize)>>();
+        let top_score = team_scores.iter().map(|(_, s)| *s).max();
+
+        team_scores
+            .into_iter()
+            .filter(|(_, s)| Some(*s) == top_score)
+            .map(|(t, _)| t)
+            .sorted()
+            .collect()
+    }
+}
+
+// where to go once the battle is over, the main menu if missing
+#[derive(Resource)]
+pub struct BattleModeExitState(pub AppState);
+
 // round start freeze
 #[derive(Resource)]
 pub struct FreezeTimer(pub Timer);
@@ -55,6 +184,14 @@ pub struct LeaderboardDisplayContext {
     pub timer: Timer,
 }
 
+// match stats display
+#[derive(Resource)]
+pub struct MatchStatsDisplayContext {
+    pub match_stats_display_box: Entity,
+    // the text that reports the outcome of an export
+    pub export_status_text: Entity,
+}
+
 #[derive(Resource)]
 pub struct LeaderboardTextures {
     pub trophy: Handle<Image>,
@@ -77,3 +214,29 @@ impl FromWorld for LeaderboardTextures {
         leaderboard_textures
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::types::BotDifficulty;
+
+    use super::*;
+
+    #[test]
+    fn test_deathmatch_leaders() {
+        let bot = PenguinControlType::Bot(BotDifficulty::Medium);
+        let players = [
+            (Penguin(0), bot, TeamID(0)),
+            (Penguin(1), bot, TeamID(1)),
+            (Penguin(2), bot, TeamID(0)),
+            (Penguin(3), bot, TeamID(1)),
+        ];
+        let mut deathmatch = Deathmatch::new(players.iter().map(|(p, _, _)| *p), vec![]);
+        assert!(deathmatch.leaders(&players) == vec![TeamID(0), TeamID(1)]);
+
+        // team scores add up, self-kills included
+        deathmatch.scores.insert(Penguin(0), 3);
+        deathmatch.scores.insert(Penguin(2), -2);
+        deathmatch.scores.insert(Penguin(1), 2);
+        assert!(deathmatch.leaders(&players) == vec![TeamID(1)]);
+    }
+}
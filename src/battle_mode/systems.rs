@@ -1,26 +1,46 @@
 use bevy::{
-    ecs::event::Events, prelude::*, render::camera::Camera, utils::HashSet, window::PrimaryWindow,
+    ecs::{event::Events, query::Has},
+    prelude::*,
+    render::camera::Camera,
+    utils::HashSet,
+    window::PrimaryWindow,
 };
+use itertools::Itertools;
 use rand::{prelude::IteratorRandom, Rng};
 
 use crate::{
     common::{
         constants::{COLORS, PIXEL_SCALE},
         resources::*,
+        types::InputAction,
     },
     game::{
         components::*,
-        constants::{TILE_HEIGHT, TILE_WIDTH},
+        constants::{
+            AIRBORNE_BOMB_MOVE_COOLDOWN_SECS, BOMB_THROW_DISTANCE, TILE_HEIGHT, TILE_WIDTH,
+        },
         events::*,
+        loot_tables::{LootSource, LootTables, MatchLoot},
         resources::*,
+        rules::GameRules,
+        simulation::get_flame_positions,
+        stats::MatchStats,
+        types::{BombKind, Cooldown, Direction, PlayerAction},
         utils::*,
     },
     map_transition::MapTransitionInput,
+    replay::ReplayPlayback,
     AppState,
 };
 
 use super::{
-    constants::BATTLE_MODE_ROUND_DURATION_SECS, resources::*, types::PenguinControlType, utils::*,
+    components::{DeathmatchScoreDisplay, Respawning},
+    constants::{
+        BATTLE_MODE_ROUND_DURATION_SECS, DEATHMATCH_OVERTIME_SECS, DEATHMATCH_RESPAWN_DELAY_SECS,
+    },
+    resources::*,
+    types::{BattleModeKind, PenguinControlType},
+    utils::*,
 };
 
 pub fn setup_battle_mode(
@@ -29,14 +49,20 @@ pub fn setup_battle_mode(
     fonts: Res<Fonts>,
     hud_colors: Res<HUDColors>,
     battle_mode_configuration: Res<BattleModeConfiguration>,
+    next_match_seed: Option<Res<NextMatchSeed>>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    let world_id = WorldID(rand::thread_rng().gen_range(1..=3));
+    let mut game_rng = GameRng::for_new_match(next_match_seed.as_deref());
+
+    let world_id = WorldID(game_rng.map.gen_range(1..=3));
     game_textures.set_map_textures(world_id);
 
-    let (map_size, percent_of_passable_positions_to_fill) = get_battle_mode_map_size_fill(
+    let (mut map_size, percent_of_passable_positions_to_fill) = get_battle_mode_map_size_fill(
         battle_mode_configuration.amount_of_players + battle_mode_configuration.amount_of_bots,
     );
+    if let Some(ref ascii_map) = battle_mode_configuration.map {
+        map_size = ascii_map.map_size();
+    }
 
     // spawn HUD
     commands
@@ -66,7 +92,7 @@ pub fn setup_battle_mode(
             );
         });
 
-    let players: Vec<(Penguin, PenguinControlType)> =
+    let players: Vec<(Penguin, PenguinControlType, TeamID)> =
         (0..battle_mode_configuration.amount_of_players + battle_mode_configuration.amount_of_bots)
             .map(|i| {
                 (
@@ -74,33 +100,45 @@ pub fn setup_battle_mode(
                     if i < battle_mode_configuration.amount_of_players {
                         PenguinControlType::Human(i)
                     } else {
-                        PenguinControlType::Bot
+                        PenguinControlType::Bot(battle_mode_configuration.bot_difficulty)
                     },
+                    TeamID(
+                        battle_mode_configuration
+                            .teams
+                            .as_ref()
+                            .map_or(i, |teams| teams[i]),
+                    ),
                 )
             })
             .collect();
 
+    commands.insert_resource(MatchStats::new(players.iter().map(|(p, _, t)| (*p, *t))));
+
     let leaderboard = Leaderboard {
-        scores: players.iter().map(|(pt, _)| (*pt, 0)).collect(),
+        scores: players.iter().map(|(_, _, t)| (*t, 0)).collect(),
         winning_score: battle_mode_configuration.winning_score,
     };
     commands.insert_resource(BattleModeContext {
         state: BattleModeState::RoundSetup,
         players,
-        bot_difficulty: battle_mode_configuration.bot_difficulty,
         leaderboard,
         round_outcome: None,
         percent_of_passable_positions_to_fill,
+        map: battle_mode_configuration.map.clone(),
+        map_generator: battle_mode_configuration.map_generator,
+        kind: battle_mode_configuration.kind,
     });
 
     let game_mode_manager_state = AppState::BattleModeManager;
     commands.insert_resource(GameContext {
         pausable: battle_mode_configuration.amount_of_players > 0,
-        reduced_loot: true,
         game_mode_manager_state,
     });
     commands.insert_resource(world_id);
     commands.insert_resource(map_size);
+    commands.insert_resource(game_rng);
+    commands.remove_resource::<NextMatchSeed>();
+    commands.insert_resource(battle_mode_configuration.rules.clone());
 
     commands.remove_resource::<BattleModeConfiguration>();
 
@@ -112,6 +150,9 @@ pub fn battle_mode_manager(
     game_textures: Res<GameTextures>,
     map_size: Res<MapSize>,
     mut battle_mode_context: ResMut<BattleModeContext>,
+    loot_tables: Res<LootTables>,
+    game_rules: Res<GameRules>,
+    mut game_rng: ResMut<GameRng>,
     game_option_store: Res<GameOptionStore>,
     mut next_state: ResMut<NextState<AppState>>,
     query: Query<Entity, (Without<Window>, Without<Camera>, Without<UIComponent>)>,
@@ -122,25 +163,54 @@ pub fn battle_mode_manager(
         match battle_mode_context.state {
             BattleModeState::RoundSetup => {
                 // map generation //
-                let player_spawn_positions = spawn_battle_mode_players(
-                    &mut commands,
-                    &game_textures,
-                    *map_size,
-                    &battle_mode_context.players,
-                    battle_mode_context.bot_difficulty,
-                );
+                let (wall_entity_reveal_groups, player_spawn_positions) =
+                    if let Some(ref ascii_map) = battle_mode_context.map {
+                        let player_spawn_positions = spawn_battle_mode_players(
+                            &mut commands,
+                            &game_textures,
+                            &ascii_map.penguin_spawn_positions(),
+                            &battle_mode_context.players,
+                            &game_rules,
+                        );
 
-                let wall_entity_reveal_groups = spawn_map(
-                    &mut commands,
-                    &game_textures,
-                    *map_size,
-                    battle_mode_context.percent_of_passable_positions_to_fill,
-                    true,
-                    &player_spawn_positions,
-                    &[],
-                    false,
-                );
+                        (
+                            spawn_ascii_map(&mut commands, &game_textures, ascii_map, false),
+                            player_spawn_positions,
+                        )
+                    } else {
+                        let player_spawn_positions = spawn_battle_mode_players(
+                            &mut commands,
+                            &game_textures,
+                            &get_battle_mode_player_spawn_positions(*map_size),
+                            &battle_mode_context.players,
+                            &game_rules,
+                        );
 
+                        match spawn_battle_mode_map(
+                            &mut commands,
+                            &game_textures,
+                            battle_mode_context.map_generator,
+                            *map_size,
+                            battle_mode_context.percent_of_passable_positions_to_fill,
+                            &player_spawn_positions,
+                            &mut game_rng.map,
+                        ) {
+                            Ok(wall_entity_reveal_groups) => {
+                                (wall_entity_reveal_groups, player_spawn_positions)
+                            }
+                            Err(e) => {
+                                eprintln!("Cannot generate the battle mode map. Error: {}", e);
+                                next_state.set(AppState::BattleModeTeardown);
+                                return;
+                            }
+                        }
+                    };
+
+                commands.insert_resource(MatchLoot::new(
+                    &loot_tables,
+                    &loot_tables.battle,
+                    &game_rules,
+                ));
                 commands.insert_resource(GameTimer(Timer::from_seconds(
                     BATTLE_MODE_ROUND_DURATION_SECS as f32,
                     TimerMode::Once,
@@ -148,10 +218,25 @@ pub fn battle_mode_manager(
                 // update HUD clock
                 query4.single_mut().sections[0].value =
                     format_hud_time(BATTLE_MODE_ROUND_DURATION_SECS);
-                commands.insert_resource(WallOfDeath::Dormant(Timer::from_seconds(
-                    BATTLE_MODE_ROUND_DURATION_SECS as f32 / 2.0,
-                    TimerMode::Once,
-                )));
+                match battle_mode_context.kind {
+                    BattleModeKind::LastStanding
+                    | BattleModeKind::CaptureTheFlag
+                    | BattleModeKind::KingOfTheHill => {
+                        commands.insert_resource(WallOfDeath::new(&game_rules));
+                    }
+                    BattleModeKind::Infection => {
+                        // the infected keep respawning at their spawn positions
+                        commands.insert_resource(WallOfDeath::Done);
+                    }
+                    BattleModeKind::Deathmatch => {
+                        // the wall of death is saved for the overtime
+                        commands.insert_resource(WallOfDeath::Done);
+                        commands.insert_resource(Deathmatch::new(
+                            battle_mode_context.players.iter().map(|(p, _, _)| *p),
+                            player_spawn_positions,
+                        ));
+                    }
+                }
 
                 battle_mode_context.round_outcome = None;
 
@@ -170,21 +255,35 @@ pub fn battle_mode_manager(
                 start_round(battle_mode_context, commands, next_state)
             }
             BattleModeState::InGame => {
-                match battle_mode_context.round_outcome {
+                match battle_mode_context.round_outcome.clone() {
                     Some(result) => {
                         match result {
                             RoundOutcome::Tie => {
                                 println!("Round over with no winners!");
                             }
-                            RoundOutcome::Winner(penguin) => {
-                                println!("Player {:?} won the round!", penguin.0);
-                                *battle_mode_context
-                                    .leaderboard
-                                    .scores
-                                    .get_mut(&penguin)
-                                    .unwrap() += 1;
+                            RoundOutcome::Winner(team_id) => {
+                                if battle_mode_context.is_team_battle() {
+                                    println!("Team {:?} won the round!", team_id.0);
+                                } else {
+                                    println!("Player {:?} won the round!", team_id.0);
+                                }
+                            }
+                            RoundOutcome::Winners(ref team_ids) => {
+                                let team_ids: Vec<usize> = team_ids.iter().map(|t| t.0).collect();
+                                if battle_mode_context.is_team_battle() {
+                                    println!("Teams {:?} won the round!", team_ids);
+                                } else {
+                                    println!("Players {:?} won the round!", team_ids);
+                                }
                             }
                         }
+                        for team_id in result.winners() {
+                            *battle_mode_context
+                                .leaderboard
+                                .scores
+                                .get_mut(team_id)
+                                .unwrap() += 1;
+                        }
 
                         for entity in query.iter() {
                             commands.entity(entity).despawn();
@@ -205,14 +304,14 @@ pub fn battle_mode_manager(
                 }
             }
             BattleModeState::LeaderboardDisplay => {
-                if let Some((penguin, _)) = battle_mode_context
+                if let Some((team_id, _)) = battle_mode_context
                     .leaderboard
                     .scores
                     .iter()
                     .find(|(_, s)| **s == battle_mode_context.leaderboard.winning_score)
                 {
-                    println!("Tournament complete! Winner: {:?}", penguin.0);
-                    next_state.set(AppState::BattleModeTeardown);
+                    println!("Tournament complete! Winner: {:?}", team_id.0);
+                    next_state.set(AppState::MatchStatsDisplay);
                 } else {
                     battle_mode_context.state = BattleModeState::RoundSetup;
                     continue;
@@ -240,7 +339,8 @@ pub fn on_death_item_pinata(
     mut commands: Commands,
     game_textures: Res<GameTextures>,
     map_size: Res<MapSize>,
-    game_context: Res<GameContext>,
+    mut match_loot: ResMut<MatchLoot>,
+    mut game_rng: ResMut<GameRng>,
     query: Query<
         &Position,
         Or<(
@@ -264,43 +364,595 @@ pub fn on_death_item_pinata(
                 })
             })
             .filter(|p| !invalid_positions.contains(p));
-        for position in valid_positions.choose_multiple(&mut rand::thread_rng(), 3) {
+        for position in valid_positions.choose_multiple(&mut game_rng.loot, 3) {
             generate_item_at_position(
                 position,
                 &mut commands,
                 &game_textures,
-                game_context.reduced_loot,
+                &mut match_loot,
+                LootSource::Death,
+                &mut game_rng.loot,
             );
         }
     }
 }
 
+pub fn track_match_stats(
+    time: Res<Time>,
+    mut match_stats: ResMut<MatchStats>,
+    query: Query<(Entity, &Penguin, Has<Player>)>,
+) {
+    // ghosts are registered too, so that their kills are credited
+    for (entity, penguin, is_player) in query.iter() {
+        match_stats.register_player(entity, *penguin);
+        if is_player {
+            match_stats.get_mut(entity).unwrap().time_alive += time.delta();
+        }
+    }
+}
+
+pub fn track_match_deaths(
+    mut match_stats: ResMut<MatchStats>,
+    mut ev_player_death_event: EventReader<PlayerDeathEvent>,
+) {
+    for PlayerDeathEvent { player, source, .. } in ev_player_death_event.iter() {
+        if let Some(stats) = match_stats.get_mut(*player) {
+            stats.deaths += 1;
+            if *source == DamageSource::SelfKill {
+                stats.self_kills += 1;
+            }
+        }
+
+        if let DamageSource::Bomb(bomber) = source {
+            if let Some(stats) = match_stats.get_mut(*bomber) {
+                stats.kills += 1;
+            }
+        }
+    }
+}
+
+pub fn revenge_ghosts(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    (map_size, game_rules): (Res<MapSize>, Res<GameRules>),
+    battle_mode_context: Res<BattleModeContext>,
+    query: Query<(&Penguin, &TeamID), With<Ghost>>,
+    deathmatch: Option<Res<Deathmatch>>,
+    mut ev_player_death_event: EventReader<PlayerDeathEvent>,
+) {
+    // fallen deathmatch and infected penguins respawn instead
+    if !game_rules.revenge_ghosts
+        || deathmatch.is_some()
+        || battle_mode_context.kind == BattleModeKind::Infection
+    {
+        return;
+    }
+
+    let ghost_positions: Vec<Position> = get_ghost_ring_positions(*map_size)
+        .into_iter()
+        .filter(|p| get_ghost_throw_direction(*p, *map_size).is_some())
+        .collect();
+    let mut revived_ghosts = HashSet::default();
+    for (position, penguin, source) in ev_player_death_event
+        .iter()
+        .filter_map(|e| e.penguin.map(|p| (e.position, p, e.source)))
+    {
+        let get_player = |penguin: Penguin| {
+            battle_mode_context
+                .players
+                .iter()
+                .find(|(p, _, _)| *p == penguin)
+                .copied()
+        };
+        let (penguin_control_type, team_id) = match get_player(penguin) {
+            Some((_, penguin_control_type, team_id)) => (penguin_control_type, team_id),
+            None => continue,
+        };
+
+        // a ghost that takes out an enemy gets back into the round where the enemy fell
+        if let DamageSource::Bomb(bomber) = source {
+            if let Ok((ghost_penguin, ghost_team_id)) = query.get(bomber) {
+                if *ghost_team_id != team_id && revived_ghosts.insert(bomber) {
+                    println!("ghost took revenge: {:?}", bomber);
+                    commands.entity(bomber).despawn_recursive();
+
+                    let (_, ghost_control_type, _) = get_player(*ghost_penguin).unwrap();
+                    let player = spawn_battle_mode_player(
+                        &mut commands,
+                        &game_textures,
+                        *ghost_penguin,
+                        ghost_control_type,
+                        *ghost_team_id,
+                        position,
+                        &game_rules,
+                    );
+                    commands
+                        .entity(player)
+                        .insert(Immortal::new(game_rules.immortality_secs));
+                }
+            }
+        }
+
+        let ghost_position = *ghost_positions
+            .iter()
+            .min_by_key(|p| (p.y - position.y).abs() + (p.x - position.x).abs())
+            .unwrap();
+        println!("ghost rises: {:?}", ghost_position);
+        spawn_ghost(
+            &mut commands,
+            &game_textures,
+            penguin,
+            penguin_control_type,
+            team_id,
+            ghost_position,
+        );
+    }
+}
+
+pub fn ghost_ai(
+    map_size: Res<MapSize>,
+    game_rules: Res<GameRules>,
+    query: Query<(Entity, &Ghost, &Position, &MoveCooldown, &TeamID), With<BotAI>>,
+    query2: Query<(&Position, &TeamID), With<Player>>,
+    mut ev_player_action: EventWriter<PlayerActionEvent>,
+) {
+    let ring_positions = get_ghost_ring_positions(*map_size);
+    let throw_reach = (BOMB_THROW_DISTANCE + game_rules.starting_bomb_range) as isize;
+
+    for (entity, ghost, position, move_cooldown, team_id) in query.iter() {
+        if ghost.throws_left == 0 {
+            continue;
+        }
+
+        let enemy_positions: Vec<Position> = query2
+            .iter()
+            .filter(|(_, tid)| **tid != *team_id)
+            .map(|(p, _)| *p)
+            .collect();
+
+        // throw once an enemy is within reach of the bomb
+        if let Some(direction) = get_ghost_throw_direction(*position, *map_size) {
+            if ghost.throw_cooldown.ready()
+                && enemy_positions.iter().any(|p| match direction {
+                    Direction::Up | Direction::Down => {
+                        p.x == position.x && (p.y - position.y).abs() <= throw_reach
+                    }
+                    Direction::Left | Direction::Right => {
+                        p.y == position.y && (p.x - position.x).abs() <= throw_reach
+                    }
+                })
+            {
+                ev_player_action.send(PlayerActionEvent {
+                    player: entity,
+                    action: PlayerAction::DropBomb,
+                });
+                continue;
+            }
+        }
+
+        if !move_cooldown.0.ready() {
+            continue;
+        }
+
+        // otherwise go around the ring, the shorter way, to the tile closest to the nearest enemy
+        let distance = |a: &Position, b: &Position| (a.y - b.y).abs() + (a.x - b.x).abs();
+        let target = enemy_positions
+            .iter()
+            .min_by_key(|p| distance(p, position))
+            .and_then(|enemy_position| {
+                ring_positions
+                    .iter()
+                    .filter(|p| get_ghost_throw_direction(**p, *map_size).is_some())
+                    .min_by_key(|p| distance(p, enemy_position))
+            });
+        if let Some(target) = target {
+            let ring_length = ring_positions.len();
+            let index = ring_positions.iter().position(|p| p == position).unwrap();
+            let target_index = ring_positions.iter().position(|p| p == target).unwrap();
+            let steps_clockwise = (target_index + ring_length - index) % ring_length;
+            if steps_clockwise == 0 {
+                continue;
+            }
+
+            let next_position = if steps_clockwise <= ring_length / 2 {
+                ring_positions[(index + 1) % ring_length]
+            } else {
+                ring_positions[(index + ring_length - 1) % ring_length]
+            };
+            if let Some(direction) = Direction::LIST
+                .into_iter()
+                .find(|d| position.offset(*d, 1) == next_position)
+            {
+                ev_player_action.send(PlayerActionEvent {
+                    player: entity,
+                    action: PlayerAction::Move(direction),
+                });
+            }
+        }
+    }
+}
+
+pub fn ghost_move(
+    map_size: Res<MapSize>,
+    mut ev_player_action: EventReader<PlayerActionEvent>,
+    mut query: Query<
+        (
+            &mut Position,
+            &mut Transform,
+            &mut Sprite,
+            Option<&mut MoveCooldown>,
+        ),
+        With<Ghost>,
+    >,
+) {
+    for (entity, direction) in ev_player_action.iter().filter_map(|p| {
+        if let PlayerAction::Move(direction) = p.action {
+            Some((p.player, direction))
+        } else {
+            None
+        }
+    }) {
+        if let Ok((mut position, mut transform, mut sprite, move_cooldown)) = query.get_mut(entity)
+        {
+            match direction {
+                Direction::Left => sprite.flip_x = true,
+                Direction::Right => sprite.flip_x = false,
+                _ => (),
+            }
+
+            // ghosts can't leave the outer ring
+            let new_position = position.offset(direction, 1);
+            if get_ring(new_position, *map_size) != 0 {
+                continue;
+            }
+
+            if let Some(mut move_cooldown) = move_cooldown {
+                if !move_cooldown.0.ready() {
+                    continue;
+                }
+                move_cooldown.0.trigger();
+            }
+
+            *position = new_position;
+            let translation = &mut transform.translation;
+            translation.x = get_x(position.x);
+            translation.y = get_y(position.y);
+        }
+    }
+}
+
+pub fn ghost_bomb_throw(
+    mut commands: Commands,
+    time: Res<Time>,
+    (game_textures, fonts, world_id): (Res<GameTextures>, Res<Fonts>, Res<WorldID>),
+    (map_size, game_rules): (Res<MapSize>, Res<GameRules>),
+    mut match_stats: Option<ResMut<MatchStats>>,
+    mut query: Query<(&mut Ghost, &Position)>,
+    mut ev_player_action: EventReader<PlayerActionEvent>,
+) {
+    for (mut ghost, _) in query.iter_mut() {
+        ghost.throw_cooldown.tick(time.delta());
+    }
+
+    for entity in ev_player_action
+        .iter()
+        .filter(|pa| matches!(pa.action, PlayerAction::DropBomb))
+        .map(|pa| pa.player)
+    {
+        if let Ok((mut ghost, position)) = query.get_mut(entity) {
+            if ghost.throws_left == 0 || !ghost.throw_cooldown.ready() {
+                continue;
+            }
+
+            // no throwing from the corners, the bomb would only fly over the walls
+            if let Some(direction) = get_ghost_throw_direction(*position, *map_size) {
+                println!("ghost bomb thrown: {:?}", position);
+                ghost.throws_left -= 1;
+                ghost.throw_cooldown.trigger();
+                if let Some(stats) = match_stats.as_mut().and_then(|ms| ms.get_mut(entity)) {
+                    stats.bombs_dropped += 1;
+                }
+
+                let bomb_entity = spawn_bomb(
+                    &mut commands,
+                    &game_textures,
+                    &fonts,
+                    *world_id,
+                    *position,
+                    Bomb {
+                        owner: Some(entity),
+                        ignited_by: None,
+                        range: game_rules.starting_bomb_range,
+                        kind: BombKind::Regular,
+                        timer: Timer::from_seconds(game_rules.bomb_fuse_secs, TimerMode::Once),
+                    },
+                );
+                commands.entity(bomb_entity).remove::<Solid>().insert((
+                    Airborne::new(direction),
+                    MoveCooldown(Cooldown::from_seconds(AIRBORNE_BOMB_MOVE_COOLDOWN_SECS)),
+                ));
+            }
+        }
+    }
+}
+
 pub fn finish_round(
     game_timer: Res<GameTimer>,
     mut battle_mode_context: ResMut<BattleModeContext>,
-    query: Query<&Penguin, With<Player>>,
+    mut match_stats: Option<ResMut<MatchStats>>,
+    query: Query<&TeamID, (With<Player>, With<Penguin>)>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
+    // the game mode might have decided the round already
+    if battle_mode_context.round_outcome.is_some() {
+        return;
+    }
+
+    let surviving_teams: HashSet<TeamID> = query.iter().copied().collect();
+
     let mut round_over = false;
-    if game_timer.0.finished() || query.iter().count() == 0 {
+    if game_timer.0.finished() || surviving_teams.is_empty() {
         battle_mode_context.round_outcome = Some(RoundOutcome::Tie);
         round_over = true;
-    } else if let Ok(penguin) = query.get_single() {
-        battle_mode_context.round_outcome = Some(RoundOutcome::Winner(*penguin));
+    } else if surviving_teams.len() == 1 {
+        battle_mode_context.round_outcome = Some(RoundOutcome::Winner(
+            *surviving_teams.iter().next().unwrap(),
+        ));
         round_over = true;
     }
 
     if round_over {
+        if let Some(ref mut match_stats) = match_stats {
+            match_stats.rounds += 1;
+        }
         next_state.set(AppState::BattleModeManager);
     }
 }
 
+pub fn deathmatch_scoring(
+    mut commands: Commands,
+    mut deathmatch: ResMut<Deathmatch>,
+    battle_mode_context: Res<BattleModeContext>,
+    query: Query<(Entity, &Penguin, &TeamID), With<Player>>,
+    mut ev_player_death_event: EventReader<PlayerDeathEvent>,
+) {
+    for (entity, penguin, team_id) in query.iter() {
+        deathmatch
+            .player_penguins
+            .insert(entity, (*penguin, *team_id));
+    }
+
+    for PlayerDeathEvent {
+        penguin, source, ..
+    } in ev_player_death_event.iter()
+    {
+        let (penguin, penguin_control_type, team_id) = match battle_mode_context
+            .players
+            .iter()
+            .find(|(p, _, _)| Some(*p) == *penguin)
+        {
+            Some(player) => *player,
+            None => continue,
+        };
+
+        match source {
+            DamageSource::SelfKill => *deathmatch.scores.get_mut(&penguin).unwrap() -= 1,
+            DamageSource::Bomb(bomber) => {
+                // teammates don't score off each other
+                if let Some((bomber_penguin, bomber_team_id)) =
+                    deathmatch.player_penguins.get(bomber).copied()
+                {
+                    if bomber_team_id != team_id {
+                        *deathmatch.scores.get_mut(&bomber_penguin).unwrap() += 1;
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        if deathmatch.overtime.is_none() {
+            commands.spawn((
+                Respawning {
+                    timer: Timer::from_seconds(DEATHMATCH_RESPAWN_DELAY_SECS, TimerMode::Once),
+                    penguin_control_type,
+                },
+                penguin,
+                team_id,
+            ));
+        }
+    }
+}
+
+pub fn deathmatch_respawn(
+    mut commands: Commands,
+    (time, game_rules): (Res<Time>, Res<GameRules>),
+    (game_textures, deathmatch): (Res<GameTextures>, Res<Deathmatch>),
+    mut query: Query<(Entity, &Penguin, &TeamID, &mut Respawning)>,
+    query2: Query<(&Position, &TeamID), With<Player>>,
+    query3: Query<(&Position, Option<&Bomb>), With<Solid>>,
+    query4: Query<&Position, With<Fire>>,
+) {
+    // nobody comes back during the overtime
+    if deathmatch.overtime.is_some() {
+        for (entity, _, _, _) in query.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let blocked_positions: HashSet<Position> = query3.iter().map(|(p, _)| *p).collect();
+    let danger_positions: HashSet<Position> = query3
+        .iter()
+        .filter_map(|(p, b)| b.map(|b| (p, b)))
+        .flat_map(|(p, b)| {
+            get_flame_positions(*p, b.range, b.kind, &blocked_positions, &HashSet::default()).0
+        })
+        .chain(query4.iter().copied())
+        .collect();
+    let mut spawn_positions: Vec<Position> = deathmatch
+        .spawn_positions
+        .iter()
+        .filter(|p| {
+            !blocked_positions.contains(*p)
+                && !danger_positions.contains(*p)
+                && !query2.iter().any(|(pp, _)| pp == *p)
+        })
+        .copied()
+        .collect();
+
+    for (entity, penguin, team_id, mut respawning) in query.iter_mut() {
+        respawning.timer.tick(time.delta());
+        if !respawning.timer.finished() {
+            continue;
+        }
+
+        // as far away from the enemies as possible
+        let distance_to_enemies = |position: &Position| {
+            query2
+                .iter()
+                .filter(|(_, tid)| *tid != team_id)
+                .map(|(p, _)| (p.y - position.y).abs() + (p.x - position.x).abs())
+                .min()
+                .unwrap_or(isize::MAX)
+        };
+        if let Some(index) = spawn_positions
+            .iter()
+            .position_max_by_key(|p| distance_to_enemies(p))
+        {
+            let position = spawn_positions.remove(index);
+            println!("player respawned: {:?}", position);
+            commands.entity(entity).despawn();
+            let player = spawn_battle_mode_player(
+                &mut commands,
+                &game_textures,
+                *penguin,
+                respawning.penguin_control_type,
+                *team_id,
+                position,
+                &game_rules,
+            );
+            commands
+                .entity(player)
+                .insert(Immortal::new(game_rules.immortality_secs));
+        }
+    }
+}
+
+pub fn finish_deathmatch_round(
+    (time, game_timer): (Res<Time>, Res<GameTimer>),
+    (game_rules, mut wall_of_death, mut deathmatch): (
+        Res<GameRules>,
+        ResMut<WallOfDeath>,
+        ResMut<Deathmatch>,
+    ),
+    (mut battle_mode_context, mut match_stats): (
+        ResMut<BattleModeContext>,
+        Option<ResMut<MatchStats>>,
+    ),
+    query: Query<&TeamID, With<Player>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let leaders = deathmatch.leaders(&battle_mode_context.players);
+
+    let round_outcome = match deathmatch.overtime {
+        None => {
+            if !game_timer.0.finished() {
+                None
+            } else if leaders.len() == 1 {
+                Some(RoundOutcome::Winner(leaders[0]))
+            } else {
+                println!("Overtime!");
+                deathmatch.overtime = Some(Timer::from_seconds(
+                    DEATHMATCH_OVERTIME_SECS,
+                    TimerMode::Once,
+                ));
+                // no more respawns, the tied teams fight it out while the wall of death closes in
+                *wall_of_death = WallOfDeath::Dormant(
+                    Timer::from_seconds(0.0, TimerMode::Once),
+                    game_rules.sudden_death,
+                );
+                None
+            }
+        }
+        Some(ref mut overtime) => {
+            overtime.tick(time.delta());
+
+            let surviving_leaders: Vec<TeamID> = leaders
+                .iter()
+                .filter(|t| query.iter().any(|tid| tid == *t))
+                .copied()
+                .collect();
+            if leaders.len() == 1 {
+                Some(RoundOutcome::Winner(leaders[0]))
+            } else if surviving_leaders.len() == 1 {
+                Some(RoundOutcome::Winner(surviving_leaders[0]))
+            } else if surviving_leaders.is_empty() || overtime.finished() {
+                Some(RoundOutcome::Tie)
+            } else {
+                None
+            }
+        }
+    };
+
+    if let Some(round_outcome) = round_outcome {
+        battle_mode_context.round_outcome = Some(round_outcome);
+        if let Some(ref mut match_stats) = match_stats {
+            match_stats.rounds += 1;
+        }
+        next_state.set(AppState::BattleModeManager);
+    }
+}
+
+pub fn setup_deathmatch_scoreboard(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    hud_colors: Res<HUDColors>,
+    query: Query<(Entity, &PenguinPortrait)>,
+) {
+    for (entity, PenguinPortrait(penguin)) in query.iter() {
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "0",
+                        TextStyle {
+                            font: fonts.mono.clone(),
+                            font_size: 2.0 * PIXEL_SCALE as f32,
+                            color: COLORS[15].into(),
+                        },
+                    ),
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(0.0),
+                        top: Val::Px(0.0),
+                        ..Default::default()
+                    },
+                    background_color: hud_colors.black_color.into(),
+                    ..Default::default()
+                },
+                UIComponent,
+                DeathmatchScoreDisplay(*penguin),
+            ));
+        });
+    }
+}
+
+pub fn deathmatch_scoreboard_update(
+    deathmatch: Res<Deathmatch>,
+    mut query: Query<(&DeathmatchScoreDisplay, &mut Text)>,
+) {
+    for (DeathmatchScoreDisplay(penguin), mut text) in query.iter_mut() {
+        let score = deathmatch.scores.get(penguin).copied().unwrap_or(0);
+        text.sections[0].value = score.to_string();
+    }
+}
+
 pub fn setup_leaderboard_display(
     mut commands: Commands,
     game_textures: Res<GameTextures>,
-    leaderboard_textures: Res<LeaderboardTextures>,
-    fonts: Res<Fonts>,
+    (leaderboard_textures, fonts): (Res<LeaderboardTextures>, Res<Fonts>),
     battle_mode_context: Res<BattleModeContext>,
+    mut game_rng: ResMut<GameRng>,
     primary_query: Query<&Window, With<PrimaryWindow>>,
     query: Query<Entity, With<UIRoot>>,
 ) {
@@ -326,88 +978,65 @@ pub fn setup_leaderboard_display(
                     UIComponent,
                 ))
                 .with_children(|parent| {
-                    // spawn border
-                    let mut spawn_color = |y: usize, x: usize| {
-                        parent.spawn((
-                            NodeBundle {
-                                style: Style {
-                                    position_type: PositionType::Absolute,
-                                    left: Val::Px((x * PIXEL_SCALE) as f32),
-                                    top: Val::Px((y * PIXEL_SCALE) as f32),
-                                    width: Val::Px(PIXEL_SCALE as f32),
-                                    height: Val::Px(PIXEL_SCALE as f32),
-                                    ..Default::default()
-                                },
-                                background_color: (*COLORS
-                                    .iter()
-                                    .choose(&mut rand::thread_rng())
-                                    .unwrap())
-                                .into(),
-                                ..Default::default()
-                            },
-                            UIComponent,
-                        ));
-                    };
+                    spawn_color_border(parent, window, &mut game_rng.cosmetic);
 
-                    let height = window.height() as usize / PIXEL_SCALE;
-                    let width = window.width() as usize / PIXEL_SCALE;
-                    for y in 0..height {
-                        spawn_color(y, 0);
-                        spawn_color(y, 1);
-                        spawn_color(y, width - 2);
-                        spawn_color(y, width - 1);
-                    }
-                    for x in 2..width - 2 {
-                        spawn_color(0, x);
-                        spawn_color(1, x);
-                        spawn_color(height - 2, x);
-                        spawn_color(height - 1, x);
-                    }
-
-                    for (penguin, score) in &battle_mode_context.leaderboard.scores {
-                        // spawn penguin portrait
-                        parent
-                            .spawn((
-                                NodeBundle {
-                                    style: Style {
-                                        position_type: PositionType::Absolute,
-                                        left: Val::Px(4.0 * PIXEL_SCALE as f32),
-                                        top: Val::Px(((6 + penguin.0 * 12) * PIXEL_SCALE) as f32),
-                                        width: Val::Px(TILE_WIDTH as f32),
-                                        height: Val::Px(TILE_HEIGHT as f32),
-                                        ..Default::default()
-                                    },
-                                    background_color: COLORS[2].into(),
-                                    ..Default::default()
-                                },
-                                UIComponent,
-                            ))
-                            .with_children(|parent| {
-                                parent.spawn((
-                                    ImageBundle {
+                    for (row, (team_id, score)) in battle_mode_context
+                        .leaderboard
+                        .scores
+                        .iter()
+                        .sorted_by_key(|(t, _)| **t)
+                        .enumerate()
+                    {
+                        // spawn penguin portraits, one for each team member
+                        let team_members: Vec<Penguin> =
+                            battle_mode_context.team_members(*team_id).collect();
+                        for (i, penguin) in team_members.iter().enumerate() {
+                            parent
+                                .spawn((
+                                    NodeBundle {
                                         style: Style {
-                                            width: Val::Percent(100.0),
-                                            height: Val::Percent(100.0),
+                                            position_type: PositionType::Absolute,
+                                            left: Val::Px(((4 + i * 7) * PIXEL_SCALE) as f32),
+                                            top: Val::Px(((6 + row * 12) * PIXEL_SCALE) as f32),
+                                            width: Val::Px(TILE_WIDTH as f32),
+                                            height: Val::Px(TILE_HEIGHT as f32),
                                             ..Default::default()
                                         },
-                                        image: game_textures
-                                            .get_penguin_texture(*penguin)
-                                            .clone()
-                                            .into(),
+                                        background_color: COLORS[2].into(),
                                         ..Default::default()
                                     },
                                     UIComponent,
-                                ));
-                            });
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        ImageBundle {
+                                            style: Style {
+                                                width: Val::Percent(100.0),
+                                                height: Val::Percent(100.0),
+                                                ..Default::default()
+                                            },
+                                            image: game_textures
+                                                .get_penguin_texture(*penguin)
+                                                .clone()
+                                                .into(),
+                                            ..Default::default()
+                                        },
+                                        UIComponent,
+                                    ));
+                                });
+                        }
+                        let trophies_left = 8 + team_members.len() * 7;
 
-                        // spawn penguin trophies
+                        // spawn team trophies
                         for i in 0..*score {
                             parent.spawn((
                                 ImageBundle {
                                     style: Style {
                                         position_type: PositionType::Absolute,
-                                        top: Val::Px(((7 + penguin.0 * 12) * PIXEL_SCALE) as f32),
-                                        left: Val::Px(((15 + i * 9) * PIXEL_SCALE) as f32),
+                                        top: Val::Px(((7 + row * 12) * PIXEL_SCALE) as f32),
+                                        left: Val::Px(
+                                            ((trophies_left + i * 9) * PIXEL_SCALE) as f32,
+                                        ),
                                         width: Val::Px(5.0 * PIXEL_SCALE as f32),
                                         height: Val::Px(7.0 * PIXEL_SCALE as f32),
                                         ..Default::default()
@@ -419,10 +1048,8 @@ pub fn setup_leaderboard_display(
                             ));
                         }
 
-                        if let RoundOutcome::Winner(round_winner_penguin) =
-                            battle_mode_context.round_outcome.unwrap()
-                        {
-                            if *penguin == round_winner_penguin {
+                        if let Some(ref round_outcome) = battle_mode_context.round_outcome {
+                            if round_outcome.winners().contains(team_id) {
                                 let mut place_text = |y, x, str: &str, c: usize| {
                                     parent.spawn((
                                         TextBundle {
@@ -446,9 +1073,10 @@ pub fn setup_leaderboard_display(
                                     ));
                                 };
 
-                                place_text(6 + penguin.0 * 12, 15 + (*score - 1) * 9 - 2, "*", 15);
-                                place_text(8 + penguin.0 * 12, 15 + (*score - 1) * 9 + 6, "*", 15);
-                                place_text(10 + penguin.0 * 12, 15 + (*score - 1) * 9 - 1, "*", 15);
+                                let x = trophies_left + (*score - 1) * 9;
+                                place_text(6 + row * 12, x - 2, "*", 15);
+                                place_text(8 + row * 12, x + 6, "*", 15);
+                                place_text(10 + row * 12, x - 1, "*", 15);
                             }
                         }
                     }
@@ -479,6 +1107,211 @@ pub fn leaderboard_display_update(
     }
 }
 
+pub fn record_battle_results(
+    mut persistent_battle_records: ResMut<PersistentBattleRecords>,
+    battle_mode_context: Res<BattleModeContext>,
+    match_stats: Res<MatchStats>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+) {
+    // replays were already recorded when they were played
+    if replay_playback.is_some() {
+        return;
+    }
+
+    let leaderboard = &battle_mode_context.leaderboard;
+    let bot_difficulty = battle_mode_context
+        .players
+        .iter()
+        .find_map(|(_, pct, _)| match pct {
+            PenguinControlType::Bot(bot_difficulty) => Some(*bot_difficulty),
+            PenguinControlType::Human(_) => None,
+        });
+    for (_, penguin_control_type, team_id) in battle_mode_context.players.iter() {
+        if let PenguinControlType::Human(player) = penguin_control_type {
+            let rounds_won = leaderboard.scores[team_id];
+            persistent_battle_records.add_tournament(
+                *player,
+                rounds_won == leaderboard.winning_score,
+                rounds_won,
+                match_stats.rounds,
+                bot_difficulty,
+            );
+        }
+    }
+}
+
+pub fn setup_match_stats_display(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    fonts: Res<Fonts>,
+    match_stats: Res<MatchStats>,
+    mut game_rng: ResMut<GameRng>,
+    primary_query: Query<&Window, With<PrimaryWindow>>,
+    query: Query<Entity, With<UIRoot>>,
+) {
+    let mut match_stats_display_box = None;
+    let mut export_status_text = None;
+    let window = primary_query.get_single().unwrap();
+    let height = window.height() as usize / PIXEL_SCALE;
+
+    commands.entity(query.single()).with_children(|parent| {
+        match_stats_display_box = Some(
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(0.0),
+                            top: Val::Px(0.0),
+                            width: Val::Px(window.width()),
+                            height: Val::Px(window.height()),
+                            ..Default::default()
+                        },
+                        background_color: COLORS[0].into(),
+                        ..Default::default()
+                    },
+                    UIComponent,
+                ))
+                .with_children(|parent| {
+                    spawn_color_border(parent, window, &mut game_rng.cosmetic);
+
+                    let mut place_text = |y: usize, x: usize, str: &str, c: usize| {
+                        parent
+                            .spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        str.to_string(),
+                                        TextStyle {
+                                            font: fonts.mono.clone(),
+                                            font_size: 2.0 * PIXEL_SCALE as f32,
+                                            color: COLORS[c].into(),
+                                        },
+                                    ),
+                                    style: Style {
+                                        position_type: PositionType::Absolute,
+                                        top: Val::Px((y * PIXEL_SCALE) as f32),
+                                        left: Val::Px((x * PIXEL_SCALE) as f32),
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                },
+                                UIComponent,
+                            ))
+                            .id()
+                    };
+
+                    place_text(4, 4, &format!("ROUNDS: {}", match_stats.rounds), 15);
+                    for (i, header) in [
+                        "KILLS", "DEATHS", "SELF", "BOMBS", "WALLS", "ITEMS", "ALIVE",
+                    ]
+                    .iter()
+                    .enumerate()
+                    {
+                        place_text(8, 10 + i * 7, header, 14);
+                    }
+
+                    for (row, stats) in match_stats.penguins.iter().enumerate() {
+                        let top = 11 + row * 5;
+                        let time_alive = stats.time_alive.as_secs() as usize;
+                        let values = [
+                            stats.kills.to_string(),
+                            stats.deaths.to_string(),
+                            stats.self_kills.to_string(),
+                            stats.bombs_dropped.to_string(),
+                            stats.walls_destroyed.to_string(),
+                            stats.items_collected.to_string(),
+                            format_hud_time(time_alive),
+                        ];
+                        for (i, value) in values.iter().enumerate() {
+                            place_text(top + 1, 10 + i * 7, value, 15);
+                        }
+                    }
+
+                    place_text(height - 5, 4, "ENTER - CONTINUE   F - EXPORT", 15);
+                    export_status_text = Some(place_text(height - 8, 4, "", 14));
+
+                    // spawn penguin portraits
+                    for (row, stats) in match_stats.penguins.iter().enumerate() {
+                        parent
+                            .spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        position_type: PositionType::Absolute,
+                                        left: Val::Px((4 * PIXEL_SCALE) as f32),
+                                        top: Val::Px(((11 + row * 5) * PIXEL_SCALE) as f32),
+                                        width: Val::Px((TILE_WIDTH / 2) as f32),
+                                        height: Val::Px((TILE_HEIGHT / 2) as f32),
+                                        ..Default::default()
+                                    },
+                                    background_color: COLORS[2].into(),
+                                    ..Default::default()
+                                },
+                                UIComponent,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    ImageBundle {
+                                        style: Style {
+                                            width: Val::Percent(100.0),
+                                            height: Val::Percent(100.0),
+                                            ..Default::default()
+                                        },
+                                        image: game_textures
+                                            .get_penguin_texture(Penguin(stats.penguin))
+                                            .clone()
+                                            .into(),
+                                        ..Default::default()
+                                    },
+                                    UIComponent,
+                                ));
+                            });
+                    }
+                })
+                .id(),
+        );
+    });
+
+    commands.insert_resource(MatchStatsDisplayContext {
+        match_stats_display_box: match_stats_display_box.unwrap(),
+        export_status_text: export_status_text.unwrap(),
+    });
+}
+
+pub fn match_stats_display_update(
+    mut commands: Commands,
+    inputs: Res<InputActionStatusTracker>,
+    match_stats: Res<MatchStats>,
+    match_stats_display_context: Res<MatchStatsDisplayContext>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut query: Query<&mut Text>,
+) {
+    if inputs.is_active(InputAction::F) {
+        let status = match match_stats.export() {
+            Ok(export_file_path) => {
+                println!("Match stats exported to {}.", export_file_path);
+                format!("SAVED TO {}", export_file_path)
+            }
+            Err(e) => {
+                eprintln!("Cannot export match stats. Error: {}", e);
+                "EXPORT FAILED".to_string()
+            }
+        };
+        query
+            .get_mut(match_stats_display_context.export_status_text)
+            .unwrap()
+            .sections[0]
+            .value = status;
+    }
+
+    if inputs.is_active(InputAction::Return) || inputs.is_active(InputAction::Space) {
+        commands
+            .entity(match_stats_display_context.match_stats_display_box)
+            .despawn_recursive();
+        commands.remove_resource::<MatchStatsDisplayContext>();
+        next_state.set(AppState::BattleModeTeardown);
+    }
+}
+
 pub fn teardown(
     mut commands: Commands,
     query: Query<Entity, Without<Window>>,
@@ -486,7 +1319,10 @@ pub fn teardown(
     mut explosion_events: ResMut<Events<ExplosionEvent>>,
     mut burn_events: ResMut<Events<BurnEvent>>,
     mut damage_events: ResMut<Events<DamageEvent>>,
-    mut next_state: ResMut<NextState<AppState>>,
+    (battle_mode_exit_state, mut next_state): (
+        Option<Res<BattleModeExitState>>,
+        ResMut<NextState<AppState>>,
+    ),
 ) {
     // clear entities
     for entity in query.iter() {
@@ -502,13 +1338,19 @@ pub fn teardown(
     // clear resources
     // common
     commands.remove_resource::<GameContext>();
+    commands.remove_resource::<MatchLoot>();
     commands.remove_resource::<GameTimer>();
     commands.remove_resource::<WorldID>();
     commands.remove_resource::<MapSize>();
+    commands.remove_resource::<GameRng>();
+    commands.remove_resource::<GameRules>();
 
     // battle mode
     commands.remove_resource::<BattleModeContext>();
+    commands.remove_resource::<MatchStats>();
     commands.remove_resource::<WallOfDeath>();
+    commands.remove_resource::<Deathmatch>();
+    commands.remove_resource::<BattleModeExitState>();
 
-    next_state.set(AppState::MainMenu);
+    next_state.set(battle_mode_exit_state.map_or(AppState::MainMenu, |s| s.0));
 }
@@ -1,5 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::types::BotDifficulty;
+
 #[derive(Clone, Copy)]
 pub enum PenguinControlType {
     Human(usize),
-    Bot,
+    Bot(BotDifficulty),
+}
+
+// how a round is won
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub enum BattleModeKind {
+    // the last team with penguins on the map
+    #[default]
+    LastStanding,
+    // the team with the most kills once the time runs out, with respawns
+    Deathmatch,
+    // the first team to bring enough enemy flags to its base, or the last one standing
+    CaptureTheFlag,
+    // the first team to hold the hill long enough, or the last one standing
+    KingOfTheHill,
+    // the survivors once the time runs out, unless everyone got infected by then
+    Infection,
+}
+
+impl BattleModeKind {
+    pub const LIST: [Self; 5] = [
+        Self::LastStanding,
+        Self::Deathmatch,
+        Self::CaptureTheFlag,
+        Self::KingOfTheHill,
+        Self::Infection,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::LastStanding => "LAST STANDING",
+            Self::Deathmatch => "DEATHMATCH",
+            Self::CaptureTheFlag => "CAPTURE THE FLAG",
+            Self::KingOfTheHill => "KING OF THE HILL",
+            Self::Infection => "INFECTION",
+        }
+    }
 }
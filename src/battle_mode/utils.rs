@@ -1,30 +1,36 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use rand::{
+    prelude::{IteratorRandom, SliceRandom},
+    RngCore,
+};
 
 use crate::{
+    common::constants::{COLORS, PIXEL_SCALE},
     game::{
         components::*,
         constants::{TILE_HEIGHT, TILE_WIDTH},
+        map_generators::{
+            ClassicMapGenerator, MapGenerationError, MapGenerationParams, MapGenerator,
+            MapGeneratorKind,
+        },
         resources::*,
-        types::{BotDifficulty, Cooldown},
-        utils::{get_x, get_y},
+        rules::GameRules,
+        types::{BotDifficulty, Cooldown, Direction},
+        utils::{get_x, get_y, spawn_map},
     },
     AppState,
 };
 
 use super::{
-    constants::ROUND_START_FREEZE_SECS,
+    constants::{
+        GHOST_ALPHA, GHOST_BOMB_THROWS, GHOST_THROW_COOLDOWN_SECS, ROUND_START_FREEZE_SECS,
+    },
     resources::{BattleModeContext, BattleModeState, FreezeTimer},
     types::PenguinControlType,
 };
 
-pub fn spawn_battle_mode_players(
-    commands: &mut Commands,
-    game_textures: &GameTextures,
-    map_size: MapSize,
-    players: &[(Penguin, PenguinControlType)],
-    bot_difficulty: BotDifficulty,
-) -> Vec<Position> {
-    let possible_player_spawn_positions = [
+pub fn get_battle_mode_player_spawn_positions(map_size: MapSize) -> Vec<Position> {
+    [
         (1, 1),
         (map_size.rows - 2, map_size.columns - 2),
         (1, map_size.columns - 2),
@@ -33,78 +39,159 @@ pub fn spawn_battle_mode_players(
         (map_size.rows - 4, map_size.columns - 6),
         (3, map_size.columns - 6),
         (map_size.rows - 4, 5),
-    ];
-    let mut possible_player_spawn_positions =
-        possible_player_spawn_positions
-            .iter()
-            .map(|(y, x)| Position {
-                y: *y as isize,
-                x: *x as isize,
-            });
-
-    let mut player_spawn_positions = vec![];
-
-    let mut spawn_player = |penguin_tag: Penguin, penguin_control_type: PenguinControlType| {
-        let player_spawn_position = possible_player_spawn_positions.next().unwrap();
-        let base_texture = game_textures.get_penguin_texture(penguin_tag).clone();
-        let immortal_texture = game_textures.immortal_penguin.clone();
-        let mut entity_commands = commands.spawn((
-            SpriteBundle {
-                texture: base_texture.clone(),
-                transform: Transform::from_xyz(
-                    get_x(player_spawn_position.x),
-                    get_y(player_spawn_position.y),
-                    50.0,
-                ),
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
-                    ..Default::default()
-                },
+    ]
+    .iter()
+    .map(|(y, x)| Position {
+        y: *y as isize,
+        x: *x as isize,
+    })
+    .collect()
+}
+
+pub fn spawn_battle_mode_player(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    penguin_tag: Penguin,
+    penguin_control_type: PenguinControlType,
+    team_id: TeamID,
+    position: Position,
+    game_rules: &GameRules,
+) -> Entity {
+    let base_texture = game_textures.get_penguin_texture(penguin_tag).clone();
+    let immortal_texture = game_textures.immortal_penguin.clone();
+    let mut entity_commands = commands.spawn((
+        SpriteBundle {
+            texture: base_texture.clone(),
+            transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 50.0),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
                 ..Default::default()
             },
-            BaseTexture(base_texture),
-            ImmortalTexture(immortal_texture),
-            Player,
-            penguin_tag,
-            Health {
-                lives: 1,
-                max_health: 1,
-                health: 1,
-            },
-            player_spawn_position,
-            SpawnPosition(player_spawn_position),
-            BombSatchel {
-                bombs_available: 1,
-                bomb_range: 2,
-            },
-            TeamID(penguin_tag.0),
-        ));
-        match penguin_control_type {
-            PenguinControlType::Human(i) => {
-                entity_commands.insert(HumanControlled(i));
-            }
-            PenguinControlType::Bot => {
-                entity_commands.insert((
-                    BotAI {
-                        difficulty: bot_difficulty,
-                    },
-                    MoveCooldown(Cooldown::from_seconds(match bot_difficulty {
-                        BotDifficulty::Easy => 0.3,
-                        BotDifficulty::Medium => 0.25,
-                        BotDifficulty::Hard => 0.2,
-                    })),
-                ));
-            }
+            ..Default::default()
+        },
+        BaseTexture(base_texture),
+        ImmortalTexture(immortal_texture),
+        Player,
+        penguin_tag,
+        Health {
+            lives: 1,
+            max_health: 1,
+            health: 1,
+        },
+        position,
+        SpawnPosition(position),
+        BombSatchel {
+            bombs_available: game_rules.starting_bombs,
+            bomb_range: game_rules.starting_bomb_range,
+        },
+        team_id,
+    ));
+    insert_penguin_control(&mut entity_commands, penguin_control_type);
+
+    entity_commands.id()
+}
+
+fn insert_penguin_control(
+    entity_commands: &mut EntityCommands,
+    penguin_control_type: PenguinControlType,
+) {
+    match penguin_control_type {
+        PenguinControlType::Human(i) => {
+            entity_commands.insert(HumanControlled(i));
+        }
+        PenguinControlType::Bot(bot_difficulty) => {
+            entity_commands.insert((
+                BotAI {
+                    difficulty: bot_difficulty,
+                },
+                MoveCooldown(Cooldown::from_seconds(match bot_difficulty {
+                    BotDifficulty::Easy => 0.3,
+                    BotDifficulty::Medium => 0.25,
+                    BotDifficulty::Hard => 0.2,
+                })),
+            ));
         }
+    }
+}
 
-        player_spawn_positions.push(player_spawn_position);
-    };
+pub fn spawn_battle_mode_players(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    possible_player_spawn_positions: &[Position],
+    players: &[(Penguin, PenguinControlType, TeamID)],
+    game_rules: &GameRules,
+) -> Vec<Position> {
+    players
+        .iter()
+        .zip(possible_player_spawn_positions.iter().copied())
+        .map(|((penguin_tag, penguin_control_type, team_id), position)| {
+            spawn_battle_mode_player(
+                commands,
+                game_textures,
+                *penguin_tag,
+                *penguin_control_type,
+                *team_id,
+                position,
+                game_rules,
+            );
+            position
+        })
+        .collect()
+}
 
-    for (penguin_tag, penguin_control_type) in players {
-        spawn_player(*penguin_tag, *penguin_control_type);
+// the tiles of the outer ring in clockwise order, starting from the top left corner
+pub fn get_ghost_ring_positions(map_size: MapSize) -> Vec<Position> {
+    let rows = map_size.rows as isize;
+    let columns = map_size.columns as isize;
+    (0..columns - 1)
+        .map(|x| Position { y: 0, x })
+        .chain((0..rows - 1).map(|y| Position { y, x: columns - 1 }))
+        .chain((1..columns).rev().map(|x| Position { y: rows - 1, x }))
+        .chain((1..rows).rev().map(|y| Position { y, x: 0 }))
+        .collect()
+}
+
+// the way a ghost on the outer ring throws its bombs, there's no way into the map from the corners
+pub fn get_ghost_throw_direction(position: Position, map_size: MapSize) -> Option<Direction> {
+    let on_top_or_bottom = position.y == 0 || position.y == map_size.rows as isize - 1;
+    let on_left_or_right = position.x == 0 || position.x == map_size.columns as isize - 1;
+    match (on_top_or_bottom, on_left_or_right) {
+        (true, false) if position.y == 0 => Some(Direction::Down),
+        (true, false) => Some(Direction::Up),
+        (false, true) if position.x == 0 => Some(Direction::Right),
+        (false, true) => Some(Direction::Left),
+        _ => None,
     }
+}
 
-    player_spawn_positions
+pub fn spawn_ghost(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    penguin_tag: Penguin,
+    penguin_control_type: PenguinControlType,
+    team_id: TeamID,
+    position: Position,
+) {
+    let mut entity_commands = commands.spawn((
+        SpriteBundle {
+            texture: game_textures.get_penguin_texture(penguin_tag).clone(),
+            transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 50.0),
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Ghost {
+            throws_left: GHOST_BOMB_THROWS,
+            throw_cooldown: Cooldown::from_seconds(GHOST_THROW_COOLDOWN_SECS),
+        },
+        penguin_tag,
+        position,
+        team_id,
+    ));
+    insert_penguin_control(&mut entity_commands, penguin_control_type);
 }
 
 pub fn get_battle_mode_map_size_fill(player_count: usize) -> (MapSize, f32) {
@@ -127,6 +214,80 @@ pub fn get_battle_mode_map_size_fill(player_count: usize) -> (MapSize, f32) {
     }
 }
 
+// falls back to the classic layout if the chosen generator can't make the map
+pub fn spawn_battle_mode_map(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    map_generator_kind: Option<MapGeneratorKind>,
+    map_size: MapSize,
+    percent_of_passable_positions_to_fill: f32,
+    player_spawn_positions: &[Position],
+    rng: &mut dyn RngCore,
+) -> Result<Vec<Vec<Entity>>, MapGenerationError> {
+    let map_generator_kind =
+        map_generator_kind.unwrap_or_else(|| *MapGeneratorKind::LIST.choose(rng).unwrap());
+
+    match spawn_map_with_lowered_fill(
+        commands,
+        game_textures,
+        map_generator_kind.map_generator().as_ref(),
+        map_size,
+        percent_of_passable_positions_to_fill,
+        player_spawn_positions,
+        rng,
+    ) {
+        Ok(wall_entity_reveal_groups) => Ok(wall_entity_reveal_groups),
+        Err(e) => {
+            eprintln!(
+                "Cannot generate a {} map, using the classic one. Error: {}",
+                map_generator_kind.name().to_lowercase(),
+                e
+            );
+            spawn_map_with_lowered_fill(
+                commands,
+                game_textures,
+                &ClassicMapGenerator::default(),
+                map_size,
+                percent_of_passable_positions_to_fill,
+                player_spawn_positions,
+                rng,
+            )
+        }
+    }
+}
+
+// layouts without room for the whole fill (e.g. caves, mazes) get as much of it as they can take
+fn spawn_map_with_lowered_fill(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    map_generator: &dyn MapGenerator,
+    map_size: MapSize,
+    mut percent_of_passable_positions_to_fill: f32,
+    player_spawn_positions: &[Position],
+    rng: &mut dyn RngCore,
+) -> Result<Vec<Vec<Entity>>, MapGenerationError> {
+    loop {
+        match spawn_map(
+            commands,
+            game_textures,
+            map_generator,
+            &MapGenerationParams {
+                map_size,
+                percent_of_passable_positions_to_fill,
+                penguin_spawn_positions: player_spawn_positions,
+                mob_spawn_positions: &[],
+                spawn_exit: false,
+            },
+            rng,
+        ) {
+            Err(MapGenerationError::NotEnoughRoom { available, needed }) => {
+                percent_of_passable_positions_to_fill *= available as f32 / needed as f32;
+            }
+            result => return result,
+        }
+    }
+}
+
 pub fn start_round(
     mut battle_mode_context: ResMut<BattleModeContext>,
     mut commands: Commands,
@@ -139,3 +300,71 @@ pub fn start_round(
     )));
     next_state.set(AppState::RoundStartFreeze);
 }
+
+// a two pixel wide border of random colors around the window
+pub fn spawn_color_border(parent: &mut ChildBuilder, window: &Window, rng: &mut dyn RngCore) {
+    let mut spawn_color = |y: usize, x: usize| {
+        parent.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px((x * PIXEL_SCALE) as f32),
+                    top: Val::Px((y * PIXEL_SCALE) as f32),
+                    width: Val::Px(PIXEL_SCALE as f32),
+                    height: Val::Px(PIXEL_SCALE as f32),
+                    ..Default::default()
+                },
+                background_color: (*COLORS.iter().choose(&mut *rng).unwrap()).into(),
+                ..Default::default()
+            },
+            UIComponent,
+        ));
+    };
+
+    let height = window.height() as usize / PIXEL_SCALE;
+    let width = window.width() as usize / PIXEL_SCALE;
+    for y in 0..height {
+        spawn_color(y, 0);
+        spawn_color(y, 1);
+        spawn_color(y, width - 2);
+        spawn_color(y, width - 1);
+    }
+    for x in 2..width - 2 {
+        spawn_color(0, x);
+        spawn_color(1, x);
+        spawn_color(height - 2, x);
+        spawn_color(height - 1, x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ghost_ring() {
+        let map_size = MapSize {
+            rows: 11,
+            columns: 15,
+        };
+        let ring_positions = get_ghost_ring_positions(map_size);
+        assert_eq!(ring_positions.len(), 2 * (11 + 15) - 4);
+        assert!(ring_positions
+            .iter()
+            .zip(ring_positions.iter().cycle().skip(1))
+            .all(|(a, b)| (a.y - b.y).abs() + (a.x - b.x).abs() == 1));
+
+        assert_eq!(
+            get_ghost_throw_direction(Position { y: 0, x: 0 }, map_size),
+            None
+        );
+        assert_eq!(
+            get_ghost_throw_direction(Position { y: 10, x: 3 }, map_size),
+            Some(Direction::Up)
+        );
+        assert_eq!(
+            get_ghost_throw_direction(Position { y: 4, x: 14 }, map_size),
+            Some(Direction::Left)
+        );
+    }
+}
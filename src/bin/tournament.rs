@@ -0,0 +1,3 @@
+fn main() {
+    ascii_bomb_ecs_lib::run_tournament();
+}
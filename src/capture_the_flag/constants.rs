@@ -0,0 +1,3 @@
+pub const CAPTURES_TO_WIN: usize = 3;
+// the colors of the team flags, kept apart from the flag status colors of the HUD
+pub const FLAG_COLORS: [usize; 8] = [9, 10, 11, 13, 1, 2, 3, 5];
@@ -0,0 +1,50 @@
+mod components;
+mod constants;
+mod resources;
+mod systems;
+mod utils;
+
+use bevy::prelude::*;
+
+use crate::{battle_mode::finish_round, game::Set, AppState};
+
+use self::{resources::CaptureTheFlag, systems::*};
+
+/*
+Capture the flag plays along with battle mode rounds started as capture the flag, it doesn't get
+app states of its own. Everything around a round (the map and player setup, the leaderboard between
+rounds, the match stats and the teardown) is the same for every battle mode kind, so it is left to
+`battle_mode_manager`, and only who won the round is decided here, through `RoundOutcome`.
+
+What happens within a round goes through `CaptureTheFlagManagerState`, kept in the `CaptureTheFlag`
+resource that is made anew as each round starts:
+- `Setup` plants the flags at the bases and adds the flag status to the HUD, on the first frame of
+  the round
+- `InPlay` brings back the flags lost to the wall of death, while `flag_update` moves and scores
+  them
+- `Finished` stops all of the above once the round is decided, by the captures or by the clock,
+  and hands over to `BattleModeManager`
+*/
+pub struct CaptureTheFlagPlugin;
+
+impl Plugin for CaptureTheFlagPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::RoundStartFreeze), setup_capture_the_flag)
+            .add_systems(
+                Update,
+                (
+                    capture_the_flag_manager,
+                    apply_deferred,
+                    flag_update,
+                    flag_status_display_update,
+                )
+                    .chain()
+                    .after(Set::TimeUpdate)
+                    .after(Set::PlayerDeathEvent)
+                    .before(finish_round)
+                    .run_if(resource_exists::<CaptureTheFlag>())
+                    .run_if(in_state(AppState::BattleModeInGame)),
+            )
+            .add_systems(OnEnter(AppState::BattleModeTeardown), teardown);
+    }
+}
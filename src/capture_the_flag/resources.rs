@@ -0,0 +1,59 @@
+use bevy::{ecs as bevy_ecs, prelude::*, utils::HashMap};
+
+use crate::game::components::{Position, TeamID};
+
+pub enum CaptureTheFlagManagerState {
+    // the flags are yet to be planted at the bases
+    Setup,
+    InPlay,
+    // the round has been decided
+    Finished,
+}
+
+#[derive(Resource)]
+pub struct CaptureTheFlag {
+    pub manager_state: CaptureTheFlagManagerState,
+    pub bases: HashMap<TeamID, Position>,
+    pub captures: HashMap<TeamID, usize>,
+}
+
+impl CaptureTheFlag {
+    pub fn new(bases: HashMap<TeamID, Position>) -> Self {
+        Self {
+            manager_state: CaptureTheFlagManagerState::Setup,
+            captures: bases.keys().map(|t| (*t, 0)).collect(),
+            bases,
+        }
+    }
+
+    // the team with the most captures, unless it's a tie
+    pub fn leader(&self) -> Option<TeamID> {
+        let most_captures = self.captures.values().max()?;
+        let mut leaders = self.captures.iter().filter(|(_, c)| *c == most_captures);
+        match (leaders.next(), leaders.next()) {
+            (Some((team_id, _)), None) => Some(*team_id),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_leader() {
+        let mut capture_the_flag = CaptureTheFlag::new(
+            [
+                (TeamID(0), Position { y: 1, x: 1 }),
+                (TeamID(1), Position { y: 9, x: 13 }),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        assert!(capture_the_flag.leader().is_none());
+
+        *capture_the_flag.captures.get_mut(&TeamID(1)).unwrap() += 1;
+        assert!(capture_the_flag.leader() == Some(TeamID(1)));
+    }
+}
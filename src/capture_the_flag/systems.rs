@@ -0,0 +1,258 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    battle_mode::{BattleModeContext, BattleModeKind, RoundOutcome},
+    common::{
+        constants::{COLORS, PIXEL_SCALE},
+        resources::Fonts,
+    },
+    game::{
+        components::*,
+        resources::{GameTimer, HUDColors},
+        stats::MatchStats,
+    },
+    AppState,
+};
+
+use super::{
+    components::FlagStatusDisplay,
+    constants::CAPTURES_TO_WIN,
+    resources::{CaptureTheFlag, CaptureTheFlagManagerState},
+    utils::*,
+};
+
+pub fn setup_capture_the_flag(
+    mut commands: Commands,
+    battle_mode_context: Res<BattleModeContext>,
+    query: Query<(&Penguin, &TeamID, &Position), With<Player>>,
+) {
+    if battle_mode_context.kind != BattleModeKind::CaptureTheFlag {
+        return;
+    }
+
+    // each team has its base where its first penguin spawned
+    let mut bases: HashMap<TeamID, (Penguin, Position)> = HashMap::default();
+    for (penguin, team_id, position) in query.iter() {
+        match bases.get(team_id) {
+            Some((p, _)) if p.0 < penguin.0 => (),
+            _ => {
+                bases.insert(*team_id, (*penguin, *position));
+            }
+        }
+    }
+
+    commands.insert_resource(CaptureTheFlag::new(
+        bases.into_iter().map(|(t, (_, p))| (t, p)).collect(),
+    ));
+}
+
+pub fn capture_the_flag_manager(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    hud_colors: Res<HUDColors>,
+    mut capture_the_flag: ResMut<CaptureTheFlag>,
+    query: Query<&Flag>,
+    query2: Query<&Position, With<Wall>>,
+    query3: Query<(Entity, &PenguinPortrait)>,
+) {
+    match capture_the_flag.manager_state {
+        CaptureTheFlagManagerState::Setup => {
+            for (team_id, base) in capture_the_flag.bases.iter() {
+                spawn_flag(&mut commands, &fonts, *team_id, *base);
+            }
+
+            for (entity, PenguinPortrait(penguin)) in query3.iter() {
+                commands.entity(entity).with_children(|parent| {
+                    let text_style = TextStyle {
+                        font: fonts.mono.clone(),
+                        font_size: 2.0 * PIXEL_SCALE as f32,
+                        color: COLORS[15].into(),
+                    };
+                    parent.spawn((
+                        TextBundle {
+                            text: Text::from_sections([
+                                TextSection::new("0", text_style.clone()),
+                                TextSection::new("", text_style),
+                            ]),
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(0.0),
+                                top: Val::Px(0.0),
+                                ..Default::default()
+                            },
+                            background_color: hud_colors.black_color.into(),
+                            ..Default::default()
+                        },
+                        UIComponent,
+                        FlagStatusDisplay(*penguin),
+                    ));
+                });
+            }
+
+            capture_the_flag.manager_state = CaptureTheFlagManagerState::InPlay;
+        }
+        CaptureTheFlagManagerState::InPlay => {
+            // flags swallowed by the wall of death are planted back at their bases, unless those
+            // were walled in as well
+            let wall_positions: HashSet<Position> = query2.iter().copied().collect();
+            for (team_id, base) in capture_the_flag.bases.iter() {
+                if !query.iter().any(|f| f.team_id == *team_id) && !wall_positions.contains(base) {
+                    spawn_flag(&mut commands, &fonts, *team_id, *base);
+                }
+            }
+        }
+        CaptureTheFlagManagerState::Finished => (),
+    }
+}
+
+pub fn flag_update(
+    mut capture_the_flag: ResMut<CaptureTheFlag>,
+    (game_timer, mut battle_mode_context): (Res<GameTimer>, ResMut<BattleModeContext>),
+    mut match_stats: Option<ResMut<MatchStats>>,
+    mut query: Query<(&mut Flag, &mut Position, &mut Transform), Without<Player>>,
+    query2: Query<(Entity, &Position, &TeamID), With<Player>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !matches!(
+        capture_the_flag.manager_state,
+        CaptureTheFlagManagerState::InPlay
+    ) {
+        return;
+    }
+
+    let players: Vec<(Entity, Position, TeamID)> =
+        query2.iter().map(|(e, p, t)| (e, *p, *t)).collect();
+
+    // the flags follow their carriers and stay where the carriers fell
+    for (mut flag, mut position, mut transform) in query.iter_mut() {
+        if let Some(carrier) = flag.carrier {
+            match players.iter().find(|(e, _, _)| *e == carrier) {
+                Some((_, p, _)) => {
+                    *position = *p;
+                    transform.translation = get_flag_translation(*p);
+                }
+                None => flag.carrier = None,
+            }
+        }
+    }
+
+    let mut carriers: HashSet<Entity> = query.iter().filter_map(|(f, _, _)| f.carrier).collect();
+    for (mut flag, mut position, mut transform) in query.iter_mut() {
+        if flag.carrier.is_some() {
+            continue;
+        }
+
+        for (entity, _, team_id) in players.iter().filter(|(_, p, _)| *p == *position) {
+            if *team_id != flag.team_id {
+                // a penguin can only carry one flag at a time
+                if !carriers.contains(entity) {
+                    println!(
+                        "Team {} took the flag of team {}!",
+                        team_id.0, flag.team_id.0
+                    );
+                    flag.carrier = Some(*entity);
+                    carriers.insert(*entity);
+                    break;
+                }
+            } else if *position != flag.base {
+                println!("Team {} returned its flag!", team_id.0);
+                *position = flag.base;
+                transform.translation = get_flag_translation(flag.base);
+                break;
+            }
+        }
+    }
+
+    let mut winner = None;
+    for (mut flag, mut position, mut transform) in query.iter_mut() {
+        if let Some(carrier) = flag.carrier {
+            let team_id = players.iter().find(|(e, _, _)| *e == carrier).unwrap().2;
+            if capture_the_flag.bases.get(&team_id) == Some(&*position) {
+                println!(
+                    "Team {} captured the flag of team {}!",
+                    team_id.0, flag.team_id.0
+                );
+                flag.carrier = None;
+                *position = flag.base;
+                transform.translation = get_flag_translation(flag.base);
+
+                let captures = capture_the_flag.captures.get_mut(&team_id).unwrap();
+                *captures += 1;
+                if *captures >= CAPTURES_TO_WIN {
+                    winner = Some(team_id);
+                }
+            }
+        }
+    }
+
+    // the most captures win once the time runs out, the last team standing still wins before that
+    if winner.is_none() && game_timer.0.finished() {
+        winner = capture_the_flag.leader();
+    }
+
+    if let Some(team_id) = winner {
+        battle_mode_context.round_outcome = Some(RoundOutcome::Winner(team_id));
+        if let Some(ref mut match_stats) = match_stats {
+            match_stats.rounds += 1;
+        }
+        capture_the_flag.manager_state = CaptureTheFlagManagerState::Finished;
+        next_state.set(AppState::BattleModeManager);
+    }
+}
+
+pub fn flag_status_display_update(
+    capture_the_flag: Res<CaptureTheFlag>,
+    battle_mode_context: Res<BattleModeContext>,
+    query: Query<(&Flag, &Position)>,
+    query2: Query<(Entity, &Penguin), With<Player>>,
+    mut query3: Query<(&FlagStatusDisplay, &mut Text)>,
+) {
+    for (FlagStatusDisplay(penguin), mut text) in query3.iter_mut() {
+        let team_id = match battle_mode_context
+            .players
+            .iter()
+            .find(|(p, _, _)| p.0 == penguin.0)
+        {
+            Some((_, _, team_id)) => *team_id,
+            None => continue,
+        };
+
+        // the captures of the team, colored by where its flag is
+        text.sections[0].value = capture_the_flag
+            .captures
+            .get(&team_id)
+            .copied()
+            .unwrap_or(0)
+            .to_string();
+        text.sections[0].style.color = match query.iter().find(|(f, _)| f.team_id == team_id) {
+            Some((f, _)) if f.carrier.is_some() => COLORS[12],
+            Some((f, p)) if *p != f.base => COLORS[14],
+            Some(_) => COLORS[15],
+            None => COLORS[8],
+        }
+        .into();
+
+        // along with the flag the penguin is carrying
+        let player = query2
+            .iter()
+            .find(|(_, p)| p.0 == penguin.0)
+            .map(|(e, _)| e);
+        match query
+            .iter()
+            .find(|(f, _)| f.carrier.is_some() && f.carrier == player)
+        {
+            Some((f, _)) => {
+                text.sections[1].value = String::from("█");
+                text.sections[1].style.color = get_flag_color(f.team_id);
+            }
+            None => text.sections[1].value.clear(),
+        }
+    }
+}
+
+pub fn teardown(mut commands: Commands) {
+    commands.remove_resource::<CaptureTheFlag>();
+}
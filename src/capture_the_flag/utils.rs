@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{
+        constants::{COLORS, PIXEL_SCALE},
+        resources::Fonts,
+    },
+    game::{
+        components::{Flag, Position, TeamID},
+        constants::{TILE_HEIGHT, TILE_WIDTH},
+        utils::{get_x, get_y},
+    },
+};
+
+use super::constants::FLAG_COLORS;
+
+pub fn get_flag_color(team_id: TeamID) -> Color {
+    COLORS[FLAG_COLORS[team_id.0 % FLAG_COLORS.len()]].into()
+}
+
+// drawn in the upper right corner of the tile, above a penguin carrying it
+pub fn get_flag_translation(position: Position) -> Vec3 {
+    Vec3::new(
+        get_x(position.x) + TILE_WIDTH as f32 / 4.0,
+        get_y(position.y) + TILE_HEIGHT as f32 / 4.0,
+        55.0,
+    )
+}
+
+pub fn spawn_flag(commands: &mut Commands, fonts: &Fonts, team_id: TeamID, base: Position) {
+    let text_style = |color| TextStyle {
+        font: fonts.mono.clone(),
+        font_size: 2.0 * PIXEL_SCALE as f32,
+        color,
+    };
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_sections([
+                TextSection::new("│", text_style(COLORS[7].into())),
+                TextSection::new("█", text_style(get_flag_color(team_id))),
+                TextSection::new("\n│ ", text_style(COLORS[7].into())),
+            ]),
+            transform: Transform::from_translation(get_flag_translation(base)),
+            ..Default::default()
+        },
+        Flag {
+            team_id,
+            base,
+            carrier: None,
+        },
+        base,
+    ));
+}
@@ -19,6 +19,8 @@ impl Plugin for CommonPlugin {
         app.init_resource::<InputActionStatusTracker>()
             .init_resource::<GameOptionStore>()
             .init_resource::<PersistentHighScores>()
+            .init_resource::<PersistentSurvivalHighScores>()
+            .init_resource::<PersistentBattleRecords>()
             .init_resource::<Fonts>()
             .add_systems(Startup, set_volume_based_on_options)
             .add_systems(
@@ -1,4 +1,4 @@
-use std::{cmp::Reverse, fs};
+use std::{cmp::Reverse, collections::BTreeMap, fs};
 
 use bevy::{
     ecs as bevy_ecs,
@@ -7,7 +7,7 @@ use bevy::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::loading::resources::AssetsLoading;
+use crate::{game::types::BotDifficulty, loading::resources::AssetsLoading};
 
 use super::types::InputAction;
 
@@ -134,7 +134,11 @@ impl GameOptionStore {
 
 // high scores
 #[derive(Serialize, Deserialize)]
-pub struct HighScores(pub Vec<(String, usize)>);
+pub struct HighScores(pub Vec<HighScore>);
+
+// name, score and whether the run was continued from a save, which older files do not list
+#[derive(Serialize, Deserialize)]
+pub struct HighScore(pub String, pub usize, #[serde(default)] pub bool);
 
 impl HighScores {
     pub const HIGH_SCORES_MAX_SIZE: usize = 10;
@@ -147,8 +151,8 @@ impl HighScores {
         }
     }
 
-    fn insert_score(&mut self, name: String, score: usize) {
-        self.0.push((name, score));
+    fn insert_score(&mut self, name: String, score: usize, continued: bool) {
+        self.0.push(HighScore(name, score, continued));
         self.0.sort_by_key(|k| Reverse(k.1));
         self.0.truncate(Self::HIGH_SCORES_MAX_SIZE);
     }
@@ -158,11 +162,21 @@ impl HighScores {
 pub struct PersistentHighScores {
     high_scores: HighScores,
     checksum: usize,
+    #[serde(skip)]
+    file_path: &'static str,
 }
 
 impl Default for PersistentHighScores {
     fn default() -> Self {
-        let persistent_high_scores_file_path = std::path::Path::new(Self::SERIALIZED_FILE_PATH);
+        Self::load(Self::SERIALIZED_FILE_PATH)
+    }
+}
+
+impl PersistentHighScores {
+    const SERIALIZED_FILE_PATH: &'static str = "local/high_scores";
+
+    fn load(file_path: &'static str) -> Self {
+        let persistent_high_scores_file_path = std::path::Path::new(file_path);
         let persistent_high_scores = if let Ok(content) =
             fs::read_to_string(persistent_high_scores_file_path)
         {
@@ -186,22 +200,25 @@ impl Default for PersistentHighScores {
         };
 
         match persistent_high_scores {
-            Some(persistent_high_scores) => persistent_high_scores,
+            Some(persistent_high_scores) => Self {
+                file_path,
+                ..persistent_high_scores
+            },
             None => {
-                println!("Missing or invalid high scores file detected, generating a new one.");
+                println!(
+                    "Missing or invalid high scores file detected at {}, generating a new one.",
+                    file_path
+                );
                 let persistent_high_scores = Self {
                     high_scores: HighScores(vec![]),
                     checksum: 0,
+                    file_path,
                 };
                 persistent_high_scores.save();
                 persistent_high_scores
             }
         }
     }
-}
-
-impl PersistentHighScores {
-    const SERIALIZED_FILE_PATH: &'static str = "local/high_scores";
 
     pub fn get_raw_scores(&self) -> &HighScores {
         &self.high_scores
@@ -211,16 +228,16 @@ impl PersistentHighScores {
         self.high_scores.entry_threshold()
     }
 
-    pub fn insert_score(&mut self, name: String, score: usize) {
+    pub fn insert_score(&mut self, name: String, score: usize, continued: bool) {
         if score > self.high_scores.entry_threshold() {
-            self.high_scores.insert_score(name, score);
+            self.high_scores.insert_score(name, score, continued);
             self.checksum = Self::calculate_checksum(&self.high_scores);
             self.save();
         }
     }
 
     fn save(&self) {
-        let high_scores_file_path = std::path::Path::new(Self::SERIALIZED_FILE_PATH);
+        let high_scores_file_path = std::path::Path::new(self.file_path);
         let serialized = serde_json::to_string(self).unwrap();
         match fs::create_dir_all(high_scores_file_path.parent().unwrap()) {
             Ok(()) => fs::write(high_scores_file_path, serialized).unwrap(),
@@ -229,24 +246,245 @@ impl PersistentHighScores {
     }
 
     fn calculate_checksum(high_scores: &HighScores) -> usize {
-        let mut j = 1;
-        let mut sum = 0;
-
+        let mut checksum = Checksum::new();
         for score in &high_scores.0 {
-            for ch in score.0.chars() {
-                sum += j * ch as usize;
-                j += 1;
+            checksum.add_str(&score.0);
+            checksum.add_number(score.1);
+            // left out for one-sitting runs so that older files stay valid
+            if score.2 {
+                checksum.add_str("continued");
             }
+        }
+        checksum.sum
+    }
+}
+
+impl AsMut<PersistentHighScores> for PersistentHighScores {
+    fn as_mut(&mut self) -> &mut PersistentHighScores {
+        self
+    }
+}
+
+// the survival mode keeps a table of its own
+#[derive(Resource, Deref, DerefMut)]
+pub struct PersistentSurvivalHighScores(PersistentHighScores);
+
+impl Default for PersistentSurvivalHighScores {
+    fn default() -> Self {
+        Self(PersistentHighScores::load(Self::SERIALIZED_FILE_PATH))
+    }
+}
+
+impl PersistentSurvivalHighScores {
+    const SERIALIZED_FILE_PATH: &'static str = "local/survival_high_scores";
+}
+
+impl AsMut<PersistentHighScores> for PersistentSurvivalHighScores {
+    fn as_mut(&mut self) -> &mut PersistentHighScores {
+        &mut self.0
+    }
+}
+
+// weighs each character and digit by its position, catches hand edits of the persisted files
+struct Checksum {
+    j: usize,
+    sum: usize,
+}
+
+impl Checksum {
+    fn new() -> Self {
+        Self { j: 1, sum: 0 }
+    }
+
+    fn add_str(&mut self, str: &str) {
+        for ch in str.chars() {
+            self.sum += self.j * ch as usize;
+            self.j += 1;
+        }
+    }
+
+    fn add_number(&mut self, mut val: usize) {
+        while val > 0 {
+            self.sum += self.j * val % 10;
+            self.j += 1;
+            val /= 10;
+        }
+    }
+}
+
+// battle records
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct WinRecord {
+    pub won: usize,
+    pub played: usize,
+}
+
+impl WinRecord {
+    fn add(&mut self, won: bool) {
+        self.played += 1;
+        if won {
+            self.won += 1;
+        }
+    }
+
+    pub fn win_rate(&self) -> Option<f32> {
+        if self.played > 0 {
+            Some(self.won as f32 / self.played as f32)
+        } else {
+            None
+        }
+    }
+}
+
+// the career of a human battle mode player
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct BattleRecord {
+    pub tournaments: WinRecord,
+    pub rounds: WinRecord,
+    // tournaments against bots of a difficulty
+    pub vs_bots: BTreeMap<BotDifficulty, WinRecord>,
+    pub win_streak: usize,
+    pub longest_win_streak: usize,
+}
 
-            let mut val = score.1;
-            while val > 0 {
-                sum += j * val % 10;
-                j += 1;
-                val /= 10;
+impl BattleRecord {
+    pub fn add_tournament(
+        &mut self,
+        won: bool,
+        rounds_won: usize,
+        rounds_played: usize,
+        bot_difficulty: Option<BotDifficulty>,
+    ) {
+        self.tournaments.add(won);
+        self.rounds.won += rounds_won;
+        self.rounds.played += rounds_played;
+        if let Some(bot_difficulty) = bot_difficulty {
+            self.vs_bots.entry(bot_difficulty).or_default().add(won);
+        }
+
+        if won {
+            self.win_streak += 1;
+            self.longest_win_streak = self.longest_win_streak.max(self.win_streak);
+        } else {
+            self.win_streak = 0;
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        [self.tournaments, self.rounds]
+            .iter()
+            .chain(self.vs_bots.values())
+            .all(|r| r.won <= r.played)
+            && self.win_streak <= self.longest_win_streak
+            && self.longest_win_streak <= self.tournaments.won
+            && self.vs_bots.values().map(|r| r.played).sum::<usize>() <= self.tournaments.played
+    }
+}
+
+// one record for each human player slot, i.e. P1 and P2
+#[derive(Serialize, Deserialize)]
+pub struct BattleRecords(pub Vec<BattleRecord>);
+
+impl BattleRecords {
+    pub const MAX_PLAYERS: usize = 2;
+
+    pub fn get(&self, player: usize) -> Option<&BattleRecord> {
+        self.0.get(player)
+    }
+}
+
+#[derive(Serialize, Deserialize, Resource)]
+pub struct PersistentBattleRecords {
+    battle_records: BattleRecords,
+    checksum: usize,
+}
+
+impl Default for PersistentBattleRecords {
+    fn default() -> Self {
+        let persistent_battle_records_file_path = std::path::Path::new(Self::SERIALIZED_FILE_PATH);
+        let persistent_battle_records = fs::read_to_string(persistent_battle_records_file_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<PersistentBattleRecords>(&content).ok())
+            .filter(|pbr| {
+                Self::calculate_checksum(&pbr.battle_records) == pbr.checksum
+                    && pbr.battle_records.0.len() <= BattleRecords::MAX_PLAYERS
+                    && pbr.battle_records.0.iter().all(BattleRecord::is_valid)
+            });
+
+        match persistent_battle_records {
+            Some(persistent_battle_records) => persistent_battle_records,
+            None => {
+                println!("Missing or invalid battle records file detected, generating a new one.");
+                let persistent_battle_records = Self {
+                    battle_records: BattleRecords(vec![]),
+                    checksum: 0,
+                };
+                persistent_battle_records.save();
+                persistent_battle_records
             }
         }
+    }
+}
+
+impl PersistentBattleRecords {
+    const SERIALIZED_FILE_PATH: &'static str = "local/battle_records";
 
-        sum
+    pub fn get_raw_records(&self) -> &BattleRecords {
+        &self.battle_records
+    }
+
+    pub fn add_tournament(
+        &mut self,
+        player: usize,
+        won: bool,
+        rounds_won: usize,
+        rounds_played: usize,
+        bot_difficulty: Option<BotDifficulty>,
+    ) {
+        if player >= BattleRecords::MAX_PLAYERS {
+            return;
+        }
+
+        if self.battle_records.0.len() <= player {
+            self.battle_records
+                .0
+                .resize_with(player + 1, BattleRecord::default);
+        }
+        self.battle_records.0[player].add_tournament(
+            won,
+            rounds_won,
+            rounds_played,
+            bot_difficulty,
+        );
+        self.checksum = Self::calculate_checksum(&self.battle_records);
+        self.save();
+    }
+
+    fn save(&self) {
+        let battle_records_file_path = std::path::Path::new(Self::SERIALIZED_FILE_PATH);
+        let serialized = serde_json::to_string(self).unwrap();
+        match fs::create_dir_all(battle_records_file_path.parent().unwrap()) {
+            Ok(()) => fs::write(battle_records_file_path, serialized).unwrap(),
+            Err(e) => eprintln!("Cannot save battle records file. Error: {}", e),
+        }
+    }
+
+    fn calculate_checksum(battle_records: &BattleRecords) -> usize {
+        let mut checksum = Checksum::new();
+        for battle_record in &battle_records.0 {
+            for win_record in [battle_record.tournaments, battle_record.rounds] {
+                checksum.add_number(win_record.won);
+                checksum.add_number(win_record.played);
+            }
+            for (bot_difficulty, win_record) in &battle_record.vs_bots {
+                checksum.add_number(*bot_difficulty as usize + 1);
+                checksum.add_number(win_record.won);
+                checksum.add_number(win_record.played);
+            }
+            checksum.add_number(battle_record.win_streak);
+            checksum.add_number(battle_record.longest_win_streak);
+        }
+        checksum.sum
     }
 }
 
@@ -259,12 +497,12 @@ mod tests {
         let mut high_scores = HighScores(vec![]);
         assert!(high_scores.0.is_empty());
 
-        high_scores.insert_score(String::from("1"), 1);
+        high_scores.insert_score(String::from("1"), 1, false);
         assert_eq!(high_scores.0.len(), 1);
 
         // if we insert more than allowed...
         for i in 0..2 * HighScores::HIGH_SCORES_MAX_SIZE {
-            high_scores.insert_score(i.to_string(), i);
+            high_scores.insert_score(i.to_string(), i, false);
         }
         // ...we should not go over the allowed size
         assert_eq!(high_scores.0.len(), HighScores::HIGH_SCORES_MAX_SIZE);
@@ -279,9 +517,9 @@ mod tests {
         assert!(high_scores.0.is_empty());
 
         // if alice and bob have the same score but alice got it first...
-        high_scores.insert_score(String::from("calvin"), 10);
-        high_scores.insert_score(String::from("alice"), 1234);
-        high_scores.insert_score(String::from("bob"), 1234);
+        high_scores.insert_score(String::from("calvin"), 10, false);
+        high_scores.insert_score(String::from("alice"), 1234, false);
+        high_scores.insert_score(String::from("bob"), 1234, false);
         // ...then alice should be ahead of bob in the list
         assert_eq!(high_scores.0.len(), 3);
         assert_eq!(high_scores.0[0].0, "alice");
@@ -292,9 +530,9 @@ mod tests {
         assert!(high_scores.0.is_empty());
 
         // if alice and bob have the same score but bob got it first...
-        high_scores.insert_score(String::from("bob"), 1234);
-        high_scores.insert_score(String::from("calvin"), 10000);
-        high_scores.insert_score(String::from("alice"), 1234);
+        high_scores.insert_score(String::from("bob"), 1234, false);
+        high_scores.insert_score(String::from("calvin"), 10000, false);
+        high_scores.insert_score(String::from("alice"), 1234, false);
         // ...then bob should be ahead of alice in the list
         assert_eq!(high_scores.0.len(), 3);
         assert_eq!(high_scores.0[0].0, "calvin");
@@ -311,16 +549,76 @@ mod tests {
         // if we insert one score short of the allowed size...
         let really_high_score = 1000000;
         for _ in 0..HighScores::HIGH_SCORES_MAX_SIZE - 1 {
-            high_scores.insert_score(really_high_score.to_string(), really_high_score);
+            high_scores.insert_score(really_high_score.to_string(), really_high_score, false);
         }
         // ...there should still be room for more
         assert_eq!(high_scores.0.len(), HighScores::HIGH_SCORES_MAX_SIZE - 1);
         assert_eq!(high_scores.entry_threshold(), 0);
 
         // if we insert one more...
-        high_scores.insert_score(really_high_score.to_string(), really_high_score);
+        high_scores.insert_score(really_high_score.to_string(), really_high_score, false);
         // ...the next score will need to be higher than the lowest one
         assert_eq!(high_scores.0.len(), HighScores::HIGH_SCORES_MAX_SIZE);
         assert_eq!(high_scores.entry_threshold(), really_high_score);
     }
+
+    #[test]
+    fn test_high_scores_continued_runs() {
+        // files from before continued runs were marked still load, with the same checksum
+        let high_scores: HighScores = serde_json::from_str(r#"[["alice",1234]]"#).unwrap();
+        assert!(!high_scores.0[0].2);
+        let mut continued_high_scores = HighScores(vec![]);
+        continued_high_scores.insert_score(String::from("alice"), 1234, false);
+        assert_eq!(
+            PersistentHighScores::calculate_checksum(&high_scores),
+            PersistentHighScores::calculate_checksum(&continued_high_scores)
+        );
+
+        // a continued run cannot be passed off as a one-sitting one
+        continued_high_scores.0[0].2 = true;
+        assert_ne!(
+            PersistentHighScores::calculate_checksum(&high_scores),
+            PersistentHighScores::calculate_checksum(&continued_high_scores)
+        );
+    }
+
+    #[test]
+    fn test_battle_record_win_streak() {
+        let mut battle_record = BattleRecord::default();
+        for won in [true, true, false, true] {
+            battle_record.add_tournament(
+                won,
+                if won { 3 } else { 1 },
+                4,
+                Some(BotDifficulty::Hard),
+            );
+        }
+
+        assert_eq!(battle_record.tournaments.won, 3);
+        assert_eq!(battle_record.tournaments.played, 4);
+        assert_eq!(battle_record.rounds.won, 10);
+        assert_eq!(battle_record.win_streak, 1);
+        assert_eq!(battle_record.longest_win_streak, 2);
+        assert_eq!(
+            battle_record.vs_bots[&BotDifficulty::Hard].win_rate(),
+            Some(0.75)
+        );
+        assert!(battle_record.is_valid());
+    }
+
+    #[test]
+    fn test_battle_records_checksum() {
+        let mut battle_record = BattleRecord::default();
+        battle_record.add_tournament(true, 5, 5, Some(BotDifficulty::Easy));
+        let battle_records = BattleRecords(vec![battle_record.clone()]);
+        let checksum = PersistentBattleRecords::calculate_checksum(&battle_records);
+
+        // a tampered record no longer matches the checksum
+        battle_record.tournaments.won += 10;
+        battle_record.tournaments.played += 10;
+        assert_ne!(
+            PersistentBattleRecords::calculate_checksum(&BattleRecords(vec![battle_record])),
+            checksum
+        );
+    }
 }
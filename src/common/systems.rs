@@ -35,6 +35,10 @@ pub fn handle_keyboard_input(
         (KeyCode::Escape, InputAction::Escape),
         (KeyCode::Back, InputAction::Back),
         (KeyCode::F, InputAction::F),
+        (KeyCode::H, InputAction::H),
+        (KeyCode::J, InputAction::J),
+        (KeyCode::M, InputAction::M),
+        (KeyCode::N, InputAction::N),
     ];
 
     for (key_code, input_action) in BINDINGS {
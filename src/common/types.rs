@@ -31,4 +31,8 @@ pub enum InputAction {
     Escape,
     Back,
     F,
+    H,
+    J,
+    M,
+    N,
 }
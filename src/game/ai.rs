@@ -1,18 +1,39 @@
-use bevy::{prelude::Entity, utils::HashSet};
+use std::collections::VecDeque;
+
+use bevy::{
+    prelude::Entity,
+    utils::{HashMap, HashSet},
+};
 use rand::{prelude::SliceRandom, Rng};
 
 use super::{
-    components::{BombSatchel, Position},
+    components::{BombSatchel, Flag, Position, TeamID},
     resources::{MapSize, WallOfDeath},
-    types::{Direction, PlayerAction},
+    rules::SuddenDeathKind,
+    types::{BombKind, Direction, PlayerAction, PlayerIntention},
+    utils::get_ring,
 };
 
+// what the bomb flames can't get through, pierce bomb flames still go through the pierceable
+// positions
+pub struct FlameStoppers<'a> {
+    pub fireproof_positions: &'a HashSet<Position>,
+    pub pierceable_positions: &'a HashSet<Position>,
+}
+
+impl FlameStoppers<'_> {
+    pub fn stops(&self, position: Position, kind: BombKind) -> bool {
+        self.fireproof_positions.contains(&position)
+            && !(kind == BombKind::Pierce && self.pierceable_positions.contains(&position))
+    }
+}
+
 pub fn safe(
     position: Position,
     fire_positions: &HashSet<Position>,
-    bomb_positions: &HashSet<Position>,
+    bombs: &HashMap<Position, BombKind>,
     assumed_bomb_range: usize,
-    fireproof_positions: &HashSet<Position>,
+    flame_stoppers: &FlameStoppers,
     wall_of_death: Option<&WallOfDeath>,
     map_size: MapSize,
 ) -> bool {
@@ -24,8 +45,16 @@ pub fn safe(
     // not standing in the wall-of-death path, if it's present
     if let Some(w) = wall_of_death {
         let in_danger = match w {
-            WallOfDeath::Dormant(t) => {
-                t.percent_left() * t.duration().as_secs_f32() < 5.0 && position.x == 1
+            WallOfDeath::Dormant(t, sudden_death_kind) => {
+                t.percent_left() * t.duration().as_secs_f32() < 5.0
+                    && match sudden_death_kind {
+                        SuddenDeathKind::Spiral => position.x == 1,
+                        SuddenDeathKind::ShrinkingRing | SuddenDeathKind::SpreadingFire => {
+                            get_ring(position, map_size) == 1
+                        }
+                        // the raining bombs are avoided like any other bomb
+                        SuddenDeathKind::BombRain | SuddenDeathKind::OneHit => false,
+                    }
             }
             WallOfDeath::Active(w) => match w.direction {
                 Direction::Left => {
@@ -51,6 +80,10 @@ pub fn safe(
                                 - (map_size.columns as isize - 2 - w.position.x)
                 }
             },
+            // avoid the next ring
+            WallOfDeath::ShrinkingRing { ring, .. } | WallOfDeath::SpreadingFire { ring, .. } => {
+                get_ring(position, map_size) <= *ring
+            }
             _ => false,
         };
 
@@ -59,19 +92,33 @@ pub fn safe(
         }
     }
 
+    // any bomb can reach across the map in one-hit sudden death
+    let assumed_bomb_range = if let Some(WallOfDeath::OneHit) = wall_of_death {
+        map_size.rows.max(map_size.columns)
+    } else {
+        assumed_bomb_range
+    };
+
     // not in range of bombs
-    if bomb_positions.contains(&position) {
+    if bombs.contains_key(&position) {
         return false;
     }
     for direction in Direction::LIST {
+        // each bomb kind has its flames stopped by different positions
+        let mut regular_flames_stopped = false;
         for i in 1..=assumed_bomb_range {
             let position = position.offset(direction, i);
-            if bomb_positions.contains(&position) {
-                return false;
+            match bombs.get(&position) {
+                Some(BombKind::Pierce) => return false,
+                Some(BombKind::Regular) if !regular_flames_stopped => return false,
+                _ => (),
             }
-            if fireproof_positions.contains(&position) {
+            if flame_stoppers.stops(position, BombKind::Pierce) {
                 break;
             }
+            if flame_stoppers.stops(position, BombKind::Regular) {
+                regular_flames_stopped = true;
+            }
         }
     }
 
@@ -81,9 +128,9 @@ pub fn safe(
 pub fn safe_dir(
     starting_position: Position,
     fire_positions: &HashSet<Position>,
-    bomb_positions: &HashSet<Position>,
+    bombs: &HashMap<Position, BombKind>,
     assumed_bomb_range: usize,
-    fireproof_positions: &HashSet<Position>,
+    flame_stoppers: &FlameStoppers,
     impassable_positions: &HashSet<Position>,
     wall_of_death: Option<&WallOfDeath>,
     map_size: MapSize,
@@ -96,9 +143,9 @@ pub fn safe_dir(
         safe(
             position,
             fire_positions,
-            bomb_positions,
+            bombs,
             assumed_bomb_range,
-            fireproof_positions,
+            flame_stoppers,
             wall_of_death,
             map_size,
         )
@@ -141,11 +188,11 @@ pub fn safe_dir(
             let range = min;
             for i in 1..=range {
                 let position = starting_position.offset(direction, i);
-                if (impassable_positions.contains(&position) && !bomb_positions.contains(&position))
+                if (impassable_positions.contains(&position) && !bombs.contains_key(&position))
                     || fire_positions.contains(&position)
                 {
                     break;
-                } else if bomb_positions.contains(&position)
+                } else if bombs.contains_key(&position)
                     && !moving_object_stoppers.contains(&position.offset(direction, 1))
                 {
                     if i < min {
@@ -166,9 +213,9 @@ pub fn detect_powers(
     starting_position: Position,
     impassable_positions: &HashSet<Position>,
     fire_positions: &HashSet<Position>,
-    bomb_positions: &HashSet<Position>,
+    bombs: &HashMap<Position, BombKind>,
     assumed_bomb_range: usize,
-    fireproof_positions: &HashSet<Position>,
+    flame_stoppers: &FlameStoppers,
     wall_of_death: Option<&WallOfDeath>,
     map_size: MapSize,
     item_positions: &HashSet<Position>,
@@ -179,9 +226,9 @@ pub fn detect_powers(
         safe(
             position,
             fire_positions,
-            bomb_positions,
+            bombs,
             assumed_bomb_range,
-            fireproof_positions,
+            flame_stoppers,
             wall_of_death,
             map_size,
         )
@@ -264,7 +311,8 @@ pub fn players_in_range(position: Position, player_positions: &[Position], range
 fn damage_made(
     bomb_position: Position,
     bomb_range: usize,
-    fireproof_positions: &HashSet<Position>,
+    bomb_kind: BombKind,
+    flame_stoppers: &FlameStoppers,
     destructible_positions: &HashSet<Position>,
 ) -> usize {
     let mut destructibles_in_range = 0;
@@ -275,7 +323,7 @@ fn damage_made(
             if destructible_positions.contains(&position) {
                 destructibles_in_range += 1;
             }
-            if fireproof_positions.contains(&position) {
+            if flame_stoppers.stops(position, bomb_kind) {
                 break;
             }
         }
@@ -290,11 +338,12 @@ fn can_place_bomb(position: Position, invalid_bomb_spawn_positions: &HashSet<Pos
 
 pub fn should_place_bomb(
     position: Position,
+    bomb_kind: BombKind,
     invalid_bomb_spawn_positions: &HashSet<Position>,
-    bomb_positions: &HashSet<Position>,
+    bombs: &HashMap<Position, BombKind>,
     assumed_bomb_range: usize,
     fire_positions: &HashSet<Position>,
-    fireproof_positions: &HashSet<Position>,
+    flame_stoppers: &FlameStoppers,
     impassable_positions: &HashSet<Position>,
     wall_of_death: Option<&WallOfDeath>,
     map_size: MapSize,
@@ -302,15 +351,15 @@ pub fn should_place_bomb(
     moving_object_stoppers: &HashSet<Position>,
 ) -> bool {
     if can_place_bomb(position, invalid_bomb_spawn_positions) {
-        let mut bomb_positions = bomb_positions.clone();
-        bomb_positions.insert(position);
+        let mut bombs = bombs.clone();
+        bombs.insert(position, bomb_kind);
 
         if !safe_dir(
             position,
             fire_positions,
-            &bomb_positions,
+            &bombs,
             assumed_bomb_range,
-            fireproof_positions,
+            flame_stoppers,
             impassable_positions,
             wall_of_death,
             map_size,
@@ -329,17 +378,19 @@ pub fn should_place_bomb(
 pub fn destroy_blocks(
     position: Position,
     bomb_satchel: &BombSatchel,
+    bomb_kind: BombKind,
     invalid_bomb_spawn_positions: &HashSet<Position>,
     fire_positions: &HashSet<Position>,
-    bomb_positions: &HashSet<Position>,
+    bombs: &HashMap<Position, BombKind>,
     assumed_bomb_range: usize,
-    fireproof_positions: &HashSet<Position>,
+    flame_stoppers: &FlameStoppers,
     impassable_positions: &HashSet<Position>,
     destructible_positions: &HashSet<Position>,
     wall_of_death: Option<&WallOfDeath>,
     map_size: MapSize,
     can_push_bombs: bool,
     moving_object_stoppers: &HashSet<Position>,
+    rng: &mut impl Rng,
 ) -> Option<PlayerAction> {
     let mut action = None;
     let mut max_destruction_potential = 0;
@@ -347,11 +398,12 @@ pub fn destroy_blocks(
     if bomb_satchel.bombs_available > 0
         && should_place_bomb(
             position,
+            bomb_kind,
             invalid_bomb_spawn_positions,
-            bomb_positions,
+            bombs,
             assumed_bomb_range,
             fire_positions,
-            fireproof_positions,
+            flame_stoppers,
             impassable_positions,
             wall_of_death,
             map_size,
@@ -362,7 +414,8 @@ pub fn destroy_blocks(
         max_destruction_potential = damage_made(
             position,
             bomb_satchel.bomb_range,
-            fireproof_positions,
+            bomb_kind,
+            flame_stoppers,
             destructible_positions,
         );
         if max_destruction_potential > 0 {
@@ -371,26 +424,27 @@ pub fn destroy_blocks(
     }
 
     let mut directions: Vec<Direction> = Direction::LIST.into();
-    directions.shuffle(&mut rand::thread_rng());
+    directions.shuffle(rng);
     for direction in directions {
         let position = position.offset(direction, 1);
         if !impassable_positions.contains(&position)
             && safe(
                 position,
                 fire_positions,
-                bomb_positions,
+                bombs,
                 assumed_bomb_range,
-                fireproof_positions,
+                flame_stoppers,
                 wall_of_death,
                 map_size,
             )
             && should_place_bomb(
                 position,
+                bomb_kind,
                 invalid_bomb_spawn_positions,
-                bomb_positions,
+                bombs,
                 assumed_bomb_range,
                 fire_positions,
-                fireproof_positions,
+                flame_stoppers,
                 impassable_positions,
                 wall_of_death,
                 map_size,
@@ -401,7 +455,8 @@ pub fn destroy_blocks(
             let destruction_potential = damage_made(
                 position,
                 bomb_satchel.bomb_range,
-                fireproof_positions,
+                bomb_kind,
+                flame_stoppers,
                 destructible_positions,
             );
             if destruction_potential > max_destruction_potential {
@@ -419,9 +474,9 @@ pub fn flee(
     enemy_positions: &[Position],
     impassable_positions: &HashSet<Position>,
     fire_positions: &HashSet<Position>,
-    bomb_positions: &HashSet<Position>,
+    bombs: &HashMap<Position, BombKind>,
     assumed_bomb_range: usize,
-    fireproof_positions: &HashSet<Position>,
+    flame_stoppers: &FlameStoppers,
     wall_of_death: Option<&WallOfDeath>,
     map_size: MapSize,
 ) -> HashSet<Direction> {
@@ -433,9 +488,9 @@ pub fn flee(
         safe(
             position,
             fire_positions,
-            bomb_positions,
+            bombs,
             assumed_bomb_range,
-            fireproof_positions,
+            flame_stoppers,
             wall_of_death,
             map_size,
         )
@@ -489,6 +544,132 @@ pub fn flee(
     result
 }
 
+// where a capture the flag bot should head to next
+pub fn flag_objective(
+    bot: Entity,
+    position: Position,
+    team_id: TeamID,
+    flags: &[(&Flag, Position)],
+    teammates: &HashSet<Entity>,
+) -> Option<(Position, PlayerIntention)> {
+    let own_flag = flags.iter().find(|(f, _)| f.team_id == team_id);
+
+    // bring the enemy flag home
+    if flags.iter().any(|(f, _)| f.carrier == Some(bot)) {
+        return own_flag.map(|(f, _)| (f.base, PlayerIntention::CaptureFlag));
+    }
+
+    // get the own flag back, be it dropped or carried off by an enemy
+    if let Some((f, p)) = own_flag {
+        if *p != f.base {
+            return Some((*p, PlayerIntention::ReturnFlag));
+        }
+    }
+
+    // stay close to a teammate carrying a flag
+    if let Some((_, p)) = flags
+        .iter()
+        .find(|(f, _)| f.carrier.map_or(false, |c| teammates.contains(&c)))
+    {
+        return if manhattan_dist(position, *p) > 2 {
+            Some((*p, PlayerIntention::EscortFlag))
+        } else {
+            None
+        };
+    }
+
+    // go for the closest enemy flag no one is carrying yet
+    flags
+        .iter()
+        .filter(|(f, _)| f.team_id != team_id && f.carrier.is_none())
+        .min_by_key(|(_, p)| manhattan_dist(position, *p))
+        .map(|(_, p)| (*p, PlayerIntention::CaptureFlag))
+}
+
+// the closest tile of the king of the hill zone, or staying put once in it
+pub fn hill_objective(
+    position: Position,
+    hill_positions: &[Position],
+) -> Option<(Position, PlayerIntention)> {
+    if hill_positions.contains(&position) {
+        return Some((position, PlayerIntention::ContestZone));
+    }
+
+    hill_positions
+        .iter()
+        .min_by_key(|p| manhattan_dist(position, **p))
+        .map(|p| (*p, PlayerIntention::ContestZone))
+}
+
+// the infected go after the closest survivor, touching it is enough
+pub fn infection_objective(
+    position: Position,
+    survivor_positions: &[Position],
+) -> Option<(Position, PlayerIntention)> {
+    survivor_positions
+        .iter()
+        .min_by_key(|p| manhattan_dist(position, **p))
+        .map(|p| (*p, PlayerIntention::InfectPlayers))
+}
+
+fn manhattan_dist(p1: Position, p2: Position) -> usize {
+    (p1.y - p2.y).unsigned_abs() + (p1.x - p2.x).unsigned_abs()
+}
+
+// the first steps of the shortest paths to the target, if they are safe to take
+pub fn path_to(
+    starting_position: Position,
+    target: Position,
+    obstacle_positions: &HashSet<Position>,
+    impassable_positions: &HashSet<Position>,
+    map_size: MapSize,
+    safe: impl Fn(Position) -> bool,
+) -> HashSet<Direction> {
+    let mut result = HashSet::default();
+
+    // walk back from the target, so that the distances lead the way
+    let mut distances: HashMap<Position, usize> = HashMap::default();
+    distances.insert(target, 0);
+    let mut queue = VecDeque::from([target]);
+    while let Some(position) = queue.pop_front() {
+        if position == starting_position {
+            break;
+        }
+
+        let distance = distances[&position];
+        for direction in Direction::LIST {
+            let next_position = position.offset(direction, 1);
+            if next_position.y >= 0
+                && next_position.y < map_size.rows as isize
+                && next_position.x >= 0
+                && next_position.x < map_size.columns as isize
+                && (next_position == starting_position
+                    || !obstacle_positions.contains(&next_position))
+                && !distances.contains_key(&next_position)
+            {
+                distances.insert(next_position, distance + 1);
+                queue.push_back(next_position);
+            }
+        }
+    }
+
+    if let Some(distance) = distances.get(&starting_position) {
+        for direction in Direction::LIST {
+            let position = starting_position.offset(direction, 1);
+            if distances
+                .get(&position)
+                .map_or(false, |d| d + 1 == *distance)
+                && !impassable_positions.contains(&position)
+                && safe(position)
+            {
+                result.insert(direction);
+            }
+        }
+    }
+
+    result
+}
+
 fn dist(p1: Position, p2: Position) -> f32 {
     f32::sqrt((isize::pow(p1.y - p2.y, 2) + isize::pow(p1.x - p2.x, 2)) as f32)
 }
@@ -501,12 +682,12 @@ pub fn hunt_players(
     stone_wall_positions: &HashSet<Position>,
     impassable_positions: &HashSet<Position>,
     fire_positions: &HashSet<Position>,
-    bomb_positions: &HashSet<Position>,
+    bombs: &HashMap<Position, BombKind>,
     assumed_bomb_range: usize,
-    fireproof_positions: &HashSet<Position>,
+    flame_stoppers: &FlameStoppers,
     wall_of_death: Option<&WallOfDeath>,
+    rng: &mut impl Rng,
 ) -> HashSet<Direction> {
-    let mut rng = rand::thread_rng();
     let mut minf = (map_size.rows + map_size.columns) as f32;
     let mut target = None;
     let mut result = HashSet::default();
@@ -562,9 +743,9 @@ pub fn hunt_players(
                             && safe(
                                 position,
                                 fire_positions,
-                                bomb_positions,
+                                bombs,
                                 assumed_bomb_range,
-                                fireproof_positions,
+                                flame_stoppers,
                                 wall_of_death,
                                 map_size,
                             )
@@ -579,9 +760,9 @@ pub fn hunt_players(
                             && safe(
                                 position,
                                 fire_positions,
-                                bomb_positions,
+                                bombs,
                                 assumed_bomb_range,
-                                fireproof_positions,
+                                flame_stoppers,
                                 wall_of_death,
                                 map_size,
                             )
@@ -599,9 +780,9 @@ pub fn hunt_players(
                     && safe(
                         position,
                         fire_positions,
-                        bomb_positions,
+                        bombs,
                         assumed_bomb_range,
-                        fireproof_positions,
+                        flame_stoppers,
                         wall_of_death,
                         map_size,
                     )
@@ -615,3 +796,138 @@ pub fn hunt_players(
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::{Timer, TimerMode};
+
+    use super::*;
+
+    use crate::game::types::Cooldown;
+
+    #[test]
+    fn test_path_to() {
+        let map_size = MapSize {
+            rows: 5,
+            columns: 5,
+        };
+        // a wall splitting the way to the target, open at the bottom
+        let obstacle_positions: HashSet<Position> = (0..5)
+            .flat_map(|i| {
+                [
+                    Position { y: 0, x: i },
+                    Position { y: 4, x: i },
+                    Position { y: i, x: 0 },
+                    Position { y: i, x: 4 },
+                ]
+            })
+            .chain([Position { y: 1, x: 2 }, Position { y: 2, x: 2 }])
+            .collect();
+
+        let directions = path_to(
+            Position { y: 1, x: 1 },
+            Position { y: 1, x: 3 },
+            &obstacle_positions,
+            &obstacle_positions,
+            map_size,
+            |_| true,
+        );
+        assert_eq!(directions, [Direction::Down].into_iter().collect());
+
+        // no safe way to go
+        assert!(path_to(
+            Position { y: 1, x: 1 },
+            Position { y: 1, x: 3 },
+            &obstacle_positions,
+            &obstacle_positions,
+            map_size,
+            |_| false,
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_safe_from_bombs_behind_destructible_walls() {
+        let map_size = MapSize {
+            rows: 11,
+            columns: 15,
+        };
+        let position = Position { y: 1, x: 1 };
+        let bomb_position = Position { y: 1, x: 3 };
+        let destructible_positions = HashSet::from([Position { y: 1, x: 2 }]);
+        let flame_stoppers = FlameStoppers {
+            fireproof_positions: &destructible_positions,
+            pierceable_positions: &destructible_positions,
+        };
+        let safe_from = |kind| {
+            safe(
+                position,
+                &HashSet::default(),
+                &HashMap::from([(bomb_position, kind)]),
+                3,
+                &flame_stoppers,
+                None,
+                map_size,
+            )
+        };
+
+        // the wall takes the regular flames...
+        assert!(safe_from(BombKind::Regular));
+        // ...but not the pierce ones
+        assert!(!safe_from(BombKind::Pierce));
+    }
+
+    #[test]
+    fn test_safe_from_sudden_death() {
+        let map_size = MapSize {
+            rows: 11,
+            columns: 15,
+        };
+        let fireproof_positions = HashSet::default();
+        let flame_stoppers = FlameStoppers {
+            fireproof_positions: &fireproof_positions,
+            pierceable_positions: &fireproof_positions,
+        };
+        let bombs = HashMap::from([(Position { y: 1, x: 12 }, BombKind::Regular)]);
+        let safe_at = |position, wall_of_death| {
+            safe(
+                position,
+                &HashSet::default(),
+                &bombs,
+                2,
+                &flame_stoppers,
+                wall_of_death,
+                map_size,
+            )
+        };
+        let outer_ring_position = Position { y: 1, x: 5 };
+        let inner_ring_position = Position { y: 2, x: 5 };
+
+        // the next ring to be filled in is avoided...
+        let shrinking_ring = WallOfDeath::ShrinkingRing {
+            ring: 1,
+            cooldown: Cooldown::from_seconds(1.0),
+        };
+        assert!(!safe_at(outer_ring_position, Some(&shrinking_ring)));
+        assert!(safe_at(inner_ring_position, Some(&shrinking_ring)));
+
+        // ...already shortly before the sudden death starts
+        let starting_soon = WallOfDeath::Dormant(
+            Timer::from_seconds(3.0, TimerMode::Once),
+            SuddenDeathKind::SpreadingFire,
+        );
+        let starting_later = WallOfDeath::Dormant(
+            Timer::from_seconds(60.0, TimerMode::Once),
+            SuddenDeathKind::SpreadingFire,
+        );
+        assert!(!safe_at(outer_ring_position, Some(&starting_soon)));
+        assert!(safe_at(outer_ring_position, Some(&starting_later)));
+
+        // in one-hit sudden death, bombs reach across the map
+        assert!(safe_at(Position { y: 1, x: 1 }, None));
+        assert!(!safe_at(
+            Position { y: 1, x: 1 },
+            Some(&WallOfDeath::OneHit)
+        ));
+    }
+}
@@ -0,0 +1,416 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    components::{Item, Position},
+    resources::MapSize,
+    types::{Power, Upgrade},
+};
+
+/*
+Handcrafted maps are stored as plain text, one character per tile:
+
+    #      stone wall
+    *      destructible wall
+    .      empty tile
+    1-8    penguin spawn point (player/bot 1-8 spawns there)
+    M      mob spawn point
+    E      exit, hidden under a destructible wall
+    b r l  bombs up, range up and lives up upgrades
+    w p i  wall hack, bomb push and immortality powers
+    d g    remote detonator and glove powers
+    s n    pierce bomb and line bomb powers
+
+The map has to be rectangular and surrounded by stone walls.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapTile {
+    Empty,
+    StoneWall,
+    DestructibleWall,
+    PenguinSpawn(usize),
+    MobSpawn,
+    Exit,
+    Item(Item),
+}
+
+impl MapTile {
+    pub const MAX_PENGUIN_SPAWNS: usize = 8;
+
+    pub fn from_char(character: char) -> Option<Self> {
+        let tile = match character {
+            '#' => Self::StoneWall,
+            '*' => Self::DestructibleWall,
+            '.' => Self::Empty,
+            '1'..='8' => Self::PenguinSpawn(character as usize - '1' as usize),
+            'M' => Self::MobSpawn,
+            'E' => Self::Exit,
+            'b' => Self::Item(Item::Upgrade(Upgrade::BombsUp)),
+            'r' => Self::Item(Item::Upgrade(Upgrade::RangeUp)),
+            'l' => Self::Item(Item::Upgrade(Upgrade::LivesUp)),
+            'w' => Self::Item(Item::Power(Power::WallHack)),
+            'p' => Self::Item(Item::Power(Power::BombPush)),
+            'i' => Self::Item(Item::Power(Power::Immortal)),
+            'd' => Self::Item(Item::Power(Power::RemoteDetonator)),
+            'g' => Self::Item(Item::Power(Power::Glove)),
+            's' => Self::Item(Item::Power(Power::PierceBomb)),
+            'n' => Self::Item(Item::Power(Power::LineBomb)),
+            _ => return None,
+        };
+
+        Some(tile)
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Self::StoneWall => '#',
+            Self::DestructibleWall => '*',
+            Self::Empty => '.',
+            Self::PenguinSpawn(penguin) => (b'1' + penguin as u8) as char,
+            Self::MobSpawn => 'M',
+            Self::Exit => 'E',
+            Self::Item(Item::Upgrade(Upgrade::BombsUp)) => 'b',
+            Self::Item(Item::Upgrade(Upgrade::RangeUp)) => 'r',
+            Self::Item(Item::Upgrade(Upgrade::LivesUp)) => 'l',
+            Self::Item(Item::Power(Power::WallHack)) => 'w',
+            Self::Item(Item::Power(Power::BombPush)) => 'p',
+            Self::Item(Item::Power(Power::Immortal)) => 'i',
+            Self::Item(Item::Power(Power::RemoteDetonator)) => 'd',
+            Self::Item(Item::Power(Power::Glove)) => 'g',
+            Self::Item(Item::Power(Power::PierceBomb)) => 's',
+            Self::Item(Item::Power(Power::LineBomb)) => 'n',
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AsciiMapError {
+    TooSmall,
+    UnevenRow(usize),
+    InvalidCharacter {
+        character: char,
+        row: usize,
+        column: usize,
+    },
+    OpenBorder {
+        row: usize,
+        column: usize,
+    },
+    DuplicatePenguinSpawn(usize),
+    MultipleExits,
+}
+
+impl fmt::Display for AsciiMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooSmall => write!(f, "The map must be at least 3x3 tiles."),
+            Self::UnevenRow(row) => write!(
+                f,
+                "Row {} has a different length than the first row.",
+                row + 1
+            ),
+            Self::InvalidCharacter {
+                character,
+                row,
+                column,
+            } => write!(
+                f,
+                "Invalid character '{}' at row {}, column {}.",
+                character,
+                row + 1,
+                column + 1
+            ),
+            Self::OpenBorder { row, column } => write!(
+                f,
+                "The border tile at row {}, column {} is not a stone wall.",
+                row + 1,
+                column + 1
+            ),
+            Self::DuplicatePenguinSpawn(penguin) => {
+                write!(f, "Spawn point {} is placed more than once.", penguin + 1)
+            }
+            Self::MultipleExits => write!(f, "The map can have only one exit."),
+        }
+    }
+}
+
+impl std::error::Error for AsciiMapError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct AsciiMap {
+    tiles: Vec<Vec<MapTile>>,
+}
+
+impl AsciiMap {
+    // an empty map surrounded by stone walls
+    pub fn new(map_size: MapSize) -> Self {
+        Self {
+            tiles: (0..map_size.rows)
+                .map(|y| {
+                    (0..map_size.columns)
+                        .map(|x| {
+                            if y == 0
+                                || x == 0
+                                || y == map_size.rows - 1
+                                || x == map_size.columns - 1
+                            {
+                                MapTile::StoneWall
+                            } else {
+                                MapTile::Empty
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    pub fn map_size(&self) -> MapSize {
+        MapSize {
+            rows: self.tiles.len(),
+            columns: self.tiles[0].len(),
+        }
+    }
+
+    pub fn tile(&self, position: Position) -> MapTile {
+        self.tiles[position.y as usize][position.x as usize]
+    }
+
+    // spawn points and the exit are unique, so their previous placement gets cleared
+    pub fn set_tile(&mut self, position: Position, tile: MapTile) {
+        if matches!(tile, MapTile::PenguinSpawn(_) | MapTile::Exit) {
+            for t in self.tiles.iter_mut().flatten().filter(|t| **t == tile) {
+                *t = MapTile::Empty;
+            }
+        }
+
+        self.tiles[position.y as usize][position.x as usize] = tile;
+    }
+
+    // keeps the inner tiles that still fit, the new border is made of stone walls
+    pub fn resize(&mut self, map_size: MapSize) {
+        let old_map_size = self.map_size();
+        let mut resized = Self::new(map_size);
+        for y in 1..map_size.rows.min(old_map_size.rows) - 1 {
+            for x in 1..map_size.columns.min(old_map_size.columns) - 1 {
+                resized.tiles[y][x] = self.tiles[y][x];
+            }
+        }
+
+        *self = resized;
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (Position, MapTile)> + '_ {
+        self.tiles.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, tile)| {
+                (
+                    Position {
+                        y: y as isize,
+                        x: x as isize,
+                    },
+                    *tile,
+                )
+            })
+        })
+    }
+
+    // ordered by the spawn point number
+    pub fn penguin_spawn_positions(&self) -> Vec<Position> {
+        let mut penguin_spawns: Vec<(usize, Position)> = self
+            .tiles()
+            .filter_map(|(position, tile)| match tile {
+                MapTile::PenguinSpawn(penguin) => Some((penguin, position)),
+                _ => None,
+            })
+            .collect();
+        penguin_spawns.sort_by_key(|(penguin, _)| *penguin);
+
+        penguin_spawns
+            .into_iter()
+            .map(|(_, position)| position)
+            .collect()
+    }
+
+    pub fn mob_spawn_positions(&self) -> Vec<Position> {
+        self.tiles()
+            .filter(|(_, tile)| *tile == MapTile::MobSpawn)
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    pub fn exit_position(&self) -> Option<Position> {
+        self.tiles()
+            .find(|(_, tile)| *tile == MapTile::Exit)
+            .map(|(position, _)| position)
+    }
+}
+
+impl FromStr for AsciiMap {
+    type Err = AsciiMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        let mut tiles = vec![];
+        for (row, line) in lines.iter().enumerate() {
+            let tile_row = line
+                .chars()
+                .enumerate()
+                .map(|(column, character)| {
+                    MapTile::from_char(character).ok_or(AsciiMapError::InvalidCharacter {
+                        character,
+                        row,
+                        column,
+                    })
+                })
+                .collect::<Result<Vec<MapTile>, _>>()?;
+            tiles.push(tile_row);
+        }
+
+        if tiles.len() < 3 || tiles[0].len() < 3 {
+            return Err(AsciiMapError::TooSmall);
+        }
+        if let Some(row) = tiles.iter().position(|r| r.len() != tiles[0].len()) {
+            return Err(AsciiMapError::UnevenRow(row));
+        }
+
+        let ascii_map = Self { tiles };
+        let map_size = ascii_map.map_size();
+        let mut penguin_spawns = [false; MapTile::MAX_PENGUIN_SPAWNS];
+        let mut exit_found = false;
+        for (position, tile) in ascii_map.tiles() {
+            let (row, column) = (position.y as usize, position.x as usize);
+            if (row == 0
+                || column == 0
+                || row == map_size.rows - 1
+                || column == map_size.columns - 1)
+                && tile != MapTile::StoneWall
+            {
+                return Err(AsciiMapError::OpenBorder { row, column });
+            }
+
+            match tile {
+                MapTile::PenguinSpawn(penguin) => {
+                    if penguin_spawns[penguin] {
+                        return Err(AsciiMapError::DuplicatePenguinSpawn(penguin));
+                    }
+                    penguin_spawns[penguin] = true;
+                }
+                MapTile::Exit => {
+                    if exit_found {
+                        return Err(AsciiMapError::MultipleExits);
+                    }
+                    exit_found = true;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(ascii_map)
+    }
+}
+
+impl fmt::Display for AsciiMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.tiles {
+            writeln!(f, "{}", row.iter().map(|t| t.to_char()).collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<AsciiMap> for String {
+    fn from(ascii_map: AsciiMap) -> Self {
+        ascii_map.to_string()
+    }
+}
+
+impl TryFrom<String> for AsciiMap {
+    type Error = AsciiMapError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "
+#######
+#1.*.M#
+#.#E#.#
+#b.*.2#
+#######
+";
+
+    #[test]
+    fn test_parse_map() {
+        let ascii_map: AsciiMap = MAP.parse().unwrap();
+
+        let map_size = ascii_map.map_size();
+        assert_eq!((map_size.rows, map_size.columns), (5, 7));
+        assert_eq!(
+            ascii_map.penguin_spawn_positions(),
+            vec![Position { y: 1, x: 1 }, Position { y: 3, x: 5 }]
+        );
+        assert_eq!(
+            ascii_map.mob_spawn_positions(),
+            vec![Position { y: 1, x: 5 }]
+        );
+        assert_eq!(ascii_map.exit_position(), Some(Position { y: 2, x: 3 }));
+        assert_eq!(ascii_map.to_string(), MAP.trim_start());
+    }
+
+    #[test]
+    fn test_edit_map() {
+        let mut ascii_map = AsciiMap::new(MapSize {
+            rows: 5,
+            columns: 5,
+        });
+        ascii_map.set_tile(Position { y: 1, x: 1 }, MapTile::PenguinSpawn(0));
+        ascii_map.set_tile(Position { y: 3, x: 3 }, MapTile::PenguinSpawn(0));
+        ascii_map.set_tile(Position { y: 2, x: 2 }, MapTile::StoneWall);
+        assert_eq!(ascii_map.to_string(), "#####\n#...#\n#.#.#\n#..1#\n#####\n");
+
+        ascii_map.resize(MapSize {
+            rows: 4,
+            columns: 6,
+        });
+        assert_eq!(ascii_map.to_string(), "######\n#....#\n#.#..#\n######\n");
+    }
+
+    #[test]
+    fn test_parse_invalid_maps() {
+        assert_eq!("###\n#.#".parse::<AsciiMap>(), Err(AsciiMapError::TooSmall));
+        assert_eq!(
+            "####\n#.#\n####".parse::<AsciiMap>(),
+            Err(AsciiMapError::UnevenRow(1))
+        );
+        assert_eq!(
+            "###\n#x#\n###".parse::<AsciiMap>(),
+            Err(AsciiMapError::InvalidCharacter {
+                character: 'x',
+                row: 1,
+                column: 1
+            })
+        );
+        assert_eq!(
+            "###\n..#\n###".parse::<AsciiMap>(),
+            Err(AsciiMapError::OpenBorder { row: 1, column: 0 })
+        );
+        assert_eq!(
+            "#####\n#1.1#\n#####".parse::<AsciiMap>(),
+            Err(AsciiMapError::DuplicatePenguinSpawn(0))
+        );
+    }
+}
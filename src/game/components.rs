@@ -1,6 +1,10 @@
 use bevy::{ecs as bevy_ecs, prelude::*};
+use serde::{Deserialize, Serialize};
 
-use super::types::{BotDifficulty, Cooldown, Direction, Power, Upgrade};
+use super::{
+    constants::BOMB_THROW_DISTANCE,
+    types::{BombKind, BotDifficulty, Cooldown, Direction, Power, Upgrade},
+};
 
 // UI
 
@@ -55,11 +59,28 @@ pub struct MobAI {
     pub direction: Option<Direction>,
 }
 
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MobType {
+    Crook,
+    Hatter,
+    Bat,
+}
+
 #[derive(Component)]
 pub struct BotAI {
     pub difficulty: BotDifficulty,
 }
 
+// an eliminated battle player haunting the outer ring of the map
+#[derive(Component)]
+pub struct Ghost {
+    pub throws_left: usize,
+    pub throw_cooldown: Cooldown,
+}
+
 #[derive(Component)]
 pub struct MoveCooldown(pub Cooldown);
 
@@ -69,6 +90,33 @@ pub struct Moving {
     pub direction: Direction,
 }
 
+// thrown bombs fly over everything and come back around at the map edges
+#[derive(Clone, Copy, Component)]
+pub struct Airborne {
+    pub direction: Direction,
+    pub distance: usize,
+    pub distance_travelled: usize,
+}
+
+impl Airborne {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            distance: BOMB_THROW_DISTANCE,
+            distance_travelled: 0,
+        }
+    }
+
+    // height of the throw arc, in tiles
+    pub fn height(&self) -> f32 {
+        (std::f32::consts::PI * self.distance_travelled as f32 / self.distance as f32).sin() / 2.0
+    }
+}
+
+// the direction of the last move, used for throwing
+#[derive(Component)]
+pub struct Facing(pub Direction);
+
 #[derive(Component)]
 pub struct Health {
     pub lives: usize,
@@ -84,7 +132,10 @@ pub struct ImmortalTexture(pub Handle<Image>);
 #[derive(Clone, Component)]
 pub struct Bomb {
     pub owner: Option<Entity>,
+    // the player whose fire set the bomb off early, for chain reactions
+    pub ignited_by: Option<Entity>,
     pub range: usize,
+    pub kind: BombKind,
     pub timer: Timer,
 }
 
@@ -106,10 +157,10 @@ pub struct Immortal {
     pub animation_timer: Timer,
 }
 
-impl Default for Immortal {
-    fn default() -> Self {
+impl Immortal {
+    pub fn new(duration_secs: f32) -> Self {
         Immortal {
-            timer: Timer::from_seconds(2.0, TimerMode::Once),
+            timer: Timer::from_seconds(duration_secs, TimerMode::Once),
             animation_timer: Timer::from_seconds(0.66, TimerMode::Repeating),
         }
     }
@@ -122,9 +173,21 @@ pub struct WallHack;
 pub struct BombPush;
 
 #[derive(Component)]
-pub struct MeleeAttacker;
+pub struct RemoteDetonator;
 
 #[derive(Component)]
+pub struct Glove;
+
+#[derive(Component)]
+pub struct PierceBomb;
+
+#[derive(Component)]
+pub struct LineBomb;
+
+#[derive(Component)]
+pub struct MeleeAttacker;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Component)]
 pub struct TeamID(pub usize);
 
 #[derive(Component)]
@@ -132,6 +195,8 @@ pub struct PointValue(pub usize);
 
 #[derive(Component)]
 pub struct Fire {
+    // the player whose bomb started the fire, following chain reactions back to the first bomb
+    pub owner: Option<Entity>,
     pub timer: Timer,
 }
 
@@ -149,7 +214,11 @@ pub struct Crumbling {
     pub timer: Timer,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Component)]
+// listed by the inner upgrade or power name in the loot tables
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component, Serialize, Deserialize,
+)]
+#[serde(untagged)]
 pub enum Item {
     Upgrade(Upgrade),
     Power(Power),
@@ -173,6 +242,18 @@ impl Default for Exit {
     }
 }
 
+// a tile of the king of the hill zone
+#[derive(Component)]
+pub struct Hill;
+
+// a capture the flag team's flag, which follows its carrier around
+#[derive(Component)]
+pub struct Flag {
+    pub team_id: TeamID,
+    pub base: Position,
+    pub carrier: Option<Entity>,
+}
+
 // HUD display
 
 #[derive(Component)]
@@ -192,3 +273,6 @@ pub struct PenguinPortraitDisplay;
 
 #[derive(Component)]
 pub struct PenguinPortrait(pub Penguin);
+
+#[derive(Component)]
+pub struct PenguinPortraitGlove(pub Penguin);
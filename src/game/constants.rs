@@ -5,4 +5,7 @@ pub const HUD_HEIGHT: usize = 14 * PIXEL_SCALE;
 pub const TILE_HEIGHT: usize = 8 * PIXEL_SCALE;
 pub const TILE_WIDTH: usize = 6 * PIXEL_SCALE;
 
-pub const ITEM_SPAWN_CHANCE: f32 = 0.1;
+pub const SHORTENED_FUSE_DURATION_SECS: f32 = 0.05;
+pub const PUSHED_BOMB_MOVE_COOLDOWN_SECS: f32 = 0.01;
+pub const BOMB_THROW_DISTANCE: usize = 3;
+pub const AIRBORNE_BOMB_MOVE_COOLDOWN_SECS: f32 = 0.06;
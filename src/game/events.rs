@@ -19,15 +19,36 @@ pub struct BombRestockEvent {
     pub satchel_owner: Entity,
 }
 
+// what hurt a player
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageSource {
+    // the player whose bomb started the fire, chain reactions included
+    Bomb(Entity),
+    SelfKill,
+    Mob(Entity),
+    WallOfDeath,
+    // e.g. bombs without an owner
+    Unknown,
+}
+
 #[derive(Event)]
 pub struct DamageEvent {
     pub target: Entity,
+    pub source: DamageSource,
 }
 
 #[derive(Event)]
 pub struct BurnEvent {
     pub position: Position,
+    // the player whose bomb started the fire
+    pub bomber: Option<Entity>,
 }
 
 #[derive(Event)]
-pub struct PlayerDeathEvent;
+pub struct PlayerDeathEvent {
+    pub player: Entity,
+    pub position: Position,
+    pub mob_type: Option<MobType>,
+    pub penguin: Option<Penguin>,
+    pub source: DamageSource,
+}
@@ -0,0 +1,341 @@
+use std::{collections::BTreeMap, fs};
+
+use bevy::{ecs as bevy_ecs, prelude::*, utils::HashMap};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    components::{Item, MobType},
+    resources::WorldID,
+    rules::GameRules,
+};
+
+/*
+Loot tables decide what the destructible walls, fallen penguins and mobs drop. The bundled tables
+can be replaced with a local file of the same layout, see `assets/loot_tables.json`.
+
+Items are listed by name (e.g. "BombsUp", "WallHack"). A table drops with its `drop_chance`,
+picking an item by weight. Guaranteed items are handed out first, no matter the drop chance, and
+caps limit how many of an item can drop over a single round or level. A cap set on any table holds
+for the drops of every table, guaranteed ones included.
+*/
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootTable {
+    pub drop_chance: f32,
+    pub weights: BTreeMap<Item, u32>,
+    #[serde(default)]
+    pub guaranteed: Vec<Item>,
+    #[serde(default)]
+    pub caps: BTreeMap<Item, usize>,
+}
+
+// what drops from where in a game mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootSources {
+    pub walls: LootTable,
+    // what a penguin scatters around the map when it dies
+    #[serde(default)]
+    pub deaths: Option<LootTable>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+pub struct LootTables {
+    pub story: LootSources,
+    pub battle: LootSources,
+    pub secret: LootSources,
+    // replaces the story mode sources in some worlds
+    #[serde(default)]
+    pub story_worlds: BTreeMap<usize, LootSources>,
+    #[serde(default)]
+    pub mobs: BTreeMap<MobType, LootTable>,
+}
+
+impl Default for LootTables {
+    fn default() -> Self {
+        match fs::read_to_string(Self::LOOT_TABLES_FILE_PATH) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(loot_tables) => return loot_tables,
+                Err(e) => eprintln!(
+                    "Cannot load loot tables from {}, using the bundled ones. Error: {}",
+                    Self::LOOT_TABLES_FILE_PATH,
+                    e
+                ),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => eprintln!(
+                "Cannot read {}, using the bundled loot tables. Error: {}",
+                Self::LOOT_TABLES_FILE_PATH,
+                e
+            ),
+        }
+
+        Self::bundled()
+    }
+}
+
+impl LootTables {
+    const LOOT_TABLES_FILE_PATH: &'static str = "local/loot_tables.json";
+    const BUNDLED_LOOT_TABLES: &'static str = include_str!("../../assets/loot_tables.json");
+
+    pub fn bundled() -> Self {
+        serde_json::from_str(Self::BUNDLED_LOOT_TABLES).unwrap()
+    }
+
+    pub fn story_world(&self, world_id: WorldID) -> &LootSources {
+        self.story_worlds.get(&world_id.0).unwrap_or(&self.story)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LootSource {
+    Wall,
+    Death,
+    Mob(MobType),
+}
+
+// the caps of all the tables in a match, along with what has dropped so far
+#[derive(Default)]
+struct LootCaps {
+    caps: HashMap<Item, usize>,
+    dropped: HashMap<Item, usize>,
+}
+
+impl LootCaps {
+    fn add_caps(&mut self, table: &LootTable) {
+        for (item, cap) in table.caps.iter() {
+            // the tightest cap wins if several tables set one
+            let merged_cap = self.caps.entry(*item).or_insert(*cap);
+            *merged_cap = (*merged_cap).min(*cap);
+        }
+    }
+
+    fn allows(&self, item: Item) -> bool {
+        match self.caps.get(&item) {
+            Some(cap) => self.dropped.get(&item).copied().unwrap_or(0) < *cap,
+            None => true,
+        }
+    }
+
+    fn count(&mut self, item: Item) {
+        *self.dropped.entry(item).or_insert(0) += 1;
+    }
+}
+
+// a table in use, along with the guaranteed drops it still owes
+struct ActiveLootTable {
+    table: LootTable,
+    guaranteed_left: Vec<Item>,
+}
+
+impl ActiveLootTable {
+    fn new(table: &LootTable) -> Self {
+        Self {
+            table: table.clone(),
+            guaranteed_left: table.guaranteed.clone(),
+        }
+    }
+
+    fn roll(&mut self, loot_caps: &mut LootCaps, rng: &mut impl Rng) -> Option<Item> {
+        // guaranteed items that already hit their cap are forfeited
+        while !self.guaranteed_left.is_empty() {
+            let item = self.guaranteed_left.remove(0);
+            if loot_caps.allows(item) {
+                loot_caps.count(item);
+                return Some(item);
+            }
+        }
+
+        let item = if rng.gen_range(0.0..1.0) < self.table.drop_chance {
+            let available: Vec<(Item, u32)> = self
+                .table
+                .weights
+                .iter()
+                .filter(|(item, _)| loot_caps.allows(**item))
+                .map(|(item, weight)| (*item, *weight))
+                .collect();
+            let total_weight: u32 = available.iter().map(|(_, w)| w).sum();
+            if total_weight == 0 {
+                return None;
+            }
+
+            let mut r = rng.gen_range(0..total_weight);
+            available.into_iter().find_map(|(item, weight)| {
+                if r < weight {
+                    Some(item)
+                } else {
+                    r -= weight;
+                    None
+                }
+            })
+        } else {
+            None
+        };
+
+        if let Some(item) = item {
+            loot_caps.count(item);
+        }
+
+        item
+    }
+}
+
+/// The loot tables of the current round or level, which keep count of what they dropped.
+#[derive(Resource)]
+pub struct MatchLoot {
+    walls: ActiveLootTable,
+    deaths: Option<ActiveLootTable>,
+    mobs: HashMap<MobType, ActiveLootTable>,
+    loot_caps: LootCaps,
+}
+
+impl MatchLoot {
+    pub fn new(
+        loot_tables: &LootTables,
+        loot_sources: &LootSources,
+        game_rules: &GameRules,
+    ) -> Self {
+        let mut walls = ActiveLootTable::new(&loot_sources.walls);
+        if let Some(item_spawn_chance) = game_rules.item_spawn_chance {
+            walls.table.drop_chance = item_spawn_chance;
+        }
+
+        let deaths = loot_sources.deaths.as_ref().map(ActiveLootTable::new);
+        let mobs: HashMap<MobType, ActiveLootTable> = loot_tables
+            .mobs
+            .iter()
+            .map(|(mob_type, table)| (*mob_type, ActiveLootTable::new(table)))
+            .collect();
+
+        let mut loot_caps = LootCaps::default();
+        for active_loot_table in [Some(&walls), deaths.as_ref()]
+            .into_iter()
+            .flatten()
+            .chain(mobs.values())
+        {
+            loot_caps.add_caps(&active_loot_table.table);
+        }
+
+        Self {
+            walls,
+            deaths,
+            mobs,
+            loot_caps,
+        }
+    }
+
+    pub fn roll(&mut self, loot_source: LootSource, rng: &mut impl Rng) -> Option<Item> {
+        let active_loot_table = match loot_source {
+            LootSource::Wall => Some(&mut self.walls),
+            LootSource::Death => self.deaths.as_mut(),
+            LootSource::Mob(mob_type) => self.mobs.get_mut(&mob_type),
+        };
+
+        active_loot_table.and_then(|t| t.roll(&mut self.loot_caps, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::game::types::{Power, Upgrade};
+
+    use super::*;
+
+    #[test]
+    fn test_bundled_loot_tables() {
+        let loot_tables = LootTables::bundled();
+        assert!(loot_tables.battle.deaths.is_some());
+        assert!(!loot_tables
+            .battle
+            .walls
+            .weights
+            .contains_key(&Item::Upgrade(Upgrade::LivesUp)));
+    }
+
+    #[test]
+    fn test_guaranteed_drops_and_caps() {
+        let wall_hack = Item::Power(Power::WallHack);
+        let table = LootTable {
+            drop_chance: 1.0,
+            weights: [(wall_hack, 1), (Item::Upgrade(Upgrade::BombsUp), 1)]
+                .into_iter()
+                .collect(),
+            guaranteed: vec![Item::Upgrade(Upgrade::RangeUp)],
+            caps: [(wall_hack, 2)].into_iter().collect(),
+        };
+        let mut match_loot = MatchLoot::new(
+            &LootTables::bundled(),
+            &LootSources {
+                walls: table,
+                deaths: None,
+            },
+            &GameRules::default(),
+        );
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let drops: Vec<Option<Item>> = (0..50)
+            .map(|_| match_loot.roll(LootSource::Wall, &mut rng))
+            .collect();
+        assert_eq!(drops[0], Some(Item::Upgrade(Upgrade::RangeUp)));
+        assert!(drops.iter().all(|d| d.is_some()));
+        assert_eq!(drops.iter().filter(|d| **d == Some(wall_hack)).count(), 2);
+
+        // no table means no drop
+        assert_eq!(match_loot.roll(LootSource::Death, &mut rng), None);
+    }
+
+    #[test]
+    fn test_caps_across_sources() {
+        let wall_hack = Item::Power(Power::WallHack);
+        let mut loot_tables = LootTables::bundled();
+        loot_tables.mobs.clear();
+        let mut match_loot = MatchLoot::new(
+            &loot_tables,
+            &LootSources {
+                // only the wall table sets the cap...
+                walls: LootTable {
+                    drop_chance: 1.0,
+                    weights: [(wall_hack, 1)].into_iter().collect(),
+                    guaranteed: vec![],
+                    caps: [(wall_hack, 2)].into_iter().collect(),
+                },
+                deaths: Some(LootTable {
+                    drop_chance: 1.0,
+                    weights: [(wall_hack, 1)].into_iter().collect(),
+                    guaranteed: vec![wall_hack],
+                    caps: BTreeMap::new(),
+                }),
+            },
+            &GameRules::default(),
+        );
+
+        // ...but it counts and limits the drops of both tables
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(match_loot.roll(LootSource::Wall, &mut rng), Some(wall_hack));
+        assert_eq!(
+            match_loot.roll(LootSource::Death, &mut rng),
+            Some(wall_hack)
+        );
+        assert_eq!(match_loot.roll(LootSource::Wall, &mut rng), None);
+        assert_eq!(match_loot.roll(LootSource::Death, &mut rng), None);
+
+        // guaranteed drops are held to the cap too
+        let mut match_loot = MatchLoot::new(
+            &loot_tables,
+            &LootSources {
+                walls: LootTable {
+                    drop_chance: 0.0,
+                    weights: BTreeMap::new(),
+                    guaranteed: vec![wall_hack, wall_hack, wall_hack],
+                    caps: [(wall_hack, 1)].into_iter().collect(),
+                },
+                deaths: None,
+            },
+            &GameRules::default(),
+        );
+        assert_eq!(match_loot.roll(LootSource::Wall, &mut rng), Some(wall_hack));
+        assert_eq!(match_loot.roll(LootSource::Wall, &mut rng), None);
+    }
+}
@@ -0,0 +1,662 @@
+use std::{collections::VecDeque, fmt};
+
+use bevy::utils::HashSet;
+use itertools::Itertools;
+use rand::{prelude::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ascii_map::{AsciiMap, MapTile},
+    components::Position,
+    resources::MapSize,
+    types::Direction,
+    utils::get_stone_wall_spawn_groups,
+};
+
+// layouts that can fail the spawn point checks (e.g. sealed off by random walls) get a few tries
+const MAP_GENERATION_ATTEMPTS: usize = 10;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MapGenerationError {
+    BlockedSpawnPoint(Position),
+    UnreachableSpawnPoint(Position),
+    NotEnoughRoom { available: usize, needed: usize },
+    NoRoomForExit,
+}
+
+impl fmt::Display for MapGenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BlockedSpawnPoint(position) => {
+                write!(f, "The spawn point at {:?} is inside a wall.", position)
+            }
+            Self::UnreachableSpawnPoint(position) => write!(
+                f,
+                "The spawn point at {:?} can't be reached from the other spawn points.",
+                position
+            ),
+            Self::NotEnoughRoom { available, needed } => write!(
+                f,
+                "Not enough passable positions available for placing destructible walls. Have {}, but need at least {}.",
+                available, needed
+            ),
+            Self::NoRoomForExit => write!(f, "There are no destructible walls to hide the exit under."),
+        }
+    }
+}
+
+impl std::error::Error for MapGenerationError {}
+
+pub struct MapGenerationParams<'a> {
+    pub map_size: MapSize,
+    pub percent_of_passable_positions_to_fill: f32,
+    pub penguin_spawn_positions: &'a [Position],
+    pub mob_spawn_positions: &'a [Position],
+    pub spawn_exit: bool,
+}
+
+pub trait MapGenerator {
+    // the stone wall layout, border included
+    fn stone_wall_positions(
+        &self,
+        map_size: MapSize,
+        spawn_positions: &[Position],
+        rng: &mut dyn RngCore,
+    ) -> HashSet<Position>;
+
+    // the positions that have to look the same as the given one (itself included)
+    fn mirrored_positions(&self, _map_size: MapSize, position: Position) -> Vec<Position> {
+        vec![position]
+    }
+
+    fn generate(
+        &self,
+        params: &MapGenerationParams,
+        rng: &mut dyn RngCore,
+    ) -> Result<AsciiMap, MapGenerationError> {
+        let spawn_positions: Vec<Position> = params
+            .penguin_spawn_positions
+            .iter()
+            .chain(params.mob_spawn_positions)
+            .copied()
+            .collect();
+
+        let mut attempt = 1;
+        loop {
+            let stone_wall_positions =
+                self.stone_wall_positions(params.map_size, &spawn_positions, rng);
+            let result = make_room(&stone_wall_positions, params, rng).and_then(
+                |destructible_wall_candidates| {
+                    check_spawn_points(params.map_size, &stone_wall_positions, &spawn_positions)?;
+                    fill_map(
+                        self,
+                        &stone_wall_positions,
+                        destructible_wall_candidates,
+                        params,
+                        rng,
+                    )
+                },
+            );
+
+            if result.is_ok() || attempt == MAP_GENERATION_ATTEMPTS {
+                return result;
+            }
+            attempt += 1;
+        }
+    }
+}
+
+fn get_all_positions(map_size: MapSize) -> impl Iterator<Item = Position> {
+    (0..map_size.rows).flat_map(move |y| {
+        (0..map_size.columns).map(move |x| Position {
+            y: y as isize,
+            x: x as isize,
+        })
+    })
+}
+
+fn is_border_position(map_size: MapSize, position: Position) -> bool {
+    position.y == 0
+        || position.x == 0
+        || position.y == map_size.rows as isize - 1
+        || position.x == map_size.columns as isize - 1
+}
+
+fn is_inside_map(map_size: MapSize, position: Position) -> bool {
+    (0..map_size.rows as isize).contains(&position.y)
+        && (0..map_size.columns as isize).contains(&position.x)
+}
+
+// the spawn points and their direct neighbours
+fn get_spawn_area_positions(spawn_positions: &[Position]) -> HashSet<Position> {
+    spawn_positions
+        .iter()
+        .flat_map(|p| {
+            std::iter::once(*p).chain(Direction::LIST.iter().map(move |d| p.offset(*d, 1)))
+        })
+        .collect()
+}
+
+// reserves room around the spawn points, returns the positions where destructible walls can go
+// if there are enough of them for the requested fill
+fn make_room(
+    stone_wall_positions: &HashSet<Position>,
+    params: &MapGenerationParams,
+    rng: &mut dyn RngCore,
+) -> Result<Vec<Position>, MapGenerationError> {
+    let map_size = params.map_size;
+    let mut reserved_positions = HashSet::default();
+
+    // reserve room for the penguins (cross-shaped)
+    reserved_positions.extend(get_spawn_area_positions(params.penguin_spawn_positions));
+
+    // reserve room for the mobs (line-shaped)
+    for mob_spawn_position in params.mob_spawn_positions {
+        reserved_positions.insert(*mob_spawn_position);
+
+        for direction in [
+            [Direction::Left, Direction::Right],
+            [Direction::Up, Direction::Down],
+        ]
+        .choose(rng)
+        .unwrap()
+        {
+            for j in 1..3 {
+                let position = mob_spawn_position.offset(*direction, j);
+                if stone_wall_positions.contains(&position) {
+                    break;
+                }
+                reserved_positions.insert(position);
+            }
+        }
+    }
+
+    let passable_positions: Vec<Position> = get_all_positions(map_size)
+        .filter(|p| !stone_wall_positions.contains(p))
+        .collect();
+    let number_of_destructible_walls = (passable_positions.len() as f32
+        * params.percent_of_passable_positions_to_fill
+        / 100.0) as usize;
+    let destructible_wall_candidates: Vec<Position> = passable_positions
+        .into_iter()
+        .filter(|p| !reserved_positions.contains(p))
+        .collect();
+
+    // denser layouts (caves, mazes) can't always take the fill, the caller decides what to do then
+    if destructible_wall_candidates.len() >= number_of_destructible_walls {
+        Ok(destructible_wall_candidates)
+    } else {
+        Err(MapGenerationError::NotEnoughRoom {
+            available: destructible_wall_candidates.len(),
+            needed: number_of_destructible_walls,
+        })
+    }
+}
+
+// destructible walls can be blown up, so only the stone walls can separate the spawn points
+fn check_spawn_points(
+    map_size: MapSize,
+    stone_wall_positions: &HashSet<Position>,
+    spawn_positions: &[Position],
+) -> Result<(), MapGenerationError> {
+    if let Some(position) = spawn_positions
+        .iter()
+        .find(|p| !is_inside_map(map_size, **p) || stone_wall_positions.contains(*p))
+    {
+        return Err(MapGenerationError::BlockedSpawnPoint(*position));
+    }
+
+    if let Some(first_spawn_position) = spawn_positions.first() {
+        let mut reachable_positions = HashSet::default();
+        reachable_positions.insert(*first_spawn_position);
+        let mut queue = VecDeque::from([*first_spawn_position]);
+        while let Some(position) = queue.pop_front() {
+            for neighbour in Direction::LIST.iter().map(|d| position.offset(*d, 1)) {
+                if is_inside_map(map_size, neighbour)
+                    && !stone_wall_positions.contains(&neighbour)
+                    && reachable_positions.insert(neighbour)
+                {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        if let Some(position) = spawn_positions
+            .iter()
+            .find(|p| !reachable_positions.contains(*p))
+        {
+            return Err(MapGenerationError::UnreachableSpawnPoint(*position));
+        }
+    }
+
+    Ok(())
+}
+
+fn fill_map<G: MapGenerator + ?Sized>(
+    map_generator: &G,
+    stone_wall_positions: &HashSet<Position>,
+    destructible_wall_candidates: Vec<Position>,
+    params: &MapGenerationParams,
+    rng: &mut dyn RngCore,
+) -> Result<AsciiMap, MapGenerationError> {
+    let map_size = params.map_size;
+    let number_of_passable_positions = get_all_positions(map_size)
+        .filter(|p| !stone_wall_positions.contains(p))
+        .count();
+    let number_of_destructible_walls = (number_of_passable_positions as f32
+        * params.percent_of_passable_positions_to_fill
+        / 100.0) as usize;
+
+    // mirrored positions are filled together
+    let candidate_set: HashSet<Position> = destructible_wall_candidates.iter().copied().collect();
+    let mut grouped_positions = HashSet::default();
+    let mut candidate_groups = vec![];
+    for position in destructible_wall_candidates {
+        if grouped_positions.contains(&position) {
+            continue;
+        }
+
+        let group: Vec<Position> = map_generator
+            .mirrored_positions(map_size, position)
+            .into_iter()
+            .filter(|p| candidate_set.contains(p) && grouped_positions.insert(*p))
+            .collect();
+        candidate_groups.push(group);
+    }
+    candidate_groups.shuffle(rng);
+
+    let mut destructible_wall_positions = vec![];
+    for group in candidate_groups {
+        if destructible_wall_positions.len() >= number_of_destructible_walls {
+            break;
+        }
+        destructible_wall_positions.extend(group);
+    }
+
+    let mut ascii_map = AsciiMap::new(map_size);
+    for position in stone_wall_positions {
+        ascii_map.set_tile(*position, MapTile::StoneWall);
+    }
+    for position in &destructible_wall_positions {
+        ascii_map.set_tile(*position, MapTile::DestructibleWall);
+    }
+    for (i, position) in params
+        .penguin_spawn_positions
+        .iter()
+        .take(MapTile::MAX_PENGUIN_SPAWNS)
+        .enumerate()
+    {
+        ascii_map.set_tile(*position, MapTile::PenguinSpawn(i));
+    }
+    for position in params.mob_spawn_positions {
+        ascii_map.set_tile(*position, MapTile::MobSpawn);
+    }
+    if params.spawn_exit {
+        let exit_position = destructible_wall_positions
+            .choose(rng)
+            .ok_or(MapGenerationError::NoRoomForExit)?;
+        ascii_map.set_tile(*exit_position, MapTile::Exit);
+    }
+
+    Ok(ascii_map)
+}
+
+// the outer walls and, optionally, the checkered middle
+pub struct ClassicMapGenerator {
+    pub spawn_middle_blocks: bool,
+}
+
+impl Default for ClassicMapGenerator {
+    fn default() -> Self {
+        Self {
+            spawn_middle_blocks: true,
+        }
+    }
+}
+
+impl MapGenerator for ClassicMapGenerator {
+    fn stone_wall_positions(
+        &self,
+        map_size: MapSize,
+        _spawn_positions: &[Position],
+        _rng: &mut dyn RngCore,
+    ) -> HashSet<Position> {
+        get_stone_wall_spawn_groups(map_size, self.spawn_middle_blocks)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Symmetry {
+    // mirrored over both the horizontal and the vertical axis
+    Mirrored,
+    // the same after a half turn
+    Rotational,
+}
+
+// fair battle arenas, the middle blocks are partially removed and random pillars are added
+pub struct SymmetricMapGenerator {
+    pub symmetry: Symmetry,
+}
+
+impl MapGenerator for SymmetricMapGenerator {
+    fn stone_wall_positions(
+        &self,
+        map_size: MapSize,
+        spawn_positions: &[Position],
+        rng: &mut dyn RngCore,
+    ) -> HashSet<Position> {
+        let spawn_area_positions = get_spawn_area_positions(spawn_positions);
+
+        let mut stone_wall_positions: HashSet<Position> = get_all_positions(map_size)
+            .filter(|p| is_border_position(map_size, *p))
+            .collect();
+        for position in get_all_positions(map_size).filter(|p| !is_border_position(map_size, *p)) {
+            let mirrored_positions = self.mirrored_positions(map_size, position);
+            // each group of mirrored positions is decided once
+            if mirrored_positions
+                .iter()
+                .any(|p| (p.y, p.x) < (position.y, position.x))
+                || mirrored_positions
+                    .iter()
+                    .any(|p| spawn_area_positions.contains(p))
+            {
+                continue;
+            }
+
+            let stone_wall_chance = if position.y % 2 == 0 && position.x % 2 == 0 {
+                0.8
+            } else {
+                0.1
+            };
+            if rng.gen_bool(stone_wall_chance) {
+                stone_wall_positions.extend(mirrored_positions);
+            }
+        }
+
+        stone_wall_positions
+    }
+
+    fn mirrored_positions(&self, map_size: MapSize, position: Position) -> Vec<Position> {
+        let opposite = |p: Position| Position {
+            y: map_size.rows as isize - 1 - p.y,
+            x: map_size.columns as isize - 1 - p.x,
+        };
+
+        let positions = match self.symmetry {
+            Symmetry::Mirrored => vec![
+                position,
+                Position {
+                    y: position.y,
+                    x: opposite(position).x,
+                },
+                Position {
+                    y: opposite(position).y,
+                    x: position.x,
+                },
+                opposite(position),
+            ],
+            Symmetry::Rotational => vec![position, opposite(position)],
+        };
+        positions.into_iter().unique().collect()
+    }
+}
+
+// cellular automaton caves, with tunnels dug between the spawn points
+pub struct CaveMapGenerator;
+
+impl MapGenerator for CaveMapGenerator {
+    fn stone_wall_positions(
+        &self,
+        map_size: MapSize,
+        spawn_positions: &[Position],
+        rng: &mut dyn RngCore,
+    ) -> HashSet<Position> {
+        let spawn_area_positions = get_spawn_area_positions(spawn_positions);
+        let is_open =
+            |p: &Position| !is_border_position(map_size, *p) && spawn_area_positions.contains(p);
+
+        let mut stone_wall_positions: HashSet<Position> = get_all_positions(map_size)
+            .filter(|p| is_border_position(map_size, *p) || (!is_open(p) && rng.gen_bool(0.35)))
+            .collect();
+
+        // smooth out the noise
+        for _ in 0..3 {
+            stone_wall_positions = get_all_positions(map_size)
+                .filter(|p| {
+                    if is_border_position(map_size, *p) {
+                        return true;
+                    }
+                    if is_open(p) {
+                        return false;
+                    }
+
+                    let neighbouring_stone_walls = (-1..=1)
+                        .cartesian_product(-1..=1)
+                        .filter(|(y, x)| {
+                            (*y, *x) != (0, 0)
+                                && stone_wall_positions.contains(&Position {
+                                    y: p.y + y,
+                                    x: p.x + x,
+                                })
+                        })
+                        .count();
+                    neighbouring_stone_walls >= 5
+                        || (neighbouring_stone_walls == 4 && stone_wall_positions.contains(p))
+                })
+                .collect();
+        }
+
+        // connect every spawn point to the first one
+        if let Some(first_spawn_position) = spawn_positions.first() {
+            for spawn_position in spawn_positions.iter().skip(1) {
+                let mut position = *spawn_position;
+                while position != *first_spawn_position {
+                    if position.x != first_spawn_position.x {
+                        position.x += (first_spawn_position.x - position.x).signum();
+                    } else {
+                        position.y += (first_spawn_position.y - position.y).signum();
+                    }
+                    if !is_border_position(map_size, position) {
+                        stone_wall_positions.remove(&position);
+                    }
+                }
+            }
+        }
+
+        stone_wall_positions
+    }
+}
+
+// maze corridors between the checkered middle blocks, with some loops knocked through
+pub struct MazeMapGenerator;
+
+impl MapGenerator for MazeMapGenerator {
+    fn stone_wall_positions(
+        &self,
+        map_size: MapSize,
+        spawn_positions: &[Position],
+        rng: &mut dyn RngCore,
+    ) -> HashSet<Position> {
+        // the maze cells are on the odd positions, everything between them starts as a wall
+        let is_cell = |p: &Position| {
+            p.y % 2 == 1
+                && p.x % 2 == 1
+                && p.y < map_size.rows as isize - 1
+                && p.x < map_size.columns as isize - 1
+        };
+        let mut stone_wall_positions: HashSet<Position> = get_all_positions(map_size)
+            .filter(|p| !is_cell(p))
+            .collect();
+
+        // randomized depth-first search
+        let start = Position { y: 1, x: 1 };
+        let mut visited_cells = HashSet::default();
+        visited_cells.insert(start);
+        let mut stack = vec![start];
+        while let Some(cell) = stack.last().copied() {
+            let unvisited_neighbours: Vec<Direction> = Direction::LIST
+                .iter()
+                .copied()
+                .filter(|d| {
+                    let neighbour = cell.offset(*d, 2);
+                    is_cell(&neighbour) && !visited_cells.contains(&neighbour)
+                })
+                .collect();
+
+            if let Some(direction) = unvisited_neighbours.choose(rng) {
+                stone_wall_positions.remove(&cell.offset(*direction, 1));
+                let neighbour = cell.offset(*direction, 2);
+                visited_cells.insert(neighbour);
+                stack.push(neighbour);
+            } else {
+                stack.pop();
+            }
+        }
+
+        // a perfect maze has only one path between two points, which makes for dull fights
+        let remaining_maze_walls: Vec<Position> = stone_wall_positions
+            .iter()
+            .filter(|p| !is_border_position(map_size, **p) && (p.y % 2 == 1 || p.x % 2 == 1))
+            .copied()
+            .sorted_by_key(|p| (p.y, p.x))
+            .collect();
+        for position in remaining_maze_walls {
+            if rng.gen_bool(0.4) {
+                stone_wall_positions.remove(&position);
+            }
+        }
+
+        for position in get_spawn_area_positions(spawn_positions) {
+            if !is_border_position(map_size, position) {
+                stone_wall_positions.remove(&position);
+            }
+        }
+
+        stone_wall_positions
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapGeneratorKind {
+    Classic,
+    Mirrored,
+    Rotational,
+    Caves,
+    Maze,
+}
+
+impl MapGeneratorKind {
+    pub const LIST: [Self; 5] = [
+        Self::Classic,
+        Self::Mirrored,
+        Self::Rotational,
+        Self::Caves,
+        Self::Maze,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "CLASSIC",
+            Self::Mirrored => "MIRRORED",
+            Self::Rotational => "ROTATIONAL",
+            Self::Caves => "CAVES",
+            Self::Maze => "MAZE",
+        }
+    }
+
+    pub fn map_generator(self) -> Box<dyn MapGenerator> {
+        match self {
+            Self::Classic => Box::<ClassicMapGenerator>::default(),
+            Self::Mirrored => Box::new(SymmetricMapGenerator {
+                symmetry: Symmetry::Mirrored,
+            }),
+            Self::Rotational => Box::new(SymmetricMapGenerator {
+                symmetry: Symmetry::Rotational,
+            }),
+            Self::Caves => Box::new(CaveMapGenerator),
+            Self::Maze => Box::new(MazeMapGenerator),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    const MAP_SIZE: MapSize = MapSize {
+        rows: 13,
+        columns: 17,
+    };
+
+    fn get_spawn_positions() -> Vec<Position> {
+        [
+            (1, 1),
+            (11, 15),
+            (1, 15),
+            (11, 1),
+            (3, 5),
+            (9, 11),
+            (3, 11),
+            (9, 5),
+        ]
+        .iter()
+        .map(|(y, x)| Position { y: *y, x: *x })
+        .collect()
+    }
+
+    #[test]
+    fn test_generators_keep_spawn_points_connected() {
+        let spawn_positions = get_spawn_positions();
+        for map_generator_kind in MapGeneratorKind::LIST {
+            for seed in 0..20 {
+                let ascii_map = map_generator_kind
+                    .map_generator()
+                    .generate(
+                        &MapGenerationParams {
+                            map_size: MAP_SIZE,
+                            percent_of_passable_positions_to_fill: 70.0,
+                            penguin_spawn_positions: &spawn_positions,
+                            mob_spawn_positions: &[],
+                            spawn_exit: false,
+                        },
+                        &mut StdRng::seed_from_u64(seed),
+                    )
+                    .unwrap();
+
+                let stone_wall_positions: HashSet<Position> = ascii_map
+                    .tiles()
+                    .filter(|(_, t)| *t == MapTile::StoneWall)
+                    .map(|(p, _)| p)
+                    .collect();
+                assert_eq!(
+                    check_spawn_points(MAP_SIZE, &stone_wall_positions, &spawn_positions),
+                    Ok(())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_impossible_fill() {
+        let result = ClassicMapGenerator::default().generate(
+            &MapGenerationParams {
+                map_size: MAP_SIZE,
+                percent_of_passable_positions_to_fill: 100.0,
+                penguin_spawn_positions: &get_spawn_positions(),
+                mob_spawn_positions: &[],
+                spawn_exit: false,
+            },
+            &mut StdRng::seed_from_u64(0),
+        );
+
+        assert!(matches!(
+            result,
+            Err(MapGenerationError::NotEnoughRoom { .. })
+        ));
+    }
+}
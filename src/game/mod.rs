@@ -3,15 +3,21 @@ use bevy::{
     prelude::*,
 };
 
-use crate::AppState;
+use crate::{replay::ReplayPlayback, AppState};
 
-use self::{events::*, resources::*, systems::*};
+use self::{events::*, loot_tables::LootTables, resources::*, rules::RulesetLibrary, systems::*};
 
 mod ai;
+pub mod ascii_map;
 pub mod components;
 pub mod constants;
 pub mod events;
+pub mod loot_tables;
+pub mod map_generators;
 pub mod resources;
+pub mod rules;
+pub mod simulation;
+pub mod stats;
 pub mod systems;
 pub mod types;
 pub mod utils;
@@ -56,7 +62,9 @@ pub fn common_game_systems() -> SystemConfigs {
             mob_ai,
             bot_ai.after(Set::TimeUpdate),
         )
-            .in_set(Set::Input),
+            .in_set(Set::Input)
+            // replays feed the recorded actions instead
+            .run_if(not(resource_exists::<ReplayPlayback>())),
         // handle movement
         (
             (player_move, apply_deferred)
@@ -69,7 +77,8 @@ pub fn common_game_systems() -> SystemConfigs {
         )
             .after(Set::TimeUpdate),
         // handle bomb logic
-        (bomb_drop, apply_deferred)
+        // detonations are decided before the bomb drops use up the satchel
+        (remote_detonation, bomb_throw, bomb_drop, apply_deferred)
             .chain()
             .in_set(Set::BombSpawn)
             .after(Set::Input),
@@ -78,6 +87,7 @@ pub fn common_game_systems() -> SystemConfigs {
             .in_set(Set::BombRestockEvent)
             .in_set(Set::FireSpawn)
             .after(Set::TimeUpdate)
+            .after(Set::BombSpawn)
             .after(Set::MovingObjectUpdate),
         bomb_restock.after(Set::BombRestockEvent),
         (
@@ -108,6 +118,10 @@ pub fn common_game_systems() -> SystemConfigs {
             .in_set(Set::PlayerDeathEvent)
             .after(Set::PlayerMovement)
             .after(Set::DamageEvent),
+        (mob_loot_drop, apply_deferred)
+            .chain()
+            .in_set(Set::ItemSpawn)
+            .after(Set::PlayerDeathEvent),
         pick_up_item
             .after(Set::PlayerDeathEvent)
             .after(Set::ItemSpawn)
@@ -125,6 +139,9 @@ impl Plugin for GamePlugin {
         app.init_resource::<HUDColors>()
             .init_resource::<GameTextures>()
             .init_resource::<Sounds>()
+            .init_resource::<MapLibrary>()
+            .init_resource::<LootTables>()
+            .init_resource::<RulesetLibrary>()
             .add_event::<PlayerActionEvent>()
             .add_event::<ExplosionEvent>()
             .add_event::<BombRestockEvent>()
@@ -1,4 +1,8 @@
+use std::fs;
+
 use bevy::{ecs as bevy_ecs, prelude::*};
+use itertools::Itertools;
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
     audio::{SoundHandles, SoundID},
@@ -8,8 +12,10 @@ use crate::{
 };
 
 use super::{
-    components::{Penguin, Position},
-    types::{Cooldown, Direction},
+    ascii_map::{AsciiMap, AsciiMapError},
+    components::{Item, Penguin, Position},
+    rules::{GameRules, SuddenDeathKind},
+    types::{Cooldown, Direction, Power, Upgrade},
 };
 
 #[derive(Default)]
@@ -46,6 +52,10 @@ pub struct GameTextures {
     pub wall_hack: Handle<Image>,
     pub bomb_push: Handle<Image>,
     pub immortal: Handle<Image>,
+    pub remote_detonator: Handle<Image>,
+    pub glove: Handle<Image>,
+    pub pierce_bomb: Handle<Image>,
+    pub line_bomb: Handle<Image>,
     pub burning_item: Handle<Image>,
 }
 
@@ -61,6 +71,51 @@ impl GameTextures {
     pub fn get_penguin_texture(&self, penguin: Penguin) -> &Handle<Image> {
         self.penguin_variants.iter().cycle().nth(penguin.0).unwrap()
     }
+
+    pub fn get_item_texture(&self, item: Item) -> &Handle<Image> {
+        match item {
+            Item::Upgrade(Upgrade::BombsUp) => &self.bombs_up,
+            Item::Upgrade(Upgrade::RangeUp) => &self.range_up,
+            Item::Upgrade(Upgrade::LivesUp) => &self.lives_up,
+            Item::Power(Power::WallHack) => &self.wall_hack,
+            Item::Power(Power::BombPush) => &self.bomb_push,
+            Item::Power(Power::Immortal) => &self.immortal,
+            Item::Power(Power::RemoteDetonator) => &self.remote_detonator,
+            Item::Power(Power::Glove) => &self.glove,
+            Item::Power(Power::PierceBomb) => &self.pierce_bomb,
+            Item::Power(Power::LineBomb) => &self.line_bomb,
+        }
+    }
+
+    // textureless placeholders, used when the game runs without a window (e.g. bot tournaments)
+    pub fn placeholder() -> Self {
+        Self {
+            penguin_variants: vec![Handle::default()],
+            immortal_penguin: Handle::default(),
+            crook: Handle::default(),
+            immortal_crook: Handle::default(),
+            hatter: Handle::default(),
+            immortal_hatter: Handle::default(),
+            bat: Handle::default(),
+            immortal_bat: Handle::default(),
+            bomb: Handle::default(),
+            fire: Handle::default(),
+            map_textures: (1..=3).map(|_| MapTextures::default()).collect(),
+            map_textures_index: 0,
+            exit: Handle::default(),
+            bombs_up: Handle::default(),
+            range_up: Handle::default(),
+            lives_up: Handle::default(),
+            wall_hack: Handle::default(),
+            bomb_push: Handle::default(),
+            immortal: Handle::default(),
+            remote_detonator: Handle::default(),
+            glove: Handle::default(),
+            pierce_bomb: Handle::default(),
+            line_bomb: Handle::default(),
+            burning_item: Handle::default(),
+        }
+    }
 }
 
 impl FromWorld for GameTextures {
@@ -98,6 +153,10 @@ impl FromWorld for GameTextures {
         let wall_hack_texture = asset_server.load("sprites/wall_hack.png");
         let bomb_push_texture = asset_server.load("sprites/bomb_push.png");
         let immortal_texture = asset_server.load("sprites/immortal.png");
+        let remote_detonator_texture = asset_server.load("sprites/remote_detonator.png");
+        let glove_texture = asset_server.load("sprites/glove.png");
+        let pierce_bomb_texture = asset_server.load("sprites/pierce_bomb.png");
+        let line_bomb_texture = asset_server.load("sprites/line_bomb.png");
         let burning_item_texture = asset_server.load("sprites/burning_item.png");
 
         let game_textures = GameTextures {
@@ -133,6 +192,10 @@ impl FromWorld for GameTextures {
             wall_hack: wall_hack_texture.clone(),
             bomb_push: bomb_push_texture.clone(),
             immortal: immortal_texture.clone(),
+            remote_detonator: remote_detonator_texture.clone(),
+            glove: glove_texture.clone(),
+            pierce_bomb: pierce_bomb_texture.clone(),
+            line_bomb: line_bomb_texture.clone(),
             burning_item: burning_item_texture.clone(),
         };
 
@@ -168,6 +231,10 @@ impl FromWorld for GameTextures {
                 wall_hack_texture.clone_untyped(),
                 bomb_push_texture.clone_untyped(),
                 immortal_texture.clone_untyped(),
+                remote_detonator_texture.clone_untyped(),
+                glove_texture.clone_untyped(),
+                pierce_bomb_texture.clone_untyped(),
+                line_bomb_texture.clone_untyped(),
                 burning_item_texture.clone_untyped(),
             ]);
         }
@@ -244,16 +311,151 @@ pub struct MapSize {
 #[derive(Clone, Copy, Resource)]
 pub struct WorldID(pub usize);
 
+// handcrafted maps, see the ascii_map module for the format
+#[derive(Resource)]
+pub struct MapLibrary(Vec<(String, AsciiMap)>);
+
+impl Default for MapLibrary {
+    fn default() -> Self {
+        let mut map_library = Self(
+            Self::BUNDLED_MAPS
+                .iter()
+                .map(|(name, content)| (name.to_string(), content.parse().unwrap()))
+                .collect(),
+        );
+
+        if let Ok(entries) = fs::read_dir(Self::MAPS_DIRECTORY_PATH) {
+            for path in entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().map_or(false, |e| e == "txt"))
+                .sorted()
+            {
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                match fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|c| c.parse().map_err(|e: AsciiMapError| e.to_string()))
+                {
+                    Ok(ascii_map) => map_library.insert(name, ascii_map),
+                    Err(e) => eprintln!("Cannot load map {}. Error: {}", path.display(), e),
+                }
+            }
+        }
+
+        map_library
+    }
+}
+
+impl MapLibrary {
+    const MAPS_DIRECTORY_PATH: &'static str = "local/maps";
+    const BUNDLED_MAPS: [(&'static str, &'static str); 2] = [
+        ("arena", include_str!("../../assets/maps/arena.txt")),
+        (
+            "crossroads",
+            include_str!("../../assets/maps/crossroads.txt"),
+        ),
+    ];
+
+    pub fn get(&self, name: &str) -> Option<&AsciiMap> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, m)| m)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AsciiMap)> {
+        self.0.iter().map(|(n, m)| (n.as_str(), m))
+    }
+
+    // maps with the same name are replaced
+    pub fn insert(&mut self, name: String, ascii_map: AsciiMap) {
+        if let Some(entry) = self.0.iter_mut().find(|(n, _)| *n == name) {
+            entry.1 = ascii_map;
+        } else {
+            self.0.push((name, ascii_map));
+        }
+    }
+
+    pub fn save(&mut self, name: String, ascii_map: AsciiMap) -> std::io::Result<()> {
+        let map_file_path =
+            std::path::Path::new(Self::MAPS_DIRECTORY_PATH).join(format!("{}.txt", name));
+        fs::create_dir_all(Self::MAPS_DIRECTORY_PATH)
+            .and_then(|_| fs::write(map_file_path, ascii_map.to_string()))?;
+        self.insert(name, ascii_map);
+
+        Ok(())
+    }
+}
+
 #[derive(Resource)]
 pub struct GameTimer(pub Timer);
 
 #[derive(Resource)]
 pub struct GameScore(pub usize);
 
+// setting this environment variable replays a match with a known seed
+const SEED_ENV_VAR: &str = "ASCII_BOMB_SEED";
+
+/// Match-wide source of randomness. Each concern gets its own stream so that, for example,
+/// a bot making a different decision doesn't change the loot that drops later on.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    pub map: StdRng,
+    pub loot: StdRng,
+    pub ai: StdRng,
+    // things happening mid-round, like the sudden death bomb rain
+    pub gameplay: StdRng,
+    // looks only, like the colors of the leaderboard border
+    pub cosmetic: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            map: StdRng::seed_from_u64(seed),
+            loot: StdRng::seed_from_u64(seed.wrapping_add(1)),
+            ai: StdRng::seed_from_u64(seed.wrapping_add(2)),
+            gameplay: StdRng::seed_from_u64(seed.wrapping_add(3)),
+            cosmetic: StdRng::seed_from_u64(seed.wrapping_add(4)),
+        }
+    }
+
+    pub fn for_new_match(next_match_seed: Option<&NextMatchSeed>) -> Self {
+        match next_match_seed {
+            Some(next_match_seed) => {
+                println!("Match seed: {}", next_match_seed.0);
+                Self::new(next_match_seed.0)
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        let seed = match std::env::var(SEED_ENV_VAR).map(|s| s.parse::<u64>()) {
+            Ok(Ok(seed)) => seed,
+            Ok(Err(_)) => {
+                println!("Invalid {} value, using a random seed.", SEED_ENV_VAR);
+                rand::random()
+            }
+            Err(_) => rand::random(),
+        };
+        println!("Match seed: {}", seed);
+
+        Self::new(seed)
+    }
+}
+
+// seed to be used by the next match instead of a random one, e.g. when playing back a replay
+#[derive(Resource)]
+pub struct NextMatchSeed(pub u64);
+
 #[derive(Resource)]
 pub struct GameContext {
     pub pausable: bool,
-    pub reduced_loot: bool,
     pub game_mode_manager_state: AppState,
 }
 
@@ -273,7 +475,40 @@ pub struct ActiveWallOfDeath {
 
 #[derive(Resource)]
 pub enum WallOfDeath {
-    Dormant(Timer),
+    Dormant(Timer, SuddenDeathKind),
+    // the spiral
     Active(ActiveWallOfDeath),
+    // the next ring from the map edge to be filled in
+    ShrinkingRing { ring: isize, cooldown: Cooldown },
+    BombRain(Cooldown),
+    SpreadingFire { ring: isize, cooldown: Cooldown },
+    // lasts until the end of the round
+    OneHit,
     Done,
 }
+
+impl WallOfDeath {
+    pub fn new(game_rules: &GameRules) -> Self {
+        Self::Dormant(
+            Timer::from_seconds(game_rules.wall_of_death_start_secs, TimerMode::Once),
+            game_rules.sudden_death,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_maps() {
+        for (name, content) in MapLibrary::BUNDLED_MAPS {
+            let ascii_map: AsciiMap = content.parse().unwrap();
+            assert!(
+                ascii_map.penguin_spawn_positions().len() >= 4,
+                "{} does not have enough spawn points",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::fs;
+
+use bevy::{ecs as bevy_ecs, prelude::*};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/*
+The timings and stats that make up the feel of a match. A ruleset file only needs to list the
+values that differ from the classic rules, see `assets/rules/` for the bundled presets. Files
+placed in `local/rules/` are offered as presets too and replace the bundled ones of the same name.
+*/
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct GameRules {
+    pub bomb_fuse_secs: f32,
+    pub fire_duration_secs: f32,
+    pub immortality_secs: f32,
+    pub crumbling_secs: f32,
+    pub burning_item_secs: f32,
+    // counted from the start of a battle round
+    pub wall_of_death_start_secs: f32,
+    // overrides the wall drop chance of the loot tables
+    pub item_spawn_chance: Option<f32>,
+    // what the penguins start a battle round with, story mode has its own progression
+    pub starting_bombs: usize,
+    pub starting_bomb_range: usize,
+    // whether the bombs of teammates hurt each other
+    pub friendly_fire: bool,
+    // what closes in on the penguins once the wall of death starts
+    pub sudden_death: SuddenDeathKind,
+    // whether eliminated battle players come back as ghosts throwing bombs from the map edge
+    pub revenge_ghosts: bool,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            bomb_fuse_secs: 2.0,
+            fire_duration_secs: 0.5,
+            immortality_secs: 2.0,
+            crumbling_secs: 0.5,
+            burning_item_secs: 0.5,
+            wall_of_death_start_secs: 60.0,
+            item_spawn_chance: None,
+            starting_bombs: 1,
+            starting_bomb_range: 2,
+            friendly_fire: true,
+            sudden_death: SuddenDeathKind::Spiral,
+            revenge_ghosts: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SuddenDeathKind {
+    // a wall spiralling inwards clockwise
+    Spiral,
+    // walls closing in from all sides at once
+    ShrinkingRing,
+    // bombs falling on random tiles
+    BombRain,
+    // fire closing in from all sides at once
+    SpreadingFire,
+    // everyone goes down in one hit and has bombs with the maximum range
+    OneHit,
+}
+
+impl SuddenDeathKind {
+    pub const LIST: [Self; 5] = [
+        Self::Spiral,
+        Self::ShrinkingRing,
+        Self::BombRain,
+        Self::SpreadingFire,
+        Self::OneHit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Spiral => "SPIRAL",
+            Self::ShrinkingRing => "SHRINKING RING",
+            Self::BombRain => "BOMB RAIN",
+            Self::SpreadingFire => "SPREADING FIRE",
+            Self::OneHit => "ONE HIT",
+        }
+    }
+}
+
+// named rule presets
+#[derive(Resource)]
+pub struct RulesetLibrary(Vec<(String, GameRules)>);
+
+impl Default for RulesetLibrary {
+    fn default() -> Self {
+        let mut ruleset_library = Self(
+            Self::BUNDLED_RULESETS
+                .iter()
+                .map(|(name, content)| (name.to_string(), serde_json::from_str(content).unwrap()))
+                .collect(),
+        );
+
+        if let Ok(entries) = fs::read_dir(Self::RULESETS_DIRECTORY_PATH) {
+            for path in entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().map_or(false, |e| e == "json"))
+                .sorted()
+            {
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                match fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))
+                {
+                    Ok(game_rules) => ruleset_library.insert(name, game_rules),
+                    Err(e) => eprintln!("Cannot load ruleset {}. Error: {}", path.display(), e),
+                }
+            }
+        }
+
+        ruleset_library
+    }
+}
+
+impl RulesetLibrary {
+    const RULESETS_DIRECTORY_PATH: &'static str = "local/rules";
+    const BUNDLED_RULESETS: [(&'static str, &'static str); 3] = [
+        ("classic", include_str!("../../assets/rules/classic.json")),
+        ("fast", include_str!("../../assets/rules/fast.json")),
+        ("chaotic", include_str!("../../assets/rules/chaotic.json")),
+    ];
+
+    pub fn get(&self, name: &str) -> Option<&GameRules> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, r)| r)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &GameRules)> {
+        self.0.iter().map(|(n, r)| (n.as_str(), r))
+    }
+
+    // rulesets with the same name are replaced
+    pub fn insert(&mut self, name: String, game_rules: GameRules) {
+        if let Some(entry) = self.0.iter_mut().find(|(n, _)| *n == name) {
+            entry.1 = game_rules;
+        } else {
+            self.0.push((name, game_rules));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_rulesets() {
+        for (name, content) in RulesetLibrary::BUNDLED_RULESETS {
+            assert!(
+                serde_json::from_str::<GameRules>(content).is_ok(),
+                "invalid ruleset: {}",
+                name
+            );
+        }
+
+        let classic: GameRules =
+            serde_json::from_str(RulesetLibrary::BUNDLED_RULESETS[0].1).unwrap();
+        assert_eq!(classic, GameRules::default());
+    }
+
+    #[test]
+    fn test_partial_ruleset() {
+        let game_rules: GameRules =
+            serde_json::from_str(r#"{"bomb_fuse_secs": 1.0, "item_spawn_chance": 0.5}"#).unwrap();
+
+        assert_eq!(game_rules.bomb_fuse_secs, 1.0);
+        assert_eq!(game_rules.item_spawn_chance, Some(0.5));
+        assert_eq!(
+            game_rules.fire_duration_secs,
+            GameRules::default().fire_duration_secs
+        );
+    }
+}
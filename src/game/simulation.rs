@@ -0,0 +1,1355 @@
+use std::time::Duration;
+
+use bevy::{
+    time::{Timer, TimerMode},
+    utils::{HashMap, HashSet},
+};
+use rand::{prelude::IteratorRandom, rngs::StdRng, SeedableRng};
+
+use super::{
+    components::{Airborne, BombSatchel, Health, Item, Position},
+    constants::*,
+    loot_tables::{LootSource, LootTables, MatchLoot},
+    resources::MapSize,
+    rules::GameRules,
+    types::{BombKind, Cooldown, Direction, PlayerAction, Power, Upgrade},
+    utils::get_stone_wall_spawn_groups,
+};
+
+// A renderer-free version of the core game rules. Everything is plain data, so a match can be
+// stepped (and tested) without a window, textures or a Bevy schedule. The rule functions below
+// are what the Bevy systems in `game::systems` call as well, so the two can't drift apart.
+
+/// Returns the positions a bomb's flames spread to, along with the fireproof positions that
+/// stopped the flames (and that should receive a burn). Pierce bomb flames spread through the
+/// pierceable positions instead of stopping at them.
+pub fn get_flame_positions(
+    origin: Position,
+    range: usize,
+    kind: BombKind,
+    fireproof_positions: &HashSet<Position>,
+    pierceable_positions: &HashSet<Position>,
+) -> (Vec<Position>, Vec<Position>) {
+    let mut fire_positions = vec![origin];
+    let mut burned_positions = vec![];
+
+    for direction in Direction::LIST {
+        for i in 1..=range {
+            let position = origin.offset(direction, i);
+
+            if kind == BombKind::Pierce && pierceable_positions.contains(&position) {
+                fire_positions.push(position);
+                continue;
+            }
+
+            if fireproof_positions.contains(&position) {
+                burned_positions.push(position);
+                break;
+            }
+
+            fire_positions.push(position);
+        }
+    }
+
+    (fire_positions, burned_positions)
+}
+
+/// Returns where a line bomb drop puts the bombs: in a row in front of the player, up to the
+/// first position a bomb can't be dropped on.
+pub fn get_line_bomb_positions(
+    origin: Position,
+    direction: Direction,
+    bomb_count: usize,
+    invalid_positions: &HashSet<Position>,
+) -> Vec<Position> {
+    (1..)
+        .map(|i| origin.offset(direction, i))
+        .take_while(|p| !invalid_positions.contains(p))
+        .take(bomb_count)
+        .collect()
+}
+
+/// Carries an airborne bomb one tile further, wrapping around the map edges. Returns whether
+/// the bomb landed. Bombs coming down on an occupied tile bounce one tile further.
+pub fn advance_airborne_bomb(
+    airborne: &mut Airborne,
+    position: &mut Position,
+    map_size: MapSize,
+    impassable_positions: &HashSet<Position>,
+) -> bool {
+    let new_position = position.offset(airborne.direction, 1);
+    *position = Position {
+        y: new_position.y.rem_euclid(map_size.rows as isize),
+        x: new_position.x.rem_euclid(map_size.columns as isize),
+    };
+    airborne.distance_travelled += 1;
+
+    if airborne.distance_travelled < airborne.distance {
+        return false;
+    }
+
+    if impassable_positions.contains(position) {
+        airborne.distance += 1;
+        return false;
+    }
+
+    true
+}
+
+/// What stands on the tile a player is trying to move onto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Obstacle {
+    Wall,
+    DestructibleWall,
+    Bomb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveOutcome {
+    Moved,
+    PushedBomb,
+    Blocked,
+}
+
+/// Decides how a move towards an obstacle goes. Wall hack holders walk through destructible
+/// walls and bomb push holders send the bomb in front of them sliding, staying put themselves.
+pub fn resolve_move(obstacle: Option<Obstacle>, wall_hack: bool, bomb_push: bool) -> MoveOutcome {
+    match obstacle {
+        None => MoveOutcome::Moved,
+        Some(Obstacle::DestructibleWall) if wall_hack => MoveOutcome::Moved,
+        Some(Obstacle::Bomb) if bomb_push => MoveOutcome::PushedBomb,
+        Some(_) => MoveOutcome::Blocked,
+    }
+}
+
+/// Returns where a press of the bomb key drops bombs: underneath the player, or, for line bomb
+/// holders standing on a bomb, the rest of the satchel in a row in front of them.
+pub fn get_bomb_drop_positions(
+    position: Position,
+    facing: Direction,
+    bomb_satchel: &BombSatchel,
+    line_bomb: bool,
+    bomb_positions: &HashSet<Position>,
+    invalid_positions: &HashSet<Position>,
+) -> Vec<Position> {
+    if bomb_satchel.bombs_available == 0 {
+        vec![]
+    } else if !invalid_positions.contains(&position) {
+        vec![position]
+    } else if line_bomb && bomb_positions.contains(&position) {
+        get_line_bomb_positions(
+            position,
+            facing,
+            bomb_satchel.bombs_available,
+            invalid_positions,
+        )
+    } else {
+        vec![]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteDetonation {
+    AllBombs,
+    AllButUnderneath,
+}
+
+/// Decides which of a remote detonator holder's bombs an action sets off. The detonate key sets
+/// off all of them. The bomb key does so once the satchel is empty, or when pressed on one of
+/// their bombs, but spares the bomb underneath the player so they don't blow themselves up.
+pub fn get_remote_detonation(
+    action: PlayerAction,
+    bomb_satchel: &BombSatchel,
+    on_own_bomb: bool,
+) -> Option<RemoteDetonation> {
+    match action {
+        PlayerAction::Detonate => Some(RemoteDetonation::AllBombs),
+        PlayerAction::DropBomb if bomb_satchel.bombs_available == 0 || on_own_bomb => {
+            Some(RemoteDetonation::AllButUnderneath)
+        }
+        PlayerAction::DropBomb | PlayerAction::Move(_) | PlayerAction::Punch => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageOutcome {
+    Hurt,
+    LifeLost,
+    Died,
+}
+
+/// Takes a point of health, and a life once the health runs out. Players with lives left come
+/// back with full health.
+pub fn take_damage(health: &mut Health) -> DamageOutcome {
+    health.health -= 1;
+    if health.health > 0 {
+        return DamageOutcome::Hurt;
+    }
+
+    health.lives -= 1;
+    if health.lives == 0 {
+        return DamageOutcome::Died;
+    }
+
+    health.health = health.max_health;
+    DamageOutcome::LifeLost
+}
+
+/// Applies an item's upgrade to the player, or returns the power it grants.
+pub fn apply_item(
+    item: Item,
+    health: &mut Health,
+    bomb_satchel: &mut BombSatchel,
+) -> Option<Power> {
+    match item {
+        Item::Upgrade(Upgrade::BombsUp) => bomb_satchel.bombs_available += 1,
+        Item::Upgrade(Upgrade::RangeUp) => bomb_satchel.bomb_range += 1,
+        Item::Upgrade(Upgrade::LivesUp) => health.lives += 1,
+        Item::Power(power) => return Some(power),
+    }
+
+    None
+}
+
+pub struct SimPlayer {
+    pub id: usize,
+    pub team: usize,
+    pub position: Position,
+    pub spawn_position: Position,
+    pub health: Health,
+    pub bomb_satchel: BombSatchel,
+    pub wall_hack: bool,
+    pub bomb_push: bool,
+    pub remote_detonator: bool,
+    pub glove: bool,
+    pub pierce_bomb: bool,
+    pub line_bomb: bool,
+    pub facing: Direction,
+    pub immortal: Option<Timer>,
+    pub move_cooldown: Option<Cooldown>,
+}
+
+impl SimPlayer {
+    // same stats as a freshly spawned battle mode penguin
+    pub fn new(id: usize, position: Position) -> Self {
+        Self {
+            id,
+            team: id,
+            position,
+            spawn_position: position,
+            health: Health {
+                lives: 1,
+                max_health: 1,
+                health: 1,
+            },
+            bomb_satchel: BombSatchel {
+                bombs_available: 1,
+                bomb_range: 2,
+            },
+            wall_hack: false,
+            bomb_push: false,
+            remote_detonator: false,
+            glove: false,
+            pierce_bomb: false,
+            line_bomb: false,
+            facing: Direction::Right,
+            immortal: None,
+            move_cooldown: None,
+        }
+    }
+
+    fn grant_power(&mut self, power: Power, rules: &GameRules) {
+        match power {
+            Power::Immortal => {
+                self.immortal = Some(Timer::from_seconds(rules.immortality_secs, TimerMode::Once));
+            }
+            Power::WallHack => self.wall_hack = true,
+            Power::BombPush => self.bomb_push = true,
+            Power::RemoteDetonator => self.remote_detonator = true,
+            Power::Glove => self.glove = true,
+            Power::PierceBomb => self.pierce_bomb = true,
+            Power::LineBomb => self.line_bomb = true,
+        }
+    }
+}
+
+pub struct SimBomb {
+    pub owner: Option<usize>,
+    pub position: Position,
+    pub range: usize,
+    pub kind: BombKind,
+    pub timer: Timer,
+    pub moving: Option<(Direction, Cooldown)>,
+    pub airborne: Option<(Airborne, Cooldown)>,
+}
+
+pub struct SimFire {
+    pub position: Position,
+    pub timer: Timer,
+}
+
+pub struct PlayerInput {
+    pub player: usize,
+    pub action: PlayerAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimEvent {
+    BombDropped {
+        player: usize,
+        position: Position,
+    },
+    BombExploded {
+        owner: Option<usize>,
+        position: Position,
+    },
+    WallDestroyed {
+        position: Position,
+    },
+    ItemSpawned {
+        item: Item,
+        position: Position,
+    },
+    ItemPickedUp {
+        player: usize,
+        item: Item,
+    },
+    PlayerDamaged {
+        player: usize,
+    },
+    PlayerDied {
+        player: usize,
+    },
+}
+
+pub struct GameState {
+    pub map_size: MapSize,
+    pub stone_walls: HashSet<Position>,
+    pub destructible_walls: HashSet<Position>,
+    pub crumbling_walls: HashMap<Position, Timer>,
+    pub players: Vec<SimPlayer>,
+    pub bombs: Vec<SimBomb>,
+    pub fires: Vec<SimFire>,
+    pub items: Vec<(Position, Item)>,
+    pub burning_items: Vec<(Position, Timer)>,
+    pub rules: GameRules,
+    pub loot: MatchLoot,
+    rng: StdRng,
+}
+
+impl GameState {
+    // starts with the outer walls and the checkered middle, like the regular game modes
+    pub fn new(map_size: MapSize, seed: u64, rules: GameRules) -> Self {
+        Self {
+            map_size,
+            stone_walls: get_stone_wall_spawn_groups(map_size, true)
+                .into_iter()
+                .flatten()
+                .collect(),
+            destructible_walls: HashSet::default(),
+            crumbling_walls: HashMap::default(),
+            players: vec![],
+            bombs: vec![],
+            fires: vec![],
+            items: vec![],
+            burning_items: vec![],
+            loot: {
+                let loot_tables = LootTables::bundled();
+                MatchLoot::new(&loot_tables, &loot_tables.story, &rules)
+            },
+            rules,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn player(&self, id: usize) -> Option<&SimPlayer> {
+        self.players.iter().find(|p| p.id == id)
+    }
+
+    pub fn step(&mut self, inputs: &[PlayerInput], dt: Duration) -> Vec<SimEvent> {
+        let mut events = vec![];
+
+        self.tick(dt, &mut events);
+        self.move_bombs();
+        self.move_players(inputs);
+        self.detonate_bombs(inputs);
+        self.throw_bombs(inputs);
+        self.drop_bombs(inputs, &mut events);
+        let burned_positions = self.explode_bombs(&mut events);
+        self.burn(burned_positions, &mut events);
+        self.pick_up_items(&mut events);
+
+        events
+    }
+
+    fn wall_positions(&self) -> HashSet<Position> {
+        self.stone_walls
+            .union(&self.destructible_walls)
+            .copied()
+            .collect()
+    }
+
+    // airborne bombs don't block anything
+    fn grounded_bombs(&self) -> impl Iterator<Item = &SimBomb> {
+        self.bombs.iter().filter(|b| b.airborne.is_none())
+    }
+
+    fn tick(&mut self, dt: Duration, events: &mut Vec<SimEvent>) {
+        for player in self.players.iter_mut() {
+            if let Some(move_cooldown) = player.move_cooldown.as_mut() {
+                move_cooldown.tick(dt);
+            }
+
+            if let Some(immortal) = player.immortal.as_mut() {
+                immortal.tick(dt);
+                if immortal.finished() {
+                    player.immortal = None;
+                }
+            }
+        }
+
+        let remote_detonator_holders: HashSet<usize> = self
+            .players
+            .iter()
+            .filter(|p| p.remote_detonator)
+            .map(|p| p.id)
+            .collect();
+        let shortened_fuse_duration = Duration::from_secs_f32(SHORTENED_FUSE_DURATION_SECS);
+        for bomb in self.bombs.iter_mut() {
+            if let Some((_, move_cooldown)) = bomb.airborne.as_mut() {
+                move_cooldown.tick(dt);
+                continue;
+            }
+
+            // remote controlled bombs wait for their owner, unless the fire got to them first
+            if !matches!(bomb.owner, Some(owner) if remote_detonator_holders.contains(&owner))
+                || bomb.timer.duration() <= shortened_fuse_duration
+            {
+                bomb.timer.tick(dt);
+            }
+            if let Some((_, move_cooldown)) = bomb.moving.as_mut() {
+                move_cooldown.tick(dt);
+            }
+        }
+
+        self.fires.retain_mut(|fire| {
+            fire.timer.tick(dt);
+            !fire.timer.finished()
+        });
+
+        self.burning_items.retain_mut(|(_, timer)| {
+            timer.tick(dt);
+            !timer.finished()
+        });
+
+        let mut crumbled_positions = vec![];
+        self.crumbling_walls.retain(|position, timer| {
+            timer.tick(dt);
+            if timer.finished() {
+                crumbled_positions.push(*position);
+            }
+            !timer.finished()
+        });
+        // keep the RNG draws in a stable order
+        crumbled_positions.sort_by_key(|p| (p.y, p.x));
+
+        for position in crumbled_positions {
+            self.destructible_walls.remove(&position);
+            events.push(SimEvent::WallDestroyed { position });
+
+            if let Some(item) = self.loot.roll(LootSource::Wall, &mut self.rng) {
+                self.items.push((position, item));
+                events.push(SimEvent::ItemSpawned { item, position });
+            }
+        }
+    }
+
+    fn move_bombs(&mut self) {
+        for i in 0..self.bombs.len() {
+            let ready = matches!(self.bombs[i].moving, Some((_, ref c)) if c.ready())
+                || matches!(self.bombs[i].airborne, Some((_, ref c)) if c.ready());
+            if !ready {
+                continue;
+            }
+
+            let mut impassable_positions = self.wall_positions();
+            impassable_positions.extend(self.grounded_bombs().map(|b| b.position));
+            impassable_positions.extend(self.items.iter().map(|(p, _)| *p));
+            impassable_positions.extend(self.players.iter().map(|p| p.position));
+
+            let map_size = self.map_size;
+            let bomb = &mut self.bombs[i];
+            if let Some((airborne, move_cooldown)) = bomb.airborne.as_mut() {
+                if advance_airborne_bomb(
+                    airborne,
+                    &mut bomb.position,
+                    map_size,
+                    &impassable_positions,
+                ) {
+                    bomb.airborne = None;
+                } else {
+                    move_cooldown.trigger();
+                }
+                continue;
+            }
+
+            let (direction, move_cooldown) = bomb.moving.as_mut().unwrap();
+            let new_position = bomb.position.offset(*direction, 1);
+            if !impassable_positions.contains(&new_position) {
+                bomb.position = new_position;
+                move_cooldown.trigger();
+            } else {
+                bomb.moving = None;
+            }
+        }
+    }
+
+    fn move_players(&mut self, inputs: &[PlayerInput]) {
+        // position -> (obstacle, bomb index)
+        let mut solids: HashMap<Position, (Obstacle, Option<usize>)> = HashMap::default();
+        solids.extend(
+            self.stone_walls
+                .iter()
+                .map(|p| (*p, (Obstacle::Wall, None))),
+        );
+        solids.extend(
+            self.destructible_walls
+                .iter()
+                .map(|p| (*p, (Obstacle::DestructibleWall, None))),
+        );
+        solids.extend(
+            self.bombs
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| b.airborne.is_none())
+                .map(|(i, b)| (b.position, (Obstacle::Bomb, Some(i)))),
+        );
+
+        for (player_id, direction) in inputs.iter().filter_map(|i| {
+            if let PlayerAction::Move(direction) = i.action {
+                Some((i.player, direction))
+            } else {
+                None
+            }
+        }) {
+            if let Some(player) = self.players.iter_mut().find(|p| p.id == player_id) {
+                player.facing = direction;
+                if matches!(player.move_cooldown, Some(ref c) if !c.ready()) {
+                    continue;
+                }
+
+                let new_position = player.position.offset(direction, 1);
+                let solid = solids.get(&new_position);
+                match resolve_move(solid.map(|s| s.0), player.wall_hack, player.bomb_push) {
+                    MoveOutcome::Moved => {
+                        player.position = new_position;
+                        if let Some(move_cooldown) = player.move_cooldown.as_mut() {
+                            move_cooldown.trigger();
+                        }
+                    }
+                    MoveOutcome::PushedBomb => {
+                        if let Some((_, Some(bomb_index))) = solid {
+                            self.bombs[*bomb_index].moving = Some((
+                                direction,
+                                Cooldown::from_seconds(PUSHED_BOMB_MOVE_COOLDOWN_SECS),
+                            ));
+                        }
+                    }
+                    MoveOutcome::Blocked => (),
+                }
+            }
+        }
+    }
+
+    fn detonate_bombs(&mut self, inputs: &[PlayerInput]) {
+        for input in inputs {
+            let Some(player) = self.player(input.player).filter(|p| p.remote_detonator) else {
+                continue;
+            };
+            let position = player.position;
+            let on_own_bomb = self
+                .grounded_bombs()
+                .any(|b| b.owner == Some(input.player) && b.position == position);
+
+            if let Some(remote_detonation) =
+                get_remote_detonation(input.action, &player.bomb_satchel, on_own_bomb)
+            {
+                for bomb in self.bombs.iter_mut().filter(|b| {
+                    b.owner == Some(input.player)
+                        && b.airborne.is_none()
+                        && (remote_detonation == RemoteDetonation::AllBombs
+                            || b.position != position)
+                }) {
+                    let remaining = bomb.timer.remaining();
+                    bomb.timer.tick(remaining);
+                }
+            }
+        }
+    }
+
+    fn throw_bombs(&mut self, inputs: &[PlayerInput]) {
+        for player_id in inputs
+            .iter()
+            .filter(|i| matches!(i.action, PlayerAction::Punch))
+            .map(|i| i.player)
+        {
+            if let Some(player) = self.player(player_id).filter(|p| p.glove) {
+                let direction = player.facing;
+
+                // pick up the bomb underneath, otherwise punch the one in front
+                let target = [player.position, player.position.offset(direction, 1)]
+                    .into_iter()
+                    .find_map(|p| {
+                        self.bombs
+                            .iter()
+                            .position(|b| b.airborne.is_none() && b.position == p)
+                    });
+                if let Some(bomb_index) = target {
+                    let bomb = &mut self.bombs[bomb_index];
+                    bomb.moving = None;
+                    bomb.airborne = Some((
+                        Airborne::new(direction),
+                        Cooldown::from_seconds(AIRBORNE_BOMB_MOVE_COOLDOWN_SECS),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn drop_bombs(&mut self, inputs: &[PlayerInput], events: &mut Vec<SimEvent>) {
+        let mut bomb_positions: HashSet<Position> =
+            self.grounded_bombs().map(|b| b.position).collect();
+        let mut invalid_positions = self.wall_positions();
+        invalid_positions.extend(bomb_positions.iter().copied());
+        invalid_positions.extend(self.burning_items.iter().map(|(p, _)| *p));
+
+        for player_id in inputs
+            .iter()
+            .filter(|i| matches!(i.action, PlayerAction::DropBomb))
+            .map(|i| i.player)
+        {
+            if let Some(player) = self.players.iter_mut().find(|p| p.id == player_id) {
+                let drop_positions = get_bomb_drop_positions(
+                    player.position,
+                    player.facing,
+                    &player.bomb_satchel,
+                    player.line_bomb,
+                    &bomb_positions,
+                    &invalid_positions,
+                );
+
+                for position in drop_positions {
+                    player.bomb_satchel.bombs_available -= 1;
+                    bomb_positions.insert(position);
+                    invalid_positions.insert(position);
+
+                    self.bombs.push(SimBomb {
+                        owner: Some(player.id),
+                        position,
+                        range: player.bomb_satchel.bomb_range,
+                        kind: if player.pierce_bomb {
+                            BombKind::Pierce
+                        } else {
+                            BombKind::Regular
+                        },
+                        timer: Timer::from_seconds(self.rules.bomb_fuse_secs, TimerMode::Once),
+                        moving: None,
+                        airborne: None,
+                    });
+                    events.push(SimEvent::BombDropped {
+                        player: player.id,
+                        position,
+                    });
+                }
+            }
+        }
+    }
+
+    fn explode_bombs(&mut self, events: &mut Vec<SimEvent>) -> Vec<Position> {
+        let mut fireproof_positions = self.wall_positions();
+        fireproof_positions.extend(
+            self.grounded_bombs()
+                .filter(|b| !b.timer.finished())
+                .map(|b| b.position),
+        );
+
+        let (exploded_bombs, remaining_bombs): (Vec<SimBomb>, Vec<SimBomb>) =
+            self.bombs.drain(..).partition(|b| b.timer.finished());
+        self.bombs = remaining_bombs;
+
+        let mut burned_positions = vec![];
+        for bomb in exploded_bombs {
+            if let Some(owner) = bomb.owner {
+                if let Some(player) = self.players.iter_mut().find(|p| p.id == owner) {
+                    player.bomb_satchel.bombs_available += 1;
+                }
+            }
+
+            events.push(SimEvent::BombExploded {
+                owner: bomb.owner,
+                position: bomb.position,
+            });
+
+            let (fire_positions, burned) = get_flame_positions(
+                bomb.position,
+                bomb.range,
+                bomb.kind,
+                &fireproof_positions,
+                &self.destructible_walls,
+            );
+            self.fires
+                .extend(fire_positions.into_iter().map(|position| SimFire {
+                    position,
+                    timer: Timer::from_seconds(self.rules.fire_duration_secs, TimerMode::Once),
+                }));
+            burned_positions.extend(burned);
+        }
+
+        burned_positions
+    }
+
+    fn burn(&mut self, burned_positions: Vec<Position>, events: &mut Vec<SimEvent>) {
+        let burned_positions: HashSet<Position> = self
+            .fires
+            .iter()
+            .map(|f| f.position)
+            .chain(burned_positions)
+            .collect();
+        let wall_positions = self.wall_positions();
+
+        // players standing on walls are out of the fire's reach
+        let damaged_players: Vec<usize> = self
+            .players
+            .iter()
+            .filter(|p| {
+                p.immortal.is_none()
+                    && burned_positions.contains(&p.position)
+                    && !wall_positions.contains(&p.position)
+            })
+            .map(|p| p.id)
+            .collect();
+
+        for bomb in self
+            .bombs
+            .iter_mut()
+            .filter(|b| b.airborne.is_none() && burned_positions.contains(&b.position))
+        {
+            let shortened_fuse_duration = Duration::from_secs_f32(SHORTENED_FUSE_DURATION_SECS);
+            if bomb.timer.duration() - bomb.timer.elapsed() > shortened_fuse_duration {
+                bomb.timer.set_duration(shortened_fuse_duration);
+                bomb.timer.reset();
+            }
+        }
+
+        for position in self
+            .destructible_walls
+            .iter()
+            .filter(|p| burned_positions.contains(*p))
+        {
+            self.crumbling_walls
+                .entry(*position)
+                .or_insert_with(|| Timer::from_seconds(self.rules.crumbling_secs, TimerMode::Once));
+        }
+
+        let (burned_items, items): (Vec<_>, Vec<_>) = self
+            .items
+            .drain(..)
+            .partition(|(p, _)| burned_positions.contains(p));
+        self.items = items;
+        self.burning_items
+            .extend(burned_items.into_iter().map(|(position, _)| {
+                (
+                    position,
+                    Timer::from_seconds(self.rules.burning_item_secs, TimerMode::Once),
+                )
+            }));
+
+        self.damage_players(&damaged_players, events);
+    }
+
+    fn damage_players(&mut self, damaged_players: &[usize], events: &mut Vec<SimEvent>) {
+        let mut dead_players = vec![];
+
+        for player in self
+            .players
+            .iter_mut()
+            .filter(|p| damaged_players.contains(&p.id))
+        {
+            events.push(SimEvent::PlayerDamaged { player: player.id });
+
+            match take_damage(&mut player.health) {
+                DamageOutcome::Hurt => (),
+                DamageOutcome::Died => {
+                    dead_players.push(player.id);
+                    events.push(SimEvent::PlayerDied { player: player.id });
+                    continue;
+                }
+                DamageOutcome::LifeLost => {
+                    // return to spawn
+                    player.position = player.spawn_position;
+
+                    // lose powers
+                    player.wall_hack = false;
+                    player.bomb_push = false;
+                    player.remote_detonator = false;
+                    player.glove = false;
+                    player.pierce_bomb = false;
+                    player.line_bomb = false;
+                }
+            }
+
+            player.immortal = Some(Timer::from_seconds(
+                self.rules.immortality_secs,
+                TimerMode::Once,
+            ));
+        }
+
+        self.players.retain(|p| !dead_players.contains(&p.id));
+    }
+
+    fn pick_up_items(&mut self, events: &mut Vec<SimEvent>) {
+        let mut remaining_items = vec![];
+
+        for (position, item) in std::mem::take(&mut self.items) {
+            if let Some(player) = self
+                .players
+                .iter_mut()
+                .filter(|p| p.position == position)
+                .choose(&mut self.rng)
+            {
+                if let Some(power) = apply_item(item, &mut player.health, &mut player.bomb_satchel)
+                {
+                    player.grant_power(power, &self.rules);
+                }
+
+                events.push(SimEvent::ItemPickedUp {
+                    player: player.id,
+                    item,
+                });
+            } else {
+                remaining_items.push((position, item));
+            }
+        }
+
+        self.items = remaining_items;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_game_state() -> GameState {
+        GameState::new(
+            MapSize {
+                rows: 11,
+                columns: 15,
+            },
+            0,
+            GameRules::default(),
+        )
+    }
+
+    fn input(player: usize, action: PlayerAction) -> PlayerInput {
+        PlayerInput { player, action }
+    }
+
+    #[test]
+    fn test_bomb_explosion() {
+        let mut game_state = new_game_state();
+        game_state
+            .players
+            .push(SimPlayer::new(0, Position { y: 1, x: 1 }));
+
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        assert_eq!(game_state.bombs.len(), 1);
+        assert_eq!(
+            game_state.player(0).unwrap().bomb_satchel.bombs_available,
+            0
+        );
+
+        // if the player runs out of the bomb's range...
+        for _ in 0..3 {
+            game_state.step(
+                &[input(0, PlayerAction::Move(Direction::Right))],
+                Duration::ZERO,
+            );
+        }
+        assert_eq!(
+            game_state.player(0).unwrap().position,
+            Position { y: 1, x: 4 }
+        );
+
+        // ...they should survive the explosion and get their bomb back
+        let events = game_state.step(
+            &[],
+            Duration::from_secs_f32(game_state.rules.bomb_fuse_secs),
+        );
+        assert!(events.contains(&SimEvent::BombExploded {
+            owner: Some(0),
+            position: Position { y: 1, x: 1 }
+        }));
+        assert!(game_state.bombs.is_empty());
+        assert_eq!(
+            game_state.player(0).unwrap().bomb_satchel.bombs_available,
+            1
+        );
+
+        // the stone walls should stop the flames
+        let fire_positions: HashSet<Position> =
+            game_state.fires.iter().map(|f| f.position).collect();
+        assert_eq!(
+            fire_positions,
+            [(1, 1), (1, 2), (1, 3), (2, 1), (3, 1)]
+                .into_iter()
+                .map(|(y, x)| Position { y, x })
+                .collect()
+        );
+    }
+
+    #[test]
+    fn test_player_death() {
+        let mut game_state = new_game_state();
+        game_state
+            .players
+            .push(SimPlayer::new(0, Position { y: 1, x: 1 }));
+
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+
+        // if the player stays on their bomb...
+        let events = game_state.step(
+            &[],
+            Duration::from_secs_f32(game_state.rules.bomb_fuse_secs),
+        );
+
+        // ...they should die
+        assert!(events.contains(&SimEvent::PlayerDied { player: 0 }));
+        assert!(game_state.players.is_empty());
+    }
+
+    #[test]
+    fn test_extra_life() {
+        let mut game_state = new_game_state();
+        let mut player = SimPlayer::new(0, Position { y: 1, x: 1 });
+        player.health.lives = 2;
+        player.spawn_position = Position { y: 9, x: 13 };
+        game_state.players.push(player);
+
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        let events = game_state.step(
+            &[],
+            Duration::from_secs_f32(game_state.rules.bomb_fuse_secs),
+        );
+
+        // a player with an extra life should lose it and return to spawn as immortal
+        assert!(events.contains(&SimEvent::PlayerDamaged { player: 0 }));
+        let player = game_state.player(0).unwrap();
+        assert_eq!(player.health.lives, 1);
+        assert_eq!(player.position, Position { y: 9, x: 13 });
+        assert!(player.immortal.is_some());
+    }
+
+    #[test]
+    fn test_destructible_wall_burn() {
+        let mut game_state = new_game_state();
+        let mut player = SimPlayer::new(0, Position { y: 1, x: 1 });
+        player.bomb_satchel.bomb_range = 3;
+        game_state.players.push(player);
+        game_state
+            .destructible_walls
+            .insert(Position { y: 1, x: 3 });
+
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        game_state.step(
+            &[input(0, PlayerAction::Move(Direction::Down))],
+            Duration::ZERO,
+        );
+        game_state.step(
+            &[input(0, PlayerAction::Move(Direction::Down))],
+            Duration::ZERO,
+        );
+        game_state.step(
+            &[input(0, PlayerAction::Move(Direction::Down))],
+            Duration::ZERO,
+        );
+        game_state.step(
+            &[input(0, PlayerAction::Move(Direction::Right))],
+            Duration::ZERO,
+        );
+        game_state.step(
+            &[],
+            Duration::from_secs_f32(game_state.rules.bomb_fuse_secs),
+        );
+
+        // the flames should stop at the destructible wall, which starts crumbling
+        assert!(!game_state
+            .fires
+            .iter()
+            .any(|f| f.position == Position { y: 1, x: 4 }));
+        assert!(game_state
+            .crumbling_walls
+            .contains_key(&Position { y: 1, x: 3 }));
+
+        // after a while the wall should be gone
+        let events = game_state.step(
+            &[],
+            Duration::from_secs_f32(game_state.rules.crumbling_secs),
+        );
+        assert!(events.contains(&SimEvent::WallDestroyed {
+            position: Position { y: 1, x: 3 }
+        }));
+        assert!(game_state.destructible_walls.is_empty());
+    }
+
+    #[test]
+    fn test_pierce_bomb() {
+        let mut game_state = new_game_state();
+        let mut player = SimPlayer::new(0, Position { y: 1, x: 1 });
+        player.bomb_satchel.bomb_range = 3;
+        player.pierce_bomb = true;
+        game_state.players.push(player);
+        game_state
+            .destructible_walls
+            .extend([Position { y: 1, x: 2 }, Position { y: 1, x: 3 }]);
+
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        for direction in [Direction::Down, Direction::Down, Direction::Right] {
+            game_state.step(&[input(0, PlayerAction::Move(direction))], Duration::ZERO);
+        }
+        game_state.step(
+            &[],
+            Duration::from_secs_f32(game_state.rules.bomb_fuse_secs),
+        );
+
+        // the flames should go through both walls
+        assert!(game_state
+            .fires
+            .iter()
+            .any(|f| f.position == Position { y: 1, x: 4 }));
+        assert!(game_state
+            .crumbling_walls
+            .contains_key(&Position { y: 1, x: 2 }));
+        assert!(game_state
+            .crumbling_walls
+            .contains_key(&Position { y: 1, x: 3 }));
+    }
+
+    #[test]
+    fn test_line_bomb() {
+        let mut game_state = new_game_state();
+        let mut player = SimPlayer::new(0, Position { y: 1, x: 1 });
+        player.bomb_satchel.bombs_available = 4;
+        player.line_bomb = true;
+        game_state.players.push(player);
+        game_state
+            .destructible_walls
+            .insert(Position { y: 1, x: 4 });
+
+        // the second press drops the rest of the bombs in front, up to the wall
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        let events = game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        assert_eq!(
+            events,
+            vec![
+                SimEvent::BombDropped {
+                    player: 0,
+                    position: Position { y: 1, x: 2 }
+                },
+                SimEvent::BombDropped {
+                    player: 0,
+                    position: Position { y: 1, x: 3 }
+                },
+            ]
+        );
+        assert_eq!(
+            game_state.player(0).unwrap().bomb_satchel.bombs_available,
+            1
+        );
+    }
+
+    #[test]
+    fn test_remote_detonator() {
+        let mut game_state = new_game_state();
+        let mut player = SimPlayer::new(0, Position { y: 1, x: 1 });
+        player.remote_detonator = true;
+        game_state.players.push(player);
+
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        for _ in 0..3 {
+            game_state.step(
+                &[input(0, PlayerAction::Move(Direction::Right))],
+                Duration::ZERO,
+            );
+        }
+
+        // the bomb shouldn't go off on its own...
+        game_state.step(
+            &[],
+            Duration::from_secs_f32(game_state.rules.bomb_fuse_secs * 2.0),
+        );
+        assert_eq!(game_state.bombs.len(), 1);
+
+        // ...but on the second press of the bomb key, with the satchel empty
+        let events = game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        assert!(events.contains(&SimEvent::BombExploded {
+            owner: Some(0),
+            position: Position { y: 1, x: 1 }
+        }));
+        assert_eq!(
+            game_state.player(0).unwrap().bomb_satchel.bombs_available,
+            1
+        );
+    }
+
+    #[test]
+    fn test_glove_throw() {
+        let mut game_state = new_game_state();
+        let mut player = SimPlayer::new(0, Position { y: 1, x: 1 });
+        player.glove = true;
+        game_state.players.push(player);
+
+        // thrown from underneath the player, over the stone wall
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        game_state.step(&[input(0, PlayerAction::Punch)], Duration::ZERO);
+        for _ in 0..BOMB_THROW_DISTANCE {
+            game_state.step(
+                &[],
+                Duration::from_secs_f32(AIRBORNE_BOMB_MOVE_COOLDOWN_SECS),
+            );
+        }
+        assert_eq!(game_state.bombs[0].position, Position { y: 1, x: 4 });
+        assert!(game_state.bombs[0].airborne.is_none());
+
+        // punched from the side, bouncing off a player and the outer walls around the map edge
+        game_state.players[0].position = Position { y: 1, x: 5 };
+        game_state
+            .players
+            .push(SimPlayer::new(1, Position { y: 1, x: 1 }));
+        game_state.step(
+            &[input(0, PlayerAction::Move(Direction::Left))],
+            Duration::ZERO,
+        );
+        game_state.step(&[input(0, PlayerAction::Punch)], Duration::ZERO);
+        for _ in 0..BOMB_THROW_DISTANCE + 3 {
+            game_state.step(
+                &[],
+                Duration::from_secs_f32(AIRBORNE_BOMB_MOVE_COOLDOWN_SECS),
+            );
+        }
+        assert_eq!(game_state.bombs[0].position, Position { y: 1, x: 13 });
+        assert!(game_state.bombs[0].airborne.is_none());
+    }
+
+    #[test]
+    fn test_pick_up_item() {
+        let mut game_state = new_game_state();
+        game_state
+            .players
+            .push(SimPlayer::new(0, Position { y: 1, x: 1 }));
+        game_state
+            .items
+            .push((Position { y: 1, x: 2 }, Item::Upgrade(Upgrade::BombsUp)));
+
+        let events = game_state.step(
+            &[input(0, PlayerAction::Move(Direction::Right))],
+            Duration::ZERO,
+        );
+
+        assert!(events.contains(&SimEvent::ItemPickedUp {
+            player: 0,
+            item: Item::Upgrade(Upgrade::BombsUp)
+        }));
+        assert!(game_state.items.is_empty());
+        assert_eq!(
+            game_state.player(0).unwrap().bomb_satchel.bombs_available,
+            2
+        );
+    }
+
+    #[test]
+    fn test_pierce_bomb_flames() {
+        let origin = Position { y: 1, x: 1 };
+        let destructible_wall = Position { y: 1, x: 2 };
+        let fireproof_positions = HashSet::from([destructible_wall]);
+        let pierceable_positions = HashSet::from([destructible_wall]);
+
+        let (fire_positions, burned_positions) = get_flame_positions(
+            origin,
+            2,
+            BombKind::Regular,
+            &fireproof_positions,
+            &pierceable_positions,
+        );
+        assert!(!fire_positions.contains(&destructible_wall));
+        assert_eq!(burned_positions, vec![destructible_wall]);
+
+        let (fire_positions, burned_positions) = get_flame_positions(
+            origin,
+            2,
+            BombKind::Pierce,
+            &fireproof_positions,
+            &pierceable_positions,
+        );
+        assert!(fire_positions.contains(&destructible_wall));
+        assert!(fire_positions.contains(&Position { y: 1, x: 3 }));
+        assert!(burned_positions.is_empty());
+    }
+
+    #[test]
+    fn test_line_bomb_positions() {
+        let origin = Position { y: 1, x: 1 };
+        let invalid_positions = HashSet::from([Position { y: 1, x: 4 }]);
+
+        assert_eq!(
+            get_line_bomb_positions(origin, Direction::Right, 5, &invalid_positions),
+            vec![Position { y: 1, x: 2 }, Position { y: 1, x: 3 }]
+        );
+        assert_eq!(
+            get_line_bomb_positions(origin, Direction::Down, 2, &invalid_positions),
+            vec![Position { y: 2, x: 1 }, Position { y: 3, x: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_airborne_bomb() {
+        let map_size = MapSize {
+            rows: 11,
+            columns: 15,
+        };
+        let mut airborne = Airborne {
+            direction: Direction::Left,
+            distance: 2,
+            distance_travelled: 0,
+        };
+        let mut position = Position { y: 1, x: 1 };
+        let impassable_positions = HashSet::from([Position { y: 1, x: 14 }]);
+
+        // wraps around the map edge...
+        assert!(!advance_airborne_bomb(
+            &mut airborne,
+            &mut position,
+            map_size,
+            &impassable_positions
+        ));
+        assert_eq!(position, Position { y: 1, x: 0 });
+
+        // ...and bounces off the occupied tile it comes down on
+        assert!(!advance_airborne_bomb(
+            &mut airborne,
+            &mut position,
+            map_size,
+            &impassable_positions
+        ));
+        assert_eq!(position, Position { y: 1, x: 14 });
+        assert!(advance_airborne_bomb(
+            &mut airborne,
+            &mut position,
+            map_size,
+            &impassable_positions
+        ));
+        assert_eq!(position, Position { y: 1, x: 13 });
+    }
+
+    #[test]
+    fn test_resolve_move() {
+        assert_eq!(resolve_move(None, false, false), MoveOutcome::Moved);
+        assert_eq!(
+            resolve_move(Some(Obstacle::DestructibleWall), false, false),
+            MoveOutcome::Blocked
+        );
+        assert_eq!(
+            resolve_move(Some(Obstacle::DestructibleWall), true, false),
+            MoveOutcome::Moved
+        );
+        assert_eq!(
+            resolve_move(Some(Obstacle::Wall), true, true),
+            MoveOutcome::Blocked
+        );
+        assert_eq!(
+            resolve_move(Some(Obstacle::Bomb), true, false),
+            MoveOutcome::Blocked
+        );
+        assert_eq!(
+            resolve_move(Some(Obstacle::Bomb), false, true),
+            MoveOutcome::PushedBomb
+        );
+    }
+
+    #[test]
+    fn test_take_damage() {
+        let mut health = Health {
+            lives: 2,
+            max_health: 2,
+            health: 2,
+        };
+
+        assert_eq!(take_damage(&mut health), DamageOutcome::Hurt);
+        assert_eq!(take_damage(&mut health), DamageOutcome::LifeLost);
+        assert_eq!((health.lives, health.health), (1, 2));
+        assert_eq!(take_damage(&mut health), DamageOutcome::Hurt);
+        assert_eq!(take_damage(&mut health), DamageOutcome::Died);
+    }
+
+    #[test]
+    fn test_bomb_push() {
+        let mut game_state = new_game_state();
+        let mut player = SimPlayer::new(0, Position { y: 1, x: 3 });
+        player.bomb_push = true;
+        game_state.players.push(player);
+        game_state
+            .destructible_walls
+            .insert(Position { y: 1, x: 6 });
+
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        game_state.step(
+            &[input(0, PlayerAction::Move(Direction::Left))],
+            Duration::ZERO,
+        );
+        game_state.step(
+            &[input(0, PlayerAction::Move(Direction::Right))],
+            Duration::ZERO,
+        );
+
+        // the player stays put and the bomb slides up to the wall
+        assert_eq!(
+            game_state.player(0).unwrap().position,
+            Position { y: 1, x: 2 }
+        );
+        for _ in 0..3 {
+            game_state.step(&[], Duration::from_secs_f32(PUSHED_BOMB_MOVE_COOLDOWN_SECS));
+        }
+        assert_eq!(game_state.bombs[0].position, Position { y: 1, x: 5 });
+        assert!(game_state.bombs[0].moving.is_none());
+    }
+
+    #[test]
+    fn test_remote_detonation_spares_the_bomb_underneath() {
+        let mut game_state = new_game_state();
+        let mut player = SimPlayer::new(0, Position { y: 1, x: 1 });
+        player.remote_detonator = true;
+        player.bomb_satchel.bombs_available = 2;
+        game_state.players.push(player);
+
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        for _ in 0..3 {
+            game_state.step(
+                &[input(0, PlayerAction::Move(Direction::Right))],
+                Duration::ZERO,
+            );
+        }
+        game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+
+        // pressing the bomb key on the second bomb sets off the first one...
+        let events = game_state.step(&[input(0, PlayerAction::DropBomb)], Duration::ZERO);
+        assert!(events.contains(&SimEvent::BombExploded {
+            owner: Some(0),
+            position: Position { y: 1, x: 1 }
+        }));
+
+        // ...while the player survives, standing on the one left behind
+        assert!(!events.contains(&SimEvent::PlayerDied { player: 0 }));
+        assert!(game_state.player(0).is_some());
+        assert_eq!(game_state.bombs.len(), 1);
+        assert_eq!(game_state.bombs[0].position, Position { y: 1, x: 4 });
+    }
+}
@@ -0,0 +1,98 @@
+use std::{fs, time::Duration};
+
+use bevy::{ecs as bevy_ecs, prelude::*, utils::HashMap};
+use serde::Serialize;
+
+use super::components::{Penguin, TeamID};
+
+/*
+Per penguin statistics gathered over all rounds of a match. The game systems only record into
+them when the current game mode keeps a `MatchStats` resource around.
+*/
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PenguinMatchStats {
+    pub penguin: usize,
+    pub team: usize,
+    pub kills: usize,
+    pub deaths: usize,
+    pub self_kills: usize,
+    pub bombs_dropped: usize,
+    pub walls_destroyed: usize,
+    pub items_collected: usize,
+    #[serde(rename = "time_alive_secs", serialize_with = "serialize_secs")]
+    pub time_alive: Duration,
+}
+
+fn serialize_secs<S: serde::Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_f32(duration.as_secs_f32())
+}
+
+#[derive(Serialize, Resource)]
+pub struct MatchStats {
+    pub rounds: usize,
+    pub penguins: Vec<PenguinMatchStats>,
+    // kept so that stats can be credited to players who already died
+    #[serde(skip)]
+    player_penguins: HashMap<Entity, Penguin>,
+}
+
+impl MatchStats {
+    const EXPORT_FILE_PATH: &'static str = "local/match_stats.json";
+
+    pub fn new(penguins: impl Iterator<Item = (Penguin, TeamID)>) -> Self {
+        Self {
+            rounds: 0,
+            penguins: penguins
+                .map(|(penguin, team_id)| PenguinMatchStats {
+                    penguin: penguin.0,
+                    team: team_id.0,
+                    ..Default::default()
+                })
+                .collect(),
+            player_penguins: HashMap::default(),
+        }
+    }
+
+    pub fn register_player(&mut self, player: Entity, penguin: Penguin) {
+        self.player_penguins.insert(player, penguin);
+    }
+
+    // the stats of the penguin controlled by a (possibly dead) player entity
+    pub fn get_mut(&mut self, player: Entity) -> Option<&mut PenguinMatchStats> {
+        let penguin = self.player_penguins.get(&player)?;
+        self.penguins.iter_mut().find(|s| s.penguin == penguin.0)
+    }
+
+    pub fn export(&self) -> Result<&'static str, String> {
+        let export_file_path = std::path::Path::new(Self::EXPORT_FILE_PATH);
+        let serialized = serde_json::to_string_pretty(self).unwrap();
+        fs::create_dir_all(export_file_path.parent().unwrap())
+            .and_then(|_| fs::write(export_file_path, serialized))
+            .map(|_| Self::EXPORT_FILE_PATH)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_of_dead_players() {
+        let mut match_stats =
+            MatchStats::new([(Penguin(0), TeamID(0)), (Penguin(1), TeamID(0))].into_iter());
+        let player = Entity::from_raw(7);
+        assert!(match_stats.get_mut(player).is_none());
+
+        match_stats.register_player(player, Penguin(1));
+        match_stats.get_mut(player).unwrap().kills += 1;
+        assert_eq!(match_stats.penguins[0].kills, 0);
+        assert_eq!(match_stats.penguins[1].kills, 1);
+
+        let json = serde_json::to_value(&match_stats).unwrap();
+        assert_eq!(json["penguins"][1]["kills"], 1);
+        assert!(json["penguins"][1]["time_alive_secs"].is_number());
+        assert!(json.get("player_penguins").is_none());
+    }
+}
@@ -1,10 +1,12 @@
 use std::time::Duration;
 
 use bevy::{
+    ecs::query::Has,
     prelude::*,
     utils::{HashMap, HashSet},
     window::PrimaryWindow,
 };
+use itertools::Itertools;
 use rand::{
     prelude::{IteratorRandom, SliceRandom},
     Rng,
@@ -25,7 +27,15 @@ use super::{
     components::*,
     constants::*,
     events::*,
+    loot_tables::{LootSource, MatchLoot},
     resources::*,
+    rules::{GameRules, SuddenDeathKind},
+    simulation::{
+        advance_airborne_bomb, apply_item, get_bomb_drop_positions, get_flame_positions,
+        get_remote_detonation, resolve_move, take_damage, DamageOutcome, MoveOutcome, Obstacle,
+        RemoteDetonation,
+    },
+    stats::MatchStats,
     types::{Direction, *},
     utils::*,
 };
@@ -70,8 +80,9 @@ pub fn hud_update(
     mut commands: Commands,
     game_timer: Res<GameTimer>,
     mut query: Query<&mut Text, With<GameTimerDisplay>>,
-    query2: Query<&Penguin>,
+    query2: Query<(&Penguin, Option<&Glove>)>,
     query3: Query<(Entity, &PenguinPortrait)>,
+    mut query4: Query<(&PenguinPortraitGlove, &mut Visibility)>,
 ) {
     let remaining_seconds = (game_timer.0.duration() - game_timer.0.elapsed())
         .as_secs_f32()
@@ -80,10 +91,18 @@ pub fn hud_update(
 
     // remove dead penguin portraits :(
     for (entity, PenguinPortrait(penguin)) in query3.iter() {
-        if !query2.iter().any(|p| p.0 == penguin.0) {
+        if !query2.iter().any(|(p, _)| p.0 == penguin.0) {
             commands.entity(entity).despawn_recursive();
         }
     }
+
+    for (PenguinPortraitGlove(penguin), mut visibility) in query4.iter_mut() {
+        *visibility = if query2.iter().any(|(p, g)| p.0 == penguin.0 && g.is_some()) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
 }
 
 pub fn hud_indicate_pause(mut query: Query<&mut Text, With<GameTimerDisplay>>) {
@@ -106,7 +125,8 @@ pub fn handle_user_input(
     sounds: Res<Sounds>,
     inputs: Res<InputActionStatusTracker>,
     game_context: Res<GameContext>,
-    query: Query<(Entity, &HumanControlled), With<Player>>,
+    // revenge ghosts are controlled too
+    query: Query<(Entity, &HumanControlled)>,
     mut ev_player_action: EventWriter<PlayerActionEvent>,
     state: Res<State<AppState>>,
     mut next_state: ResMut<NextState<AppState>>,
@@ -132,6 +152,20 @@ pub fn handle_user_input(
                 action: PlayerAction::DropBomb,
             });
         }
+
+        if inputs.is_active(InputAction::M) {
+            ev_player_action.send(PlayerActionEvent {
+                player: entity,
+                action: PlayerAction::Detonate,
+            });
+        }
+
+        if inputs.is_active(InputAction::N) {
+            ev_player_action.send(PlayerActionEvent {
+                player: entity,
+                action: PlayerAction::Punch,
+            });
+        }
     }
 
     for (entity, _) in query.iter().filter(|(_, hc)| hc.0 == 1) {
@@ -155,6 +189,20 @@ pub fn handle_user_input(
                 action: PlayerAction::DropBomb,
             });
         }
+
+        if inputs.is_active(InputAction::H) {
+            ev_player_action.send(PlayerActionEvent {
+                player: entity,
+                action: PlayerAction::Detonate,
+            });
+        }
+
+        if inputs.is_active(InputAction::J) {
+            ev_player_action.send(PlayerActionEvent {
+                player: entity,
+                action: PlayerAction::Punch,
+            });
+        }
     }
 
     if inputs.is_active(InputAction::Return) && game_context.pausable {
@@ -172,6 +220,7 @@ pub fn handle_user_input(
 }
 
 pub fn mob_ai(
+    mut game_rng: ResMut<GameRng>,
     mut query: Query<(Entity, &Position, &mut MobAI, Option<&WallHack>), With<Player>>,
     query2: Query<(&Position, Option<&Destructible>), With<Solid>>,
     mut ev_player_action: EventWriter<PlayerActionEvent>,
@@ -198,8 +247,8 @@ pub fn mob_ai(
         if mob_ai.direction.is_none() {
             // pick potential directions in random order
             let mut potential_directions: Vec<Direction> =
-                potential_directions.into_iter().collect();
-            potential_directions.shuffle(&mut rand::thread_rng());
+                potential_directions.into_iter().sorted().collect();
+            potential_directions.shuffle(&mut game_rng.ai);
 
             // move towards one that leads to passable terrain (if existing)
             let passable_dir = potential_directions.into_iter().find(|direction| {
@@ -227,40 +276,62 @@ pub fn bot_ai(
             &MoveCooldown,
             Option<&WallHack>,
             Option<&BombPush>,
+            Option<&RemoteDetonator>,
+            Has<PierceBomb>,
             &BombSatchel,
             &TeamID,
+            Has<MeleeAttacker>,
         ),
         With<Player>,
     >,
     query2: Query<&Position, With<Fire>>,
-    query3: Query<&Position, With<Bomb>>,
+    query3: Query<(&Position, &Bomb)>,
     query4: Query<(&Position, Option<&Destructible>), With<Solid>>,
     query5: Query<&Position, Or<(With<Solid>, With<Exit>)>>,
-    query6: Query<(Entity, &Position, &TeamID), With<Player>>,
+    query6: Query<(Entity, &Position, &TeamID, Has<MeleeAttacker>), With<Player>>,
     query7: Query<&Position, Or<(With<Wall>, With<Bomb>, With<Exit>, With<BurningItem>)>>,
     query8: Query<&Position, With<Destructible>>,
     query9: Query<&Position, (With<Wall>, Without<Destructible>)>,
     query10: Query<&Position, Or<(With<Solid>, With<Item>, With<Player>, With<Exit>)>>,
     query11: Query<&Position, With<Item>>,
-    map_size: Res<MapSize>,
-    wall_of_death: Option<Res<WallOfDeath>>,
+    query12: Query<(&Flag, &Position)>,
+    query13: Query<&Position, With<Hill>>,
+    (map_size, game_rules): (Res<MapSize>, Res<GameRules>),
+    (wall_of_death, mut game_rng): (Option<Res<WallOfDeath>>, ResMut<GameRng>),
     mut ev_player_action: EventWriter<PlayerActionEvent>,
 ) {
     // TODO: this is wasted work for situations where there aren't any bots
-    let mut rng = rand::thread_rng();
+    let rng = &mut game_rng.ai;
     let fire_positions: HashSet<Position> = query2.iter().copied().collect();
-    let bomb_positions: HashSet<Position> = query3.iter().copied().collect();
+    let bombs: HashMap<Position, BombKind> = query3.iter().map(|(p, b)| (*p, b.kind)).collect();
     let fireproof_positions: HashSet<Position> = query5.iter().copied().collect();
     let invalid_bomb_spawn_positions: HashSet<Position> = query7.iter().copied().collect();
     let destructible_positions: HashSet<Position> = query8.iter().copied().collect();
     let stone_wall_positions: HashSet<Position> = query9.iter().copied().collect();
     let moving_object_stoppers: HashSet<Position> = query10.iter().copied().collect();
     let item_positions: HashSet<Position> = query11.iter().copied().collect();
+    let flags: Vec<(&Flag, Position)> = query12.iter().map(|(f, p)| (f, *p)).collect();
+    let hill_positions: Vec<Position> = query13.iter().copied().collect();
+    let flame_stoppers = FlameStoppers {
+        fireproof_positions: &fireproof_positions,
+        pierceable_positions: &destructible_positions,
+    };
 
     let wall_of_death = wall_of_death.as_deref();
 
-    for (entity, bot_ai, position, move_cooldown, wall_hack, bomb_push, bomb_satchel, team_id) in
-        query.iter()
+    for (
+        entity,
+        bot_ai,
+        position,
+        move_cooldown,
+        wall_hack,
+        bomb_push,
+        remote_detonator,
+        pierce_bomb,
+        bomb_satchel,
+        team_id,
+        melee_attacker,
+    ) in query.iter()
     {
         let impassable_positions: HashSet<Position> = if wall_hack.is_none() {
             query4.iter().map(|(p, _)| *p).collect()
@@ -273,12 +344,33 @@ pub fn bot_ai(
 
         let enemy_positions: Vec<Position> = query6
             .iter()
-            .filter(|(_, _, tid)| tid.0 != team_id.0)
-            .map(|(_, p, _)| *p)
+            .filter(|(_, _, tid, _)| tid.0 != team_id.0)
+            .map(|(_, p, _, _)| *p)
+            .collect();
+        // enemies that take out the bot just by touching it, like the infected
+        let melee_enemy_positions: Vec<Position> = query6
+            .iter()
+            .filter(|(_, _, tid, m)| *m && tid.0 != team_id.0)
+            .map(|(_, p, _, _)| *p)
             .collect();
+        // teammates only need to be spared if the bombs can hurt them
+        let teammate_positions: Vec<Position> = if game_rules.friendly_fire {
+            query6
+                .iter()
+                .filter(|(e, _, tid, _)| *e != entity && tid.0 == team_id.0)
+                .map(|(_, p, _, _)| *p)
+                .collect()
+        } else {
+            vec![]
+        };
 
         let bot_difficulty = bot_ai.difficulty;
         let assumed_bomb_range = bomb_satchel.bomb_range + 2;
+        let bomb_kind = if pierce_bomb {
+            BombKind::Pierce
+        } else {
+            BombKind::Regular
+        };
 
         // miss?
         match bot_difficulty {
@@ -296,8 +388,40 @@ pub fn bot_ai(
             BotDifficulty::Hard => (),
         }
 
-        let command_priority_list = [0, 3, 6, 1, 4, 2, 5, 7];
         let mut action = None;
+
+        // set off the remote controlled bombs once out of their reach, if they can catch an enemy
+        // or if there are no bombs left to drop
+        if remote_detonator.is_some() {
+            let remote_fire_positions: HashSet<Position> = query3
+                .iter()
+                .filter(|(_, b)| b.owner == Some(entity))
+                .flat_map(|(p, b)| {
+                    get_flame_positions(
+                        *p,
+                        b.range,
+                        b.kind,
+                        &fireproof_positions,
+                        &destructible_positions,
+                    )
+                    .0
+                })
+                .collect();
+            if !remote_fire_positions.is_empty()
+                && !remote_fire_positions.contains(position)
+                && !teammate_positions
+                    .iter()
+                    .any(|p| remote_fire_positions.contains(p))
+                && (bomb_satchel.bombs_available == 0
+                    || enemy_positions
+                        .iter()
+                        .any(|p| remote_fire_positions.contains(p)))
+            {
+                action = Some((PlayerAction::Detonate, PlayerIntention::Detonate));
+            }
+        }
+
+        let command_priority_list = [0, 3, 6, 1, 4, 2, 5, 7];
         let mut bomb_flag = 0;
         let mut nav_flag = -1;
         for mut com in command_priority_list {
@@ -326,27 +450,29 @@ pub fn bot_ai(
                     if !safe(
                         *position,
                         &fire_positions,
-                        &bomb_positions,
+                        &bombs,
                         assumed_bomb_range,
-                        &fireproof_positions,
+                        &flame_stoppers,
                         wall_of_death,
                         *map_size,
                     ) {
                         action = safe_dir(
                             *position,
                             &fire_positions,
-                            &bomb_positions,
+                            &bombs,
                             assumed_bomb_range,
-                            &fireproof_positions,
+                            &flame_stoppers,
                             &impassable_positions,
                             wall_of_death,
                             *map_size,
                             bomb_push.is_some(),
                             &moving_object_stoppers,
                         )
-                        .iter()
-                        .choose(&mut rng)
-                        .map(|d| (PlayerAction::Move(*d), PlayerIntention::MoveToSafety));
+                        .into_iter()
+                        // hash set iteration order changes between runs, so sort to keep the picks seeded
+                        .sorted()
+                        .choose(rng)
+                        .map(|d| (PlayerAction::Move(d), PlayerIntention::MoveToSafety));
                     }
                 }
                 1 => {
@@ -354,32 +480,35 @@ pub fn bot_ai(
                         *position,
                         &impassable_positions,
                         &fire_positions,
-                        &bomb_positions,
+                        &bombs,
                         assumed_bomb_range,
-                        &fireproof_positions,
+                        &flame_stoppers,
                         wall_of_death,
                         *map_size,
                         &item_positions,
                     )
-                    .iter()
-                    .choose(&mut rng)
-                    .map(|d| (PlayerAction::Move(*d), PlayerIntention::PickUpItem));
+                    .into_iter()
+                    .sorted()
+                    .choose(rng)
+                    .map(|d| (PlayerAction::Move(d), PlayerIntention::PickUpItem));
                 }
                 2 => {
                     action = destroy_blocks(
                         *position,
                         bomb_satchel,
+                        bomb_kind,
                         &invalid_bomb_spawn_positions,
                         &fire_positions,
-                        &bomb_positions,
+                        &bombs,
                         assumed_bomb_range,
-                        &fireproof_positions,
+                        &flame_stoppers,
                         &impassable_positions,
                         &destructible_positions,
                         wall_of_death,
                         *map_size,
                         bomb_push.is_some(),
                         &moving_object_stoppers,
+                        rng,
                     )
                     .map(|a| (a, PlayerIntention::DestroyBlocks));
                 }
@@ -394,11 +523,12 @@ pub fn bot_ai(
                         )
                         && should_place_bomb(
                             *position,
+                            bomb_kind,
                             &invalid_bomb_spawn_positions,
-                            &bomb_positions,
+                            &bombs,
                             assumed_bomb_range,
                             &fire_positions,
-                            &fireproof_positions,
+                            &flame_stoppers,
                             &impassable_positions,
                             wall_of_death,
                             *map_size,
@@ -416,11 +546,12 @@ pub fn bot_ai(
                         && players_in_range(*position, &enemy_positions, bomb_satchel.bomb_range)
                         && should_place_bomb(
                             *position,
+                            bomb_kind,
                             &invalid_bomb_spawn_positions,
-                            &bomb_positions,
+                            &bombs,
                             assumed_bomb_range,
                             &fire_positions,
-                            &fireproof_positions,
+                            &flame_stoppers,
                             &impassable_positions,
                             wall_of_death,
                             *map_size,
@@ -442,16 +573,16 @@ pub fn bot_ai(
                 }
                 6 => {
                     if nav_flag == -1 && rng.gen_bool(0.125) {
-                        let direction = Direction::LIST.choose(&mut rng).unwrap();
+                        let direction = Direction::LIST.choose(rng).unwrap();
                         let position = position.offset(*direction, 1);
 
                         if !impassable_positions.contains(&position)
                             && safe(
                                 position,
                                 &fire_positions,
-                                &bomb_positions,
+                                &bombs,
                                 assumed_bomb_range,
-                                &fireproof_positions,
+                                &flame_stoppers,
                                 wall_of_death,
                                 *map_size,
                             )
@@ -470,42 +601,114 @@ pub fn bot_ai(
             }
         }
 
+        // game mode objectives take the place of hunting and fleeing
+        let mut objective = None;
         if action.is_none() {
-            if nav_flag == 0 {
+            if !flags.is_empty() {
+                let teammates: HashSet<Entity> = query6
+                    .iter()
+                    .filter(|(_, _, tid, _)| tid.0 == team_id.0)
+                    .map(|(e, _, _, _)| e)
+                    .collect();
+                objective = flag_objective(entity, *position, *team_id, &flags, &teammates);
+            } else if !hill_positions.is_empty() {
+                objective = hill_objective(*position, &hill_positions);
+            } else if melee_attacker {
+                objective = infection_objective(*position, &enemy_positions);
+            }
+        }
+        // the objective can be to stay put
+        let holding_position = matches!(objective, Some((target, _)) if target == *position);
+        if let Some((target, intention)) = objective {
+            if !holding_position {
+                // destructible walls on the way get blown up by the other intentions
+                let obstacle_positions: HashSet<Position> = impassable_positions
+                    .difference(&destructible_positions)
+                    .copied()
+                    .collect();
+                action = path_to(
+                    *position,
+                    target,
+                    &obstacle_positions,
+                    &impassable_positions,
+                    *map_size,
+                    |p| {
+                        safe(
+                            p,
+                            &fire_positions,
+                            &bombs,
+                            assumed_bomb_range,
+                            &flame_stoppers,
+                            wall_of_death,
+                            *map_size,
+                        )
+                    },
+                )
+                .into_iter()
+                .sorted()
+                .choose(rng)
+                .map(|d| (PlayerAction::Move(d), intention));
+            }
+        }
+
+        if action.is_none() && !holding_position {
+            if !melee_attacker && !melee_enemy_positions.is_empty() {
+                // hunting down enemies that only need to touch the bot is asking for trouble
+                action = flee(
+                    *position,
+                    &melee_enemy_positions,
+                    &impassable_positions,
+                    &fire_positions,
+                    &bombs,
+                    assumed_bomb_range,
+                    &flame_stoppers,
+                    wall_of_death,
+                    *map_size,
+                )
+                .into_iter()
+                .sorted()
+                .choose(rng)
+                .map(|d| (PlayerAction::Move(d), PlayerIntention::AvoidInfected));
+            } else if nav_flag == 0 {
                 action = hunt_players(
                     *position,
                     *map_size,
+                    // the bot itself is kept in, its spot in the list picks the next enemy to go after
                     &query6
                         .iter()
+                        .filter(|(e, _, tid, _)| *e == entity || tid.0 != team_id.0)
                         .map(|p| (*p.1, p.0))
                         .collect::<Vec<(Position, Entity)>>(),
                     entity,
                     &stone_wall_positions,
                     &impassable_positions,
                     &fire_positions,
-                    &bomb_positions,
+                    &bombs,
                     assumed_bomb_range,
-                    &fireproof_positions,
+                    &flame_stoppers,
                     wall_of_death,
+                    rng,
                 )
-                .iter()
-                .choose(&mut rng)
-                .map(|d| (PlayerAction::Move(*d), PlayerIntention::HuntPlayers));
+                .into_iter()
+                .sorted()
+                .choose(rng)
+                .map(|d| (PlayerAction::Move(d), PlayerIntention::HuntPlayers));
             } else {
                 action = flee(
                     *position,
                     &enemy_positions,
                     &impassable_positions,
                     &fire_positions,
-                    &bomb_positions,
+                    &bombs,
                     assumed_bomb_range,
-                    &fireproof_positions,
+                    &flame_stoppers,
                     wall_of_death,
                     *map_size,
                 )
-                .iter()
-                .choose(&mut rng)
-                .map(|d| (PlayerAction::Move(*d), PlayerIntention::Flee));
+                .into_iter()
+                .sorted()
+                .choose(rng)
+                .map(|d| (PlayerAction::Move(d), PlayerIntention::Flee));
             }
         }
 
@@ -549,10 +752,17 @@ pub fn player_move(
     )>,
     mut query2: Query<&mut Transform>,
 ) {
-    let solids: HashMap<Position, (Entity, bool, bool)> = p
+    let solids: HashMap<Position, (Entity, Obstacle)> = p
         .p1()
         .iter()
-        .map(|(e, _, p, d, b)| (*p, (e, d.is_some(), b.is_some())))
+        .map(|(e, _, p, d, b)| {
+            let obstacle = match (d, b) {
+                (Some(_), _) => Obstacle::DestructibleWall,
+                (None, Some(_)) => Obstacle::Bomb,
+                (None, None) => Obstacle::Wall,
+            };
+            (*p, (e, obstacle))
+        })
         .collect();
 
     for (entity, direction) in ev_player_action.iter().filter_map(|p| {
@@ -571,6 +781,7 @@ pub fn player_move(
                 Direction::Right => sprite.flip_x = false,
                 _ => (),
             }
+            commands.entity(entity).insert(Facing(direction));
 
             if let Some(move_cooldown) = move_cooldown.as_mut() {
                 if !move_cooldown.0.ready() {
@@ -581,28 +792,31 @@ pub fn player_move(
             let new_position = position.offset(direction, 1);
             let solid = solids.get(&new_position);
 
-            let mut moved = false;
-            if solid.is_none() || (matches!(solid, Some((_, true, _))) && wall_hack.is_some()) {
-                *position = new_position;
-                moved = true;
-            } else if bomb_push.is_some() {
-                if let Some((e, _, true)) = solid {
-                    commands.entity(*e).insert((
-                        Moving { direction },
-                        MoveCooldown(Cooldown::from_seconds(0.01)),
-                    ));
-                }
-            }
+            match resolve_move(
+                solid.map(|(_, o)| *o),
+                wall_hack.is_some(),
+                bomb_push.is_some(),
+            ) {
+                MoveOutcome::Moved => {
+                    *position = new_position;
+                    if let Some(mut move_cooldown) = move_cooldown {
+                        move_cooldown.0.trigger();
+                    }
 
-            if moved {
-                if let Some(mut move_cooldown) = move_cooldown {
-                    move_cooldown.0.trigger();
+                    let mut transform = query2.get_mut(entity).unwrap();
+                    let translation = &mut transform.translation;
+                    translation.x = get_x(position.x);
+                    translation.y = get_y(position.y);
                 }
-
-                let mut transform = query2.get_mut(entity).unwrap();
-                let translation = &mut transform.translation;
-                translation.x = get_x(position.x);
-                translation.y = get_y(position.y);
+                MoveOutcome::PushedBomb => {
+                    if let Some((e, _)) = solid {
+                        commands.entity(*e).insert((
+                            Moving { direction },
+                            MoveCooldown(Cooldown::from_seconds(PUSHED_BOMB_MOVE_COOLDOWN_SECS)),
+                        ));
+                    }
+                }
+                MoveOutcome::Blocked => (),
             }
         }
     }
@@ -610,27 +824,59 @@ pub fn player_move(
 
 pub fn moving_object_update(
     mut commands: Commands,
+    map_size: Res<MapSize>,
     mut p: ParamSet<(
-        Query<(
-            Entity,
-            &Moving,
-            &mut MoveCooldown,
-            &mut Position,
-            &mut Transform,
-        )>,
+        Query<
+            (
+                Entity,
+                Option<&Moving>,
+                Option<&mut Airborne>,
+                &mut MoveCooldown,
+                &mut Position,
+                &mut Transform,
+            ),
+            Or<(With<Moving>, With<Airborne>)>,
+        >,
         Query<&Position, Or<(With<Solid>, With<Item>, With<Player>, With<Exit>)>>,
     )>,
 ) {
-    let moving_object_entities: Vec<Entity> = p.p0().iter_mut().map(|(e, _, _, _, _)| e).collect();
+    let moving_object_entities: Vec<Entity> =
+        p.p0().iter_mut().map(|(e, _, _, _, _, _)| e).collect();
 
     for entity in moving_object_entities {
         let impassable_positions: HashSet<Position> = p.p1().iter().copied().collect();
 
         let mut tmp = p.p0();
-        let (entity, moving, mut move_cooldown, mut position, mut transform) =
+        let (entity, moving, airborne, mut move_cooldown, mut position, mut transform) =
             tmp.get_mut(entity).unwrap();
 
-        if move_cooldown.0.ready() {
+        if !move_cooldown.0.ready() {
+            continue;
+        }
+
+        if let Some(mut airborne) = airborne {
+            let landed = advance_airborne_bomb(
+                &mut airborne,
+                &mut position,
+                *map_size,
+                &impassable_positions,
+            );
+
+            // throw animation, the bomb arcs above its current tile and flies over the walls
+            let translation = &mut transform.translation;
+            translation.x = get_x(position.x);
+            translation.y = get_y(position.y) + airborne.height() * TILE_HEIGHT as f32;
+            translation.z = 60.0;
+
+            if landed {
+                println!("bomb landed: {:?}", *position);
+                translation.z = 25.0;
+                commands.entity(entity).remove::<(Airborne, MoveCooldown)>();
+                commands.entity(entity).insert(Solid);
+            } else {
+                move_cooldown.0.trigger();
+            }
+        } else if let Some(moving) = moving {
             let new_position = position.offset(moving.direction, 1);
             if impassable_positions.get(&new_position).is_none() {
                 *position = new_position;
@@ -652,30 +898,48 @@ pub fn pick_up_item(
     mut commands: Commands,
     mut query: Query<(Entity, &mut Health, &Position, &mut BombSatchel), With<Player>>,
     query2: Query<(Entity, &Item, &Position)>,
+    mut game_rng: ResMut<GameRng>,
+    game_rules: Res<GameRules>,
+    mut match_stats: Option<ResMut<MatchStats>>,
 ) {
-    let mut rng = rand::thread_rng();
     for (ie, i, ip) in query2.iter() {
         if let Some((pe, mut h, _, mut bomb_satchel)) = query
             .iter_mut()
             .filter(|(_, _, pp, _)| **pp == *ip)
-            .choose(&mut rng)
+            .choose(&mut game_rng.loot)
         {
             println!("powered up: {:?}", ip);
-            match i {
-                Item::Upgrade(Upgrade::BombsUp) => bomb_satchel.bombs_available += 1,
-                Item::Upgrade(Upgrade::RangeUp) => bomb_satchel.bomb_range += 1,
-                Item::Upgrade(Upgrade::LivesUp) => h.lives += 1,
-                Item::Power(Power::Immortal) => {
-                    commands.entity(pe).insert(Immortal::default());
+            match apply_item(*i, &mut h, &mut bomb_satchel) {
+                None => (),
+                Some(Power::Immortal) => {
+                    commands
+                        .entity(pe)
+                        .insert(Immortal::new(game_rules.immortality_secs));
                 }
-                Item::Power(Power::WallHack) => {
+                Some(Power::WallHack) => {
                     commands.entity(pe).insert(WallHack);
                 }
-                Item::Power(Power::BombPush) => {
+                Some(Power::BombPush) => {
                     commands.entity(pe).insert(BombPush);
                 }
+                Some(Power::RemoteDetonator) => {
+                    commands.entity(pe).insert(RemoteDetonator);
+                }
+                Some(Power::Glove) => {
+                    commands.entity(pe).insert(Glove);
+                }
+                Some(Power::PierceBomb) => {
+                    commands.entity(pe).insert(PierceBomb);
+                }
+                Some(Power::LineBomb) => {
+                    commands.entity(pe).insert(LineBomb);
+                }
             };
 
+            if let Some(stats) = match_stats.as_mut().and_then(|ms| ms.get_mut(pe)) {
+                stats.items_collected += 1;
+            }
+
             commands.entity(ie).despawn_recursive();
         }
     }
@@ -683,83 +947,57 @@ pub fn pick_up_item(
 
 pub fn bomb_drop(
     mut commands: Commands,
-    game_textures: Res<GameTextures>,
-    fonts: Res<Fonts>,
-    world_id: Res<WorldID>,
+    (game_textures, fonts, world_id): (Res<GameTextures>, Res<Fonts>, Res<WorldID>),
+    (game_rules, mut match_stats): (Res<GameRules>, Option<ResMut<MatchStats>>),
     mut ev_player_action: EventReader<PlayerActionEvent>,
-    mut query: Query<(&Position, &mut BombSatchel)>,
-    query2: Query<&Position, Or<(With<Solid>, With<Exit>, With<BurningItem>)>>,
+    mut query: Query<(&Position, &mut BombSatchel, &Sprite, Option<&Facing>)>,
+    query2: Query<(&Position, Has<Bomb>), Or<(With<Solid>, With<Exit>, With<BurningItem>)>>,
+    query3: Query<(Has<PierceBomb>, Has<LineBomb>)>,
 ) {
     for entity in ev_player_action
         .iter()
         .filter(|pa| matches!(pa.action, PlayerAction::DropBomb))
         .map(|pa| pa.player)
     {
-        if let Ok((position, mut bomb_satchel)) = query.get_mut(entity) {
-            if bomb_satchel.bombs_available > 0 && !query2.iter().any(|p| *p == *position) {
+        if let Ok((position, mut bomb_satchel, sprite, facing)) = query.get_mut(entity) {
+            let (pierce_bomb, line_bomb) = query3.get(entity).unwrap();
+            let invalid_positions: HashSet<Position> = query2.iter().map(|(p, _)| *p).collect();
+            let bomb_positions: HashSet<Position> =
+                query2.iter().filter(|(_, b)| *b).map(|(p, _)| *p).collect();
+            let drop_positions = get_bomb_drop_positions(
+                *position,
+                get_facing_direction(facing, sprite),
+                &bomb_satchel,
+                line_bomb,
+                &bomb_positions,
+                &invalid_positions,
+            );
+
+            for position in drop_positions {
                 println!("drop bomb: {:?}", position);
                 bomb_satchel.bombs_available -= 1;
+                if let Some(stats) = match_stats.as_mut().and_then(|ms| ms.get_mut(entity)) {
+                    stats.bombs_dropped += 1;
+                }
 
-                commands
-                    .spawn((
-                        SpriteBundle {
-                            texture: game_textures.bomb.clone(),
-                            transform: Transform::from_xyz(
-                                get_x(position.x),
-                                get_y(position.y),
-                                25.0,
-                            ),
-                            sprite: Sprite {
-                                custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        },
-                        Bomb {
-                            owner: Some(entity),
-                            range: bomb_satchel.bomb_range,
-                            timer: Timer::from_seconds(2.0, TimerMode::Once),
+                spawn_bomb(
+                    &mut commands,
+                    &game_textures,
+                    &fonts,
+                    *world_id,
+                    position,
+                    Bomb {
+                        owner: Some(entity),
+                        ignited_by: None,
+                        range: bomb_satchel.bomb_range,
+                        kind: if pierce_bomb {
+                            BombKind::Pierce
+                        } else {
+                            BombKind::Regular
                         },
-                        Solid,
-                        *position,
-                    ))
-                    .with_children(|parent| {
-                        let fuse_color = COLORS[if world_id.0 == 2 { 12 } else { 14 }].into();
-
-                        let mut text = Text::from_section(
-                            '*',
-                            TextStyle {
-                                font: fonts.mono.clone(),
-                                font_size: 2.0 * PIXEL_SCALE as f32,
-                                color: fuse_color,
-                            },
-                        )
-                        .with_alignment(TextAlignment::Center);
-                        text.sections.push(TextSection {
-                            value: "┐\n │".into(),
-                            style: TextStyle {
-                                font: fonts.mono.clone(),
-                                font_size: 2.0 * PIXEL_SCALE as f32,
-                                color: COLORS[0].into(),
-                            },
-                        });
-
-                        parent.spawn((
-                            Text2dBundle {
-                                text,
-                                transform: Transform::from_xyz(
-                                    0.0,
-                                    TILE_HEIGHT as f32 / 8.0 * 2.0,
-                                    0.0,
-                                ),
-                                ..Default::default()
-                            },
-                            Fuse {
-                                color: fuse_color,
-                                animation_timer: Timer::from_seconds(0.1, TimerMode::Repeating),
-                            },
-                        ));
-                    });
+                        timer: Timer::from_seconds(game_rules.bomb_fuse_secs, TimerMode::Once),
+                    },
+                );
             }
         }
     }
@@ -849,12 +1087,88 @@ pub fn animate_fuse(
     }
 }
 
-pub fn bomb_tick(time: Res<Time>, mut query: Query<&mut Bomb>) {
+pub fn bomb_tick(
+    time: Res<Time>,
+    mut query: Query<&mut Bomb, Without<Airborne>>,
+    query2: Query<(), With<RemoteDetonator>>,
+) {
+    let shortened_fuse_duration = Duration::from_secs_f32(SHORTENED_FUSE_DURATION_SECS);
     for mut bomb in query.iter_mut() {
+        // remote controlled bombs wait for their owner, unless the fire got to them first
+        if matches!(bomb.owner, Some(owner) if query2.contains(owner))
+            && bomb.timer.duration() > shortened_fuse_duration
+        {
+            continue;
+        }
+
         bomb.timer.tick(time.delta());
     }
 }
 
+pub fn remote_detonation(
+    mut ev_player_action: EventReader<PlayerActionEvent>,
+    query: Query<(&Position, &BombSatchel), With<RemoteDetonator>>,
+    query2: Query<(Entity, &Bomb, &Position), Without<Airborne>>,
+    mut ev_explosion: EventWriter<ExplosionEvent>,
+) {
+    for PlayerActionEvent { player, action } in ev_player_action.iter() {
+        if let Ok((position, bomb_satchel)) = query.get(*player) {
+            let on_own_bomb = query2
+                .iter()
+                .any(|(_, b, bp)| b.owner == Some(*player) && *bp == *position);
+            let Some(remote_detonation) = get_remote_detonation(*action, bomb_satchel, on_own_bomb)
+            else {
+                continue;
+            };
+
+            for (bomb_entity, _, _) in query2.iter().filter(|(_, b, bp)| {
+                b.owner == Some(*player)
+                    && (remote_detonation == RemoteDetonation::AllBombs || **bp != *position)
+            }) {
+                println!("remote detonation: {:?}", bomb_entity);
+                ev_explosion.send(ExplosionEvent { bomb: bomb_entity });
+            }
+        }
+    }
+}
+
+pub fn bomb_throw(
+    mut commands: Commands,
+    mut ev_player_action: EventReader<PlayerActionEvent>,
+    query: Query<(&Position, &Sprite, Option<&Facing>), With<Glove>>,
+    query2: Query<(Entity, &Bomb, &Position), Without<Airborne>>,
+) {
+    for entity in ev_player_action
+        .iter()
+        .filter(|pa| matches!(pa.action, PlayerAction::Punch))
+        .map(|pa| pa.player)
+    {
+        if let Ok((position, sprite, facing)) = query.get(entity) {
+            let direction = get_facing_direction(facing, sprite);
+
+            // pick up the bomb underneath, otherwise punch the one in front
+            let target = [*position, position.offset(direction, 1)]
+                .into_iter()
+                .find_map(|p| {
+                    query2
+                        .iter()
+                        .find(|(_, _, bp)| **bp == p)
+                        .map(|(e, _, _)| e)
+                });
+            if let Some(bomb_entity) = target {
+                println!("bomb thrown: {:?}", bomb_entity);
+                commands
+                    .entity(bomb_entity)
+                    .remove::<(Solid, Moving)>()
+                    .insert((
+                        Airborne::new(direction),
+                        MoveCooldown(Cooldown::from_seconds(AIRBORNE_BOMB_MOVE_COOLDOWN_SECS)),
+                    ));
+            }
+        }
+    }
+}
+
 pub fn fire_tick(mut commands: Commands, time: Res<Time>, mut query: Query<(Entity, &mut Fire)>) {
     for (entity, mut fire) in query.iter_mut() {
         fire.timer.tick(time.delta());
@@ -868,8 +1182,9 @@ pub fn crumbling_tick(
     mut commands: Commands,
     time: Res<Time>,
     game_textures: Res<GameTextures>,
-    game_context: Res<GameContext>,
+    mut match_loot: ResMut<MatchLoot>,
     exit_position: Option<Res<ExitPosition>>,
+    mut game_rng: ResMut<GameRng>,
     mut query: Query<(Entity, &mut Crumbling, &Position)>,
 ) {
     for (entity, mut crumbling, position) in query.iter_mut() {
@@ -891,12 +1206,14 @@ pub fn crumbling_tick(
                     *position,
                     Exit::default(),
                 ));
-            } else if rand::thread_rng().gen_range(0.0..1.0) < ITEM_SPAWN_CHANCE {
+            } else {
                 generate_item_at_position(
                     *position,
                     &mut commands,
                     &game_textures,
-                    game_context.reduced_loot,
+                    &mut match_loot,
+                    LootSource::Wall,
+                    &mut game_rng.loot,
                 );
             }
         }
@@ -919,10 +1236,12 @@ pub fn burning_item_tick(
 pub fn explode_bombs(
     mut commands: Commands,
     game_textures: Res<GameTextures>,
+    game_rules: Res<GameRules>,
     audio: Res<Audio>,
     sounds: Res<Sounds>,
     query: Query<(Entity, &Bomb, &Position)>,
     query1: Query<(Entity, &Position, Option<&Bomb>), Or<(With<Solid>, With<Exit>)>>,
+    query2: Query<&Position, With<Destructible>>,
     mut ev_explosion: EventReader<ExplosionEvent>,
     mut ev_bomb_restock: EventWriter<BombRestockEvent>,
     mut ev_burn: EventWriter<BurnEvent>,
@@ -940,6 +1259,7 @@ pub fn explode_bombs(
         })
         .copied()
         .collect();
+    let pierceable_positions: HashSet<Position> = query2.iter().copied().collect();
 
     let mut sound_played = false;
 
@@ -960,6 +1280,9 @@ pub fn explode_bombs(
             sound_played = true;
         }
 
+        // chain reactions are credited to whoever started them
+        let bomber = bomb.ignited_by.or(bomb.owner);
+
         let spawn_fire = |commands: &mut Commands, position: Position| {
             commands.spawn((
                 SpriteBundle {
@@ -972,24 +1295,49 @@ pub fn explode_bombs(
                     ..Default::default()
                 },
                 Fire {
-                    timer: Timer::from_seconds(0.5, TimerMode::Once),
+                    owner: bomber,
+                    timer: Timer::from_seconds(game_rules.fire_duration_secs, TimerMode::Once),
                 },
                 position,
             ));
         };
 
-        spawn_fire(&mut commands, *position);
-        for direction in Direction::LIST {
-            for i in 1..=bomb.range {
-                let position = position.offset(direction, i);
-
-                if fireproof_positions.contains(&position) {
-                    ev_burn.send(BurnEvent { position });
-                    break;
-                }
+        let (fire_positions, burned_positions) = get_flame_positions(
+            *position,
+            bomb.range,
+            bomb.kind,
+            &fireproof_positions,
+            &pierceable_positions,
+        );
+        for position in fire_positions {
+            spawn_fire(&mut commands, position);
+        }
+        for position in burned_positions {
+            ev_burn.send(BurnEvent { position, bomber });
+        }
+    }
+}
 
-                spawn_fire(&mut commands, position);
-            }
+pub fn mob_loot_drop(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    mut match_loot: ResMut<MatchLoot>,
+    mut game_rng: ResMut<GameRng>,
+    mut ev_player_death_event: EventReader<PlayerDeathEvent>,
+) {
+    for PlayerDeathEvent {
+        position, mob_type, ..
+    } in ev_player_death_event.iter()
+    {
+        if let Some(mob_type) = mob_type {
+            generate_item_at_position(
+                *position,
+                &mut commands,
+                &game_textures,
+                &mut match_loot,
+                LootSource::Mob(*mob_type),
+                &mut game_rng.loot,
+            );
         }
     }
 }
@@ -1056,43 +1404,71 @@ pub fn animate_immortality(
     }
 }
 
-pub fn fire_effect(mut query: Query<&Position, With<Fire>>, mut ev_burn: EventWriter<BurnEvent>) {
-    for position in query.iter_mut() {
+pub fn fire_effect(query: Query<(&Fire, &Position)>, mut ev_burn: EventWriter<BurnEvent>) {
+    for (fire, position) in query.iter() {
         ev_burn.send(BurnEvent {
             position: *position,
+            bomber: fire.owner,
         });
     }
 }
 
 pub fn melee_attack(
-    query: Query<(&Position, &TeamID), With<MeleeAttacker>>,
+    query: Query<(Entity, &Position, &TeamID), With<MeleeAttacker>>,
     query2: Query<(Entity, &Position, &TeamID), With<Player>>,
     mut ev_damage: EventWriter<DamageEvent>,
 ) {
-    for (attacker_position, attacker_team_id) in query.iter() {
+    for (attacker, attacker_position, attacker_team_id) in query.iter() {
         for (e, _, _) in query2
             .iter()
             .filter(|(_, p, tid)| **p == *attacker_position && tid.0 != attacker_team_id.0)
         {
-            ev_damage.send(DamageEvent { target: e });
+            ev_damage.send(DamageEvent {
+                target: e,
+                source: DamageSource::Mob(attacker),
+            });
         }
     }
 }
 
 pub fn player_burn(
-    query: Query<(Entity, &Position), (With<Player>, Without<Immortal>)>,
+    game_rules: Res<GameRules>,
+    query: Query<(Entity, &Position, &TeamID), (With<Player>, Without<Immortal>)>,
     query2: Query<&Position, With<Wall>>,
+    query3: Query<(&Fire, &Position)>,
+    query4: Query<&TeamID>,
     mut ev_burn: EventReader<BurnEvent>,
     mut ev_damage: EventWriter<DamageEvent>,
 ) {
-    for BurnEvent { position } in ev_burn.iter() {
-        for (pe, player_pos) in query.iter().filter(|(_, pp)| **pp == *position) {
+    for BurnEvent { position, bomber } in ev_burn.iter() {
+        for (pe, player_pos, team_id) in query.iter().filter(|(_, pp, _)| **pp == *position) {
             if query2.iter().any(|wall_pos| *wall_pos == *player_pos) {
                 // Anakin, I have the high ground
                 continue;
             }
 
-            ev_damage.send(DamageEvent { target: pe });
+            // the player's own bombs still hurt, as do the ones of fallen teammates
+            if !game_rules.friendly_fire {
+                let fire_owners: Vec<Option<Entity>> = query3
+                    .iter()
+                    .filter(|(_, p)| **p == *position)
+                    .map(|(f, _)| f.owner)
+                    .collect();
+                if !fire_owners.is_empty()
+                    && fire_owners.iter().all(|owner| {
+                        matches!(owner, Some(owner) if *owner != pe && query4.get(*owner) == Ok(team_id))
+                    })
+                {
+                    continue;
+                }
+            }
+
+            let source = match bomber {
+                Some(bomber) if *bomber == pe => DamageSource::SelfKill,
+                Some(bomber) => DamageSource::Bomb(*bomber),
+                None => DamageSource::Unknown,
+            };
+            ev_damage.send(DamageEvent { target: pe, source });
         }
     }
 }
@@ -1100,6 +1476,7 @@ pub fn player_burn(
 pub fn player_damage(
     mut commands: Commands,
     mut game_score: Option<ResMut<GameScore>>,
+    game_rules: Res<GameRules>,
     mut query: Query<
         (
             Entity,
@@ -1111,6 +1488,8 @@ pub fn player_damage(
             &mut Transform,
             &mut Sprite,
             Option<&PointValue>,
+            Option<&MobType>,
+            Option<&Penguin>,
         ),
         (With<Player>, Without<Immortal>),
     >,
@@ -1119,7 +1498,7 @@ pub fn player_damage(
 ) {
     let mut damaged_players = HashSet::default();
 
-    for DamageEvent { target } in ev_damage.iter() {
+    for DamageEvent { target, source } in ev_damage.iter() {
         if let Ok((
             pe,
             mut health,
@@ -1130,6 +1509,8 @@ pub fn player_damage(
             mut transform,
             mut sprite,
             point_value,
+            mob_type,
+            penguin,
         )) = query.get_mut(*target)
         {
             if damaged_players.contains(&pe) {
@@ -1138,17 +1519,21 @@ pub fn player_damage(
             damaged_players.insert(pe);
 
             println!("player damaged: {:?}", pe);
-            health.health -= 1;
 
             let mut gain_immortality = false;
-            if health.health == 0 {
-                println!("player lost a life: {:?}", pe);
-                health.lives -= 1;
-                if health.lives == 0 {
-                    println!("player died from damage: {:?}", pe);
+            match take_damage(&mut health) {
+                DamageOutcome::Hurt => gain_immortality = true,
+                DamageOutcome::Died => {
+                    println!("player died from damage: {:?}, source: {:?}", pe, source);
                     commands.entity(pe).despawn_recursive();
 
-                    ev_player_death_event.send(PlayerDeathEvent);
+                    ev_player_death_event.send(PlayerDeathEvent {
+                        player: pe,
+                        position: *position,
+                        mob_type: mob_type.copied(),
+                        penguin: penguin.copied(),
+                        source: *source,
+                    });
 
                     // TODO: move to story_mode
                     if let Some(ref mut game_score) = game_score {
@@ -1156,8 +1541,9 @@ pub fn player_damage(
                             game_score.0 += point_value.0;
                         }
                     }
-                } else {
-                    health.health = health.max_health;
+                }
+                DamageOutcome::LifeLost => {
+                    println!("player lost a life: {:?}", pe);
                     gain_immortality = true;
 
                     // return to spawn
@@ -1166,33 +1552,42 @@ pub fn player_damage(
                     translation.x = get_x(position.x);
                     translation.y = get_y(position.y);
                     sprite.flip_x = false;
+                    commands.entity(pe).remove::<Facing>();
 
                     // lose powers
                     commands.entity(pe).remove::<BombPush>();
                     commands.entity(pe).remove::<WallHack>();
+                    commands.entity(pe).remove::<RemoteDetonator>();
+                    commands.entity(pe).remove::<Glove>();
+                    commands.entity(pe).remove::<PierceBomb>();
+                    commands.entity(pe).remove::<LineBomb>();
                 }
-            } else {
-                gain_immortality = true;
             }
 
             if gain_immortality {
-                commands.entity(pe).insert(Immortal::default());
+                commands
+                    .entity(pe)
+                    .insert(Immortal::new(game_rules.immortality_secs));
                 *texture = immortal_texture.0.clone();
             }
         }
     }
 }
 
-pub fn bomb_burn(mut query: Query<(&mut Bomb, &Position)>, mut ev_burn: EventReader<BurnEvent>) {
-    for BurnEvent { position } in ev_burn.iter() {
+pub fn bomb_burn(
+    mut query: Query<(&mut Bomb, &Position), Without<Airborne>>,
+    mut ev_burn: EventReader<BurnEvent>,
+) {
+    for BurnEvent { position, bomber } in ev_burn.iter() {
         query
             .iter_mut()
             .filter(|(_, p)| **p == *position)
             .for_each(|(mut b, _)| {
-                const SHORTENED_FUSE_DURATION: Duration = Duration::from_millis(50);
-                if b.timer.duration() - b.timer.elapsed() > SHORTENED_FUSE_DURATION {
-                    b.timer.set_duration(SHORTENED_FUSE_DURATION);
+                let shortened_fuse_duration = Duration::from_secs_f32(SHORTENED_FUSE_DURATION_SECS);
+                if b.timer.duration() - b.timer.elapsed() > shortened_fuse_duration {
+                    b.timer.set_duration(shortened_fuse_duration);
                     b.timer.reset();
+                    b.ignited_by = *bomber;
                 }
             });
     }
@@ -1200,18 +1595,28 @@ pub fn bomb_burn(mut query: Query<(&mut Bomb, &Position)>, mut ev_burn: EventRea
 
 pub fn destructible_wall_burn(
     game_textures: Res<GameTextures>,
+    game_rules: Res<GameRules>,
     mut commands: Commands,
     mut query: Query<
         (Entity, &Position, &mut Handle<Image>, Option<&Crumbling>),
         (With<Wall>, With<Destructible>),
     >,
     mut ev_burn: EventReader<BurnEvent>,
+    mut match_stats: Option<ResMut<MatchStats>>,
 ) {
-    for BurnEvent { position } in ev_burn.iter() {
+    for BurnEvent { position, bomber } in ev_burn.iter() {
         for (e, _, mut t, perishable) in query.iter_mut().filter(|(_, p, _, _)| **p == *position) {
             if perishable.is_none() {
+                if let Some(stats) = match_stats
+                    .as_mut()
+                    .zip(*bomber)
+                    .and_then(|(ms, bomber)| ms.get_mut(bomber))
+                {
+                    stats.walls_destroyed += 1;
+                }
+
                 commands.entity(e).insert(Crumbling {
-                    timer: Timer::from_seconds(0.5, TimerMode::Once),
+                    timer: Timer::from_seconds(game_rules.crumbling_secs, TimerMode::Once),
                 });
                 *t = game_textures.get_map_textures().burning_wall.clone();
             }
@@ -1221,13 +1626,14 @@ pub fn destructible_wall_burn(
 
 pub fn item_burn(
     game_textures: Res<GameTextures>,
+    game_rules: Res<GameRules>,
     mut commands: Commands,
     mut query: Query<(Entity, &Position), With<Item>>,
     mut ev_burn: EventReader<BurnEvent>,
 ) {
     let mut burned = HashSet::default();
 
-    for BurnEvent { position } in ev_burn.iter() {
+    for BurnEvent { position, .. } in ev_burn.iter() {
         for e in query
             .iter_mut()
             .filter(|(_, p)| **p == *position)
@@ -1254,7 +1660,7 @@ pub fn item_burn(
                 },
                 *position,
                 BurningItem {
-                    timer: Timer::from_seconds(0.5, TimerMode::Once),
+                    timer: Timer::from_seconds(game_rules.burning_item_secs, TimerMode::Once),
                 },
             ));
         }
@@ -1264,6 +1670,7 @@ pub fn item_burn(
 pub fn exit_burn(
     time: Res<Time>,
     game_textures: Res<GameTextures>,
+    game_rules: Res<GameRules>,
     mut commands: Commands,
     mut query: Query<(&Position, &mut Exit)>,
     mut ev_burn: EventReader<BurnEvent>,
@@ -1273,7 +1680,7 @@ pub fn exit_burn(
         exit.spawn_cooldown.tick(time.delta());
     }
 
-    for BurnEvent { position } in ev_burn.iter() {
+    for BurnEvent { position, .. } in ev_burn.iter() {
         if let Ok((exit_position, mut exit)) = query.get_single_mut() {
             if *exit_position == *position && exit.spawn_cooldown.ready() {
                 println!("exit burned: {:?}", position);
@@ -1308,7 +1715,7 @@ pub fn exit_burn(
                     SpawnPosition(*exit_position),
                     MeleeAttacker,
                     TeamID(1),
-                    Immortal::default(),
+                    Immortal::new(game_rules.immortality_secs),
                 ));
 
                 exit.spawn_cooldown.trigger();
@@ -1321,13 +1728,30 @@ pub fn wall_of_death_update(
     mut commands: Commands,
     time: Res<Time>,
     game_textures: Res<GameTextures>,
+    (fonts, world_id, game_rules): (Res<Fonts>, Res<WorldID>, Res<GameRules>),
+    mut game_rng: ResMut<GameRng>,
     mut wall_of_death: ResMut<WallOfDeath>,
     map_size: Res<MapSize>,
     query: Query<&Position, (With<Wall>, Without<Destructible>)>,
     query2: Query<(Entity, &Position, Option<&Bomb>, Option<&Player>)>,
+    query3: Query<&Position, With<Solid>>,
+    mut query4: Query<(&mut Health, &mut BombSatchel), With<Player>>,
+    query5: Query<&Penguin>,
     mut ev_player_death_event: EventWriter<PlayerDeathEvent>,
     mut ev_bomb_restock: EventWriter<BombRestockEvent>,
 ) {
+    let walls: HashSet<Position> = query.iter().copied().collect();
+    // the innermost ring is left free
+    let map_size = *map_size;
+    let last_ring = (map_size.rows.min(map_size.columns) as isize - 1) / 2;
+    let get_ring_positions = |ring: isize| {
+        (0..map_size.rows as isize)
+            .flat_map(|y| (0..map_size.columns as isize).map(move |x| Position { y, x }))
+            .filter(|p| get_ring(*p, map_size) == ring)
+            .filter(|p| !walls.contains(p))
+            .collect::<Vec<Position>>()
+    };
+
     let get_next_position_direction = |mut position: Position,
                                        mut direction: Direction|
      -> Option<(Position, Direction)> {
@@ -1336,7 +1760,6 @@ pub fn wall_of_death_update(
             x: 3,
         };
 
-        let walls: HashSet<Position> = query.iter().copied().collect();
         loop {
             if position == end_position {
                 break None;
@@ -1375,13 +1798,19 @@ pub fn wall_of_death_update(
         }
     };
 
-    let mut clear_position_and_spawn_wall = |position: Position| {
+    let mut clear_position_and_spawn_wall = |commands: &mut Commands, position: Position| {
         for (e, _, bomb, player) in query2.iter().filter(|(_, p, _, _)| **p == position) {
             commands.entity(e).despawn_recursive();
 
             if player.is_some() {
                 println!("player died from wall of death: {:?}", e);
-                ev_player_death_event.send(PlayerDeathEvent);
+                ev_player_death_event.send(PlayerDeathEvent {
+                    player: e,
+                    position,
+                    mob_type: None,
+                    penguin: query5.get(e).ok().copied(),
+                    source: DamageSource::WallOfDeath,
+                });
             }
 
             if let Some(bomb) = bomb {
@@ -1411,20 +1840,34 @@ pub fn wall_of_death_update(
 
     loop {
         let new_state = match *wall_of_death {
-            WallOfDeath::Dormant(ref mut timer) => {
+            WallOfDeath::Dormant(ref mut timer, sudden_death_kind) => {
                 timer.tick(time.delta());
 
                 if timer.finished() {
-                    println!("Wall of Death activated!");
+                    println!("Wall of Death activated: {:?}", sudden_death_kind);
 
-                    Some(WallOfDeath::Active(ActiveWallOfDeath {
-                        position: Position {
-                            y: map_size.rows as isize - 1,
-                            x: 1,
+                    Some(match sudden_death_kind {
+                        SuddenDeathKind::Spiral => WallOfDeath::Active(ActiveWallOfDeath {
+                            position: Position {
+                                y: map_size.rows as isize - 1,
+                                x: 1,
+                            },
+                            direction: Direction::Up,
+                            cooldown: Cooldown::from_seconds(0.2),
+                        }),
+                        SuddenDeathKind::ShrinkingRing => WallOfDeath::ShrinkingRing {
+                            ring: 1,
+                            cooldown: Cooldown::from_seconds(3.0),
+                        },
+                        SuddenDeathKind::BombRain => {
+                            WallOfDeath::BombRain(Cooldown::from_seconds(0.4))
+                        }
+                        SuddenDeathKind::SpreadingFire => WallOfDeath::SpreadingFire {
+                            ring: 1,
+                            cooldown: Cooldown::from_seconds(3.0),
                         },
-                        direction: Direction::Up,
-                        cooldown: Cooldown::from_seconds(0.2),
-                    }))
+                        SuddenDeathKind::OneHit => WallOfDeath::OneHit,
+                    })
                 } else {
                     None
                 }
@@ -1440,7 +1883,7 @@ pub fn wall_of_death_update(
                         active_wall_of_death.position = position;
                         active_wall_of_death.direction = direction;
 
-                        clear_position_and_spawn_wall(active_wall_of_death.position);
+                        clear_position_and_spawn_wall(&mut commands, active_wall_of_death.position);
 
                         None
                     } else {
@@ -1450,6 +1893,112 @@ pub fn wall_of_death_update(
                     None
                 }
             }
+            WallOfDeath::ShrinkingRing {
+                ref mut ring,
+                ref mut cooldown,
+            } => {
+                cooldown.tick(time.delta());
+                if *ring >= last_ring {
+                    Some(WallOfDeath::Done)
+                } else {
+                    if cooldown.ready() {
+                        cooldown.trigger();
+                        for position in get_ring_positions(*ring) {
+                            clear_position_and_spawn_wall(&mut commands, position);
+                        }
+                        *ring += 1;
+                    }
+                    None
+                }
+            }
+            WallOfDeath::BombRain(ref mut cooldown) => {
+                cooldown.tick(time.delta());
+                if cooldown.ready() {
+                    cooldown.trigger();
+
+                    let invalid_positions: HashSet<Position> = query3.iter().copied().collect();
+                    if let Some(position) = (1..last_ring + 1)
+                        .flat_map(get_ring_positions)
+                        .filter(|p| !invalid_positions.contains(p))
+                        .choose(&mut game_rng.gameplay)
+                    {
+                        spawn_bomb(
+                            &mut commands,
+                            &game_textures,
+                            &fonts,
+                            *world_id,
+                            position,
+                            Bomb {
+                                owner: None,
+                                ignited_by: None,
+                                range: 2,
+                                kind: BombKind::Regular,
+                                timer: Timer::from_seconds(
+                                    game_rules.bomb_fuse_secs,
+                                    TimerMode::Once,
+                                ),
+                            },
+                        );
+                    }
+                }
+                None
+            }
+            WallOfDeath::SpreadingFire {
+                ref mut ring,
+                ref mut cooldown,
+            } => {
+                cooldown.tick(time.delta());
+                if *ring >= last_ring {
+                    Some(WallOfDeath::Done)
+                } else {
+                    if cooldown.ready() {
+                        cooldown.trigger();
+                        for position in get_ring_positions(*ring) {
+                            // burns until the end of the round
+                            commands.spawn((
+                                SpriteBundle {
+                                    texture: game_textures.fire.clone(),
+                                    transform: Transform::from_xyz(
+                                        get_x(position.x),
+                                        get_y(position.y),
+                                        5.0,
+                                    ),
+                                    sprite: Sprite {
+                                        custom_size: Some(Vec2::new(
+                                            TILE_WIDTH as f32,
+                                            TILE_HEIGHT as f32,
+                                        )),
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                },
+                                Fire {
+                                    owner: None,
+                                    timer: Timer::from_seconds(9999.0, TimerMode::Once),
+                                },
+                                position,
+                            ));
+                        }
+                        *ring += 1;
+                    }
+                    None
+                }
+            }
+            WallOfDeath::OneHit => {
+                let max_bomb_range = map_size.rows.max(map_size.columns);
+                for (mut health, mut bomb_satchel) in query4.iter_mut() {
+                    // picked up lives and range upgrades don't count either
+                    if health.lives > 1 || health.max_health > 1 {
+                        health.lives = 1;
+                        health.max_health = 1;
+                        health.health = 1;
+                    }
+                    if bomb_satchel.bomb_range != max_bomb_range {
+                        bomb_satchel.bomb_range = max_bomb_range;
+                    }
+                }
+                None
+            }
             WallOfDeath::Done => None,
         };
 
@@ -1,8 +1,9 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Direction {
     Left,
     Right,
@@ -19,10 +20,14 @@ impl Direction {
     ];
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum PlayerAction {
     Move(Direction),
     DropBomb,
+    // sets off the player's remote controlled bombs
+    Detonate,
+    // throws the bomb in front of the player
+    Punch,
 }
 
 #[derive(Debug)]
@@ -35,23 +40,41 @@ pub enum PlayerIntention {
     RandomMove,
     HuntPlayers,
     Flee,
+    Detonate,
+    CaptureFlag,
+    EscortFlag,
+    ReturnFlag,
+    ContestZone,
+    InfectPlayers,
+    AvoidInfected,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum BotDifficulty {
     Easy,
     Medium,
     Hard,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Power {
     WallHack,
     BombPush,
     Immortal,
+    RemoteDetonator,
+    Glove,
+    PierceBomb,
+    LineBomb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BombKind {
+    Regular,
+    // flames go through destructible walls
+    Pierce,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[allow(clippy::enum_variant_names)]
 pub enum Upgrade {
     BombsUp,
@@ -1,8 +1,5 @@
 use bevy::{prelude::*, utils::HashSet};
-use rand::{
-    prelude::{IteratorRandom, SliceRandom},
-    Rng,
-};
+use rand::{Rng, RngCore};
 
 use crate::common::{
     constants::{COLORS, PIXEL_SCALE},
@@ -10,10 +7,13 @@ use crate::common::{
 };
 
 use super::{
+    ascii_map::{AsciiMap, MapTile},
     components::*,
     constants::*,
+    loot_tables::{LootSource, MatchLoot},
+    map_generators::{MapGenerationError, MapGenerationParams, MapGenerator},
     resources::*,
-    types::{Direction, *},
+    types::Direction,
 };
 
 pub fn get_x(x: isize) -> f32 {
@@ -24,6 +24,24 @@ pub fn get_y(y: isize) -> f32 {
     -(TILE_HEIGHT as f32 / 2.0 + (y * TILE_HEIGHT as isize) as f32)
 }
 
+// how far a position is from the map edge, the edge itself being ring 0
+pub fn get_ring(position: Position, map_size: MapSize) -> isize {
+    position
+        .y
+        .min(position.x)
+        .min(map_size.rows as isize - 1 - position.y)
+        .min(map_size.columns as isize - 1 - position.x)
+}
+
+// players that haven't moved yet face the way their sprite does
+pub fn get_facing_direction(facing: Option<&Facing>, sprite: &Sprite) -> Direction {
+    match facing {
+        Some(Facing(direction)) => *direction,
+        None if sprite.flip_x => Direction::Left,
+        None => Direction::Right,
+    }
+}
+
 pub fn format_hud_time(remaining_seconds: usize) -> String {
     format!(
         "{:02}:{:02}",
@@ -169,39 +187,34 @@ pub fn init_penguin_portraits(
                             },
                             UIComponent,
                         ));
+
+                        // shown while the penguin holds the glove
+                        parent.spawn((
+                            ImageBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    right: Val::Px(0.0),
+                                    bottom: Val::Px(0.0),
+                                    width: Val::Px(3.0 * PIXEL_SCALE as f32),
+                                    height: Val::Px(4.0 * PIXEL_SCALE as f32),
+                                    ..Default::default()
+                                },
+                                image: game_textures.glove.clone().into(),
+                                visibility: Visibility::Hidden,
+                                ..Default::default()
+                            },
+                            PenguinPortraitGlove(*penguin),
+                            UIComponent,
+                        ));
                     });
             });
     }
 }
 
-pub fn spawn_map(
-    commands: &mut Commands,
-    game_textures: &GameTextures,
+pub fn get_stone_wall_spawn_groups(
     map_size: MapSize,
-    percent_of_passable_positions_to_fill: f32,
     spawn_middle_blocks: bool,
-    penguin_spawn_positions: &[Position],
-    mob_spawn_positions: &[Position],
-    spawn_exit: bool,
-) -> Vec<Vec<Entity>> {
-    let mut rng = rand::thread_rng();
-
-    // place empty/passable tiles
-    for j in 0..map_size.rows {
-        for i in 0..map_size.columns {
-            commands.spawn(SpriteBundle {
-                texture: game_textures.get_map_textures().empty.clone(),
-                transform: Transform::from_xyz(get_x(i as isize), get_y(j as isize), 0.0),
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
-                    ..Default::default()
-                },
-                ..Default::default()
-            });
-        }
-    }
-
-    // spawn walls
+) -> Vec<Vec<Position>> {
     let mut stone_wall_spawn_groups = vec![];
     for i in 0..map_size.rows {
         let left = Position {
@@ -238,159 +251,222 @@ pub fn spawn_map(
         }
     }
 
-    let mut wall_entity_reveal_groups = vec![];
-    for spawn_group in stone_wall_spawn_groups.iter() {
-        let mut reveal_group = vec![];
-        for position in spawn_group {
-            let entity = commands
-                .spawn((
-                    SpriteBundle {
-                        texture: game_textures.get_map_textures().wall.clone(),
-                        transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 10.0),
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    Wall,
-                    Solid,
-                    *position,
-                ))
-                .id();
-            reveal_group.push(entity);
+    stone_wall_spawn_groups
+}
+
+fn spawn_empty_tiles(commands: &mut Commands, game_textures: &GameTextures, map_size: MapSize) {
+    for j in 0..map_size.rows {
+        for i in 0..map_size.columns {
+            commands.spawn(SpriteBundle {
+                texture: game_textures.get_map_textures().empty.clone(),
+                transform: Transform::from_xyz(get_x(i as isize), get_y(j as isize), 0.0),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
         }
-        wall_entity_reveal_groups.push(reveal_group);
     }
+}
 
-    let stone_wall_positions: HashSet<Position> =
-        stone_wall_spawn_groups.iter().flatten().copied().collect();
-    let mut destructible_wall_potential_positions: HashSet<Position> = (0..map_size.rows)
-        .flat_map(|y| {
-            (0..map_size.columns).map(move |x| Position {
-                y: y as isize,
-                x: x as isize,
-            })
-        })
-        .filter(|p| !stone_wall_positions.contains(p))
-        .collect();
+fn spawn_stone_wall(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    position: Position,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: game_textures.get_map_textures().wall.clone(),
+                transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 10.0),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Wall,
+            Solid,
+            position,
+        ))
+        .id()
+}
 
-    let number_of_passable_positions = destructible_wall_potential_positions.len();
+fn spawn_destructible_wall(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    position: Position,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: game_textures.get_map_textures().destructible_wall.clone(),
+                transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 10.0),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Wall,
+            Solid,
+            Destructible,
+            position,
+        ))
+        .id()
+}
 
-    // reserve room for the penguins (cross-shaped)
-    for penguin_spawn_position in penguin_spawn_positions {
-        destructible_wall_potential_positions.remove(penguin_spawn_position);
-        for position in Direction::LIST
-            .iter()
-            .map(|direction| penguin_spawn_position.offset(*direction, 1))
-        {
-            destructible_wall_potential_positions.remove(&position);
-        }
-    }
+// nothing gets spawned if the map generation fails
+pub fn spawn_map(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    map_generator: &dyn MapGenerator,
+    map_generation_params: &MapGenerationParams,
+    rng: &mut dyn RngCore,
+) -> Result<Vec<Vec<Entity>>, MapGenerationError> {
+    let ascii_map = map_generator.generate(map_generation_params, rng)?;
 
-    // reserve room for the mobs (line-shaped)
-    for mob_spawn_position in mob_spawn_positions {
-        destructible_wall_potential_positions.remove(mob_spawn_position);
+    Ok(spawn_ascii_map(
+        commands,
+        game_textures,
+        &ascii_map,
+        map_generation_params.spawn_exit,
+    ))
+}
 
-        for direction in [
-            [Direction::Left, Direction::Right],
-            [Direction::Up, Direction::Down],
-        ]
-        .choose(&mut rng)
-        .unwrap()
-        {
-            for j in 1..3 {
-                let position = mob_spawn_position.offset(*direction, j);
-                if stone_wall_positions.contains(&position) {
-                    break;
-                }
-                destructible_wall_potential_positions.remove(&position);
-            }
+// spawns the walls and items of a handcrafted map, the spawn points are left to the game modes
+pub fn spawn_ascii_map(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    ascii_map: &AsciiMap,
+    spawn_exit: bool,
+) -> Vec<Vec<Entity>> {
+    let map_size = ascii_map.map_size();
+    spawn_empty_tiles(commands, game_textures, map_size);
+
+    // the border is revealed from both sides at once
+    let border_spawn_groups = get_stone_wall_spawn_groups(map_size, false);
+    let border_positions: HashSet<Position> =
+        border_spawn_groups.iter().flatten().copied().collect();
+    let mut stone_wall_reveal_groups: Vec<Vec<Entity>> = border_spawn_groups
+        .iter()
+        .map(|spawn_group| {
+            spawn_group
+                .iter()
+                .map(|position| spawn_stone_wall(commands, game_textures, *position))
+                .collect()
+        })
+        .collect();
+    let mut destructible_wall_reveal_groups = vec![];
+    for (position, tile) in ascii_map.tiles() {
+        match tile {
+            MapTile::StoneWall if border_positions.contains(&position) => (),
+            MapTile::StoneWall => stone_wall_reveal_groups.push(vec![spawn_stone_wall(
+                commands,
+                game_textures,
+                position,
+            )]),
+            MapTile::DestructibleWall | MapTile::Exit => destructible_wall_reveal_groups.push(
+                vec![spawn_destructible_wall(commands, game_textures, position)],
+            ),
+            MapTile::Item(item) => spawn_item(commands, game_textures, position, item),
+            MapTile::Empty | MapTile::PenguinSpawn(_) | MapTile::MobSpawn => (),
         }
     }
 
-    let num_of_destructible_walls_to_place = (number_of_passable_positions as f32
-        * percent_of_passable_positions_to_fill
-        / 100.0) as usize;
-    if destructible_wall_potential_positions.len() < num_of_destructible_walls_to_place {
-        panic!(
-            "Not enough passable positions available for placing destructible walls. Have {}, but need at least {}",
-            destructible_wall_potential_positions.len(),
-            num_of_destructible_walls_to_place
-        );
+    if spawn_exit {
+        if let Some(exit_position) = ascii_map.exit_position() {
+            commands.insert_resource(ExitPosition(exit_position));
+        }
     }
 
-    let destructible_wall_positions = destructible_wall_potential_positions
-        .into_iter()
-        .choose_multiple(&mut rng, num_of_destructible_walls_to_place);
-    for position in &destructible_wall_positions {
-        let entity = commands
-            .spawn((
-                SpriteBundle {
-                    texture: game_textures.get_map_textures().destructible_wall.clone(),
-                    transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 10.0),
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
-                        ..Default::default()
-                    },
+    // reveal the stone walls first, same as with the generated maps
+    stone_wall_reveal_groups.append(&mut destructible_wall_reveal_groups);
+    stone_wall_reveal_groups
+}
+
+pub fn spawn_bomb(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    fonts: &Fonts,
+    world_id: WorldID,
+    position: Position,
+    bomb: Bomb,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: game_textures.bomb.clone(),
+                transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 25.0),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
                     ..Default::default()
                 },
-                Wall,
-                Solid,
-                Destructible,
-                *position,
-            ))
-            .id();
-        wall_entity_reveal_groups.push(vec![entity]);
-    }
+                ..Default::default()
+            },
+            bomb,
+            Solid,
+            position,
+        ))
+        .with_children(|parent| {
+            let fuse_color = COLORS[if world_id.0 == 2 { 12 } else { 14 }].into();
 
-    if spawn_exit {
-        commands.insert_resource(ExitPosition(
-            *destructible_wall_positions.choose(&mut rng).unwrap(),
-        ));
-    }
+            let mut text = Text::from_section(
+                '*',
+                TextStyle {
+                    font: fonts.mono.clone(),
+                    font_size: 2.0 * PIXEL_SCALE as f32,
+                    color: fuse_color,
+                },
+            )
+            .with_alignment(TextAlignment::Center);
+            text.sections.push(TextSection {
+                value: "┐\n │".into(),
+                style: TextStyle {
+                    font: fonts.mono.clone(),
+                    font_size: 2.0 * PIXEL_SCALE as f32,
+                    color: COLORS[0].into(),
+                },
+            });
 
-    wall_entity_reveal_groups
+            parent.spawn((
+                Text2dBundle {
+                    text,
+                    transform: Transform::from_xyz(0.0, TILE_HEIGHT as f32 / 8.0 * 2.0, 0.0),
+                    ..Default::default()
+                },
+                Fuse {
+                    color: fuse_color,
+                    animation_timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+                },
+            ));
+        })
+        .id()
 }
 
 pub fn generate_item_at_position(
     position: Position,
     commands: &mut Commands,
     game_textures: &GameTextures,
-    reduced_loot: bool,
+    match_loot: &mut MatchLoot,
+    loot_source: LootSource,
+    rng: &mut impl Rng,
 ) {
-    let r = rand::thread_rng().gen::<usize>() % 100;
-
-    /* "Loot tables" */
-    let item = if !reduced_loot {
-        match r {
-            _ if r < 50 => Item::Upgrade(Upgrade::BombsUp),
-            50..=79 => Item::Upgrade(Upgrade::RangeUp),
-            80..=89 => Item::Power(Power::BombPush),
-            90..=93 => Item::Upgrade(Upgrade::LivesUp),
-            94..=97 => Item::Power(Power::WallHack),
-            _ if r >= 98 => Item::Power(Power::Immortal),
-            _ => unreachable!(),
-        }
-    } else {
-        match r {
-            _ if r < 50 => Item::Upgrade(Upgrade::BombsUp),
-            50..=89 => Item::Upgrade(Upgrade::RangeUp),
-            _ if r >= 90 => Item::Power(Power::BombPush),
-            _ => unreachable!(),
-        }
-    };
+    if let Some(item) = match_loot.roll(loot_source, rng) {
+        spawn_item(commands, game_textures, position, item);
+    }
+}
 
+pub fn spawn_item(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    position: Position,
+    item: Item,
+) {
     commands.spawn((
         SpriteBundle {
-            texture: match item {
-                Item::Upgrade(Upgrade::BombsUp) => game_textures.bombs_up.clone(),
-                Item::Upgrade(Upgrade::RangeUp) => game_textures.range_up.clone(),
-                Item::Upgrade(Upgrade::LivesUp) => game_textures.lives_up.clone(),
-                Item::Power(Power::WallHack) => game_textures.wall_hack.clone(),
-                Item::Power(Power::BombPush) => game_textures.bomb_push.clone(),
-                Item::Power(Power::Immortal) => game_textures.immortal.clone(),
-            },
+            texture: game_textures.get_item_texture(item).clone(),
             transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 20.0),
             sprite: Sprite {
                 custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
@@ -402,3 +478,23 @@ pub fn generate_item_at_position(
         item,
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_ring() {
+        let map_size = MapSize {
+            rows: 11,
+            columns: 15,
+        };
+
+        assert_eq!(get_ring(Position { y: 0, x: 0 }, map_size), 0);
+        assert_eq!(get_ring(Position { y: 5, x: 14 }, map_size), 0);
+        assert_eq!(get_ring(Position { y: 1, x: 7 }, map_size), 1);
+        assert_eq!(get_ring(Position { y: 9, x: 3 }, map_size), 1);
+        // the middle of the map is as far in as the rows allow
+        assert_eq!(get_ring(Position { y: 5, x: 7 }, map_size), 5);
+    }
+}
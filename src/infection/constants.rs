@@ -0,0 +1,7 @@
+use crate::game::components::TeamID;
+
+// the two sides of an infection round, kept apart from the teams of the leaderboard
+pub const SURVIVORS_TEAM_ID: TeamID = TeamID(usize::MAX - 1);
+pub const INFECTED_TEAM_ID: TeamID = TeamID(usize::MAX);
+// the green tint of infected penguins
+pub const INFECTED_COLOR: usize = 10;
@@ -0,0 +1,41 @@
+mod constants;
+mod resources;
+mod systems;
+mod utils;
+
+use bevy::prelude::*;
+
+use crate::{
+    battle_mode::finish_round,
+    game::{systems::hud_update, Set},
+    AppState,
+};
+
+use self::{resources::Infection, systems::*};
+
+// plays along with battle mode rounds started as infection
+pub struct InfectionPlugin;
+
+impl Plugin for InfectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::RoundStartFreeze), setup_infection)
+            .add_systems(
+                Update,
+                (
+                    infection_manager,
+                    infection_spread,
+                    apply_deferred,
+                    finish_infection_round,
+                )
+                    .chain()
+                    .after(Set::TimeUpdate)
+                    .after(Set::PlayerDeathEvent)
+                    // the portraits of the infected stay, as they are back right away
+                    .before(hud_update)
+                    .before(finish_round)
+                    .run_if(resource_exists::<Infection>())
+                    .run_if(in_state(AppState::BattleModeInGame)),
+            )
+            .add_systems(OnEnter(AppState::BattleModeTeardown), teardown);
+    }
+}
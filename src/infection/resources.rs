@@ -0,0 +1,43 @@
+use bevy::{
+    ecs as bevy_ecs,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::game::components::{Penguin, Position};
+
+pub enum InfectionManagerState {
+    // patient zero is yet to be picked
+    Setup,
+    InPlay,
+    // the round has been decided
+    Finished,
+}
+
+#[derive(Resource)]
+pub struct Infection {
+    pub manager_state: InfectionManagerState,
+    pub patient_zero: Option<Penguin>,
+    pub spawn_positions: HashMap<Penguin, Position>,
+    // kept so that the infected can be told apart after they died
+    pub player_penguins: HashMap<Entity, Penguin>,
+    pub infected: HashSet<Penguin>,
+}
+
+impl Infection {
+    pub fn new(spawn_positions: HashMap<Penguin, Position>) -> Self {
+        Self {
+            manager_state: InfectionManagerState::Setup,
+            patient_zero: None,
+            spawn_positions,
+            player_penguins: HashMap::default(),
+            infected: HashSet::default(),
+        }
+    }
+
+    pub fn is_infected(&self, player: Entity) -> bool {
+        self.player_penguins
+            .get(&player)
+            .map_or(false, |p| self.infected.contains(p))
+    }
+}
@@ -0,0 +1,174 @@
+use bevy::{prelude::*, utils::HashSet};
+use itertools::Itertools;
+use rand::prelude::IteratorRandom;
+
+use crate::{
+    battle_mode::{spawn_battle_mode_player, BattleModeContext, BattleModeKind, RoundOutcome},
+    game::{
+        components::*,
+        events::{DamageSource, PlayerDeathEvent},
+        resources::{GameRng, GameTextures, GameTimer},
+        rules::GameRules,
+        stats::MatchStats,
+    },
+    AppState,
+};
+
+use super::{
+    constants::{INFECTED_TEAM_ID, SURVIVORS_TEAM_ID},
+    resources::{Infection, InfectionManagerState},
+    utils::infect,
+};
+
+pub fn setup_infection(
+    mut commands: Commands,
+    battle_mode_context: Res<BattleModeContext>,
+    query: Query<(&Penguin, &SpawnPosition), With<Player>>,
+) {
+    if battle_mode_context.kind != BattleModeKind::Infection {
+        return;
+    }
+
+    commands.insert_resource(Infection::new(
+        query.iter().map(|(p, sp)| (*p, sp.0)).collect(),
+    ));
+}
+
+pub fn infection_manager(
+    mut commands: Commands,
+    mut game_rng: ResMut<GameRng>,
+    mut infection: ResMut<Infection>,
+    query: Query<(Entity, &Penguin), With<Player>>,
+) {
+    match infection.manager_state {
+        InfectionManagerState::Setup => {
+            let patient_zero = query
+                .iter()
+                .map(|(_, p)| *p)
+                .sorted_by_key(|p| p.0)
+                .choose(&mut game_rng.gameplay);
+
+            for (entity, penguin) in query.iter() {
+                if Some(*penguin) == patient_zero {
+                    println!("Penguin {} is patient zero!", penguin.0);
+                    infect(&mut commands.entity(entity));
+                    infection.infected.insert(*penguin);
+                } else {
+                    commands.entity(entity).insert(SURVIVORS_TEAM_ID);
+                }
+            }
+
+            infection.patient_zero = patient_zero;
+            infection.manager_state = InfectionManagerState::InPlay;
+        }
+        InfectionManagerState::InPlay | InfectionManagerState::Finished => (),
+    }
+}
+
+pub fn infection_spread(
+    mut commands: Commands,
+    (game_textures, game_rules): (Res<GameTextures>, Res<GameRules>),
+    battle_mode_context: Res<BattleModeContext>,
+    mut infection: ResMut<Infection>,
+    query: Query<(Entity, &Penguin), With<Player>>,
+    mut ev_player_death_event: EventReader<PlayerDeathEvent>,
+) {
+    for (entity, penguin) in query.iter() {
+        infection.player_penguins.insert(entity, *penguin);
+    }
+
+    for (penguin, source) in ev_player_death_event
+        .iter()
+        .filter_map(|e| e.penguin.map(|p| (p, e.source)))
+    {
+        // the infected keep coming back, and so does anyone they take out
+        let infected_by = match source {
+            DamageSource::Bomb(attacker) | DamageSource::Mob(attacker) => {
+                infection.is_infected(attacker)
+            }
+            _ => false,
+        };
+        if !infection.infected.contains(&penguin) {
+            if !infected_by {
+                continue;
+            }
+            println!("Penguin {} got infected!", penguin.0);
+            infection.infected.insert(penguin);
+        }
+
+        let penguin_control_type = match battle_mode_context
+            .players
+            .iter()
+            .find(|(p, _, _)| *p == penguin)
+        {
+            Some((_, penguin_control_type, _)) => *penguin_control_type,
+            None => continue,
+        };
+        let player = spawn_battle_mode_player(
+            &mut commands,
+            &game_textures,
+            penguin,
+            penguin_control_type,
+            INFECTED_TEAM_ID,
+            infection.spawn_positions[&penguin],
+            &game_rules,
+        );
+        let mut entity_commands = commands.entity(player);
+        infect(&mut entity_commands);
+        entity_commands.insert(Immortal::new(game_rules.immortality_secs));
+    }
+}
+
+pub fn finish_infection_round(
+    game_timer: Res<GameTimer>,
+    mut infection: ResMut<Infection>,
+    mut battle_mode_context: ResMut<BattleModeContext>,
+    mut match_stats: Option<ResMut<MatchStats>>,
+    query: Query<(&Penguin, &TeamID), With<Player>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !matches!(infection.manager_state, InfectionManagerState::InPlay) {
+        return;
+    }
+
+    let get_team_id = |penguin: Penguin| {
+        battle_mode_context
+            .players
+            .iter()
+            .find(|(p, _, _)| *p == penguin)
+            .map(|(_, _, t)| *t)
+    };
+    let survivors: Vec<Penguin> = query
+        .iter()
+        .filter(|(_, t)| **t != INFECTED_TEAM_ID)
+        .map(|(p, _)| *p)
+        .collect();
+
+    let round_outcome = if survivors.is_empty() {
+        // the team of patient zero takes the round once everyone is infected
+        Some(match infection.patient_zero.and_then(get_team_id) {
+            Some(team_id) => RoundOutcome::Winner(team_id),
+            None => RoundOutcome::Tie,
+        })
+    } else if game_timer.0.finished() {
+        let team_ids: HashSet<TeamID> = survivors.into_iter().filter_map(get_team_id).collect();
+        Some(RoundOutcome::Winners(
+            team_ids.into_iter().sorted().collect(),
+        ))
+    } else {
+        None
+    };
+
+    if round_outcome.is_some() {
+        battle_mode_context.round_outcome = round_outcome;
+        if let Some(ref mut match_stats) = match_stats {
+            match_stats.rounds += 1;
+        }
+        infection.manager_state = InfectionManagerState::Finished;
+        next_state.set(AppState::BattleModeManager);
+    }
+}
+
+pub fn teardown(mut commands: Commands) {
+    commands.remove_resource::<Infection>();
+}
@@ -0,0 +1,24 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+use crate::{
+    common::constants::COLORS,
+    game::{
+        components::MeleeAttacker,
+        constants::{TILE_HEIGHT, TILE_WIDTH},
+    },
+};
+
+use super::constants::{INFECTED_COLOR, INFECTED_TEAM_ID};
+
+// switches the penguin over to the infected team, which can take out survivors by touching them
+pub fn infect(entity_commands: &mut EntityCommands) {
+    entity_commands.insert((
+        INFECTED_TEAM_ID,
+        MeleeAttacker,
+        Sprite {
+            color: COLORS[INFECTED_COLOR].into(),
+            custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+            ..Default::default()
+        },
+    ));
+}
@@ -0,0 +1,6 @@
+use bevy::{ecs as bevy_ecs, prelude::*};
+
+use crate::game::components::Penguin;
+
+#[derive(Component)]
+pub struct HillScoreDisplay(pub Penguin);
@@ -0,0 +1,4 @@
+pub const HILL_WINNING_SCORE: usize = 30;
+// how long the hill stays at a spot before it moves on to the next one
+pub const HILL_ROTATION_SECS: f32 = 30.0;
+pub const HILL_ALPHA: f32 = 0.35;
@@ -0,0 +1,36 @@
+mod components;
+mod constants;
+mod resources;
+mod systems;
+mod utils;
+
+use bevy::prelude::*;
+
+use crate::{battle_mode::finish_round, game::Set, AppState};
+
+use self::{resources::KingOfTheHill, systems::*};
+
+// plays along with battle mode rounds started as king of the hill
+pub struct KingOfTheHillPlugin;
+
+impl Plugin for KingOfTheHillPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::RoundStartFreeze), setup_king_of_the_hill)
+            .add_systems(
+                Update,
+                (
+                    king_of_the_hill_manager,
+                    apply_deferred,
+                    hill_update,
+                    hill_score_display_update,
+                )
+                    .chain()
+                    .after(Set::TimeUpdate)
+                    .after(Set::PlayerDeathEvent)
+                    .before(finish_round)
+                    .run_if(resource_exists::<KingOfTheHill>())
+                    .run_if(in_state(AppState::BattleModeInGame)),
+            )
+            .add_systems(OnEnter(AppState::BattleModeTeardown), teardown);
+    }
+}
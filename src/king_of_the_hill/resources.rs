@@ -0,0 +1,48 @@
+use bevy::{ecs as bevy_ecs, prelude::*, utils::HashMap};
+
+use crate::game::components::{Position, TeamID};
+
+use super::constants::HILL_ROTATION_SECS;
+
+pub enum KingOfTheHillManagerState {
+    // the hill is yet to be marked on the map
+    Setup,
+    InPlay,
+    // the round has been decided
+    Finished,
+}
+
+#[derive(Resource)]
+pub struct KingOfTheHill {
+    pub manager_state: KingOfTheHillManagerState,
+    // the centers of the spots the hill moves between, starting from the middle of the map
+    pub spots: Vec<Position>,
+    pub current_spot: usize,
+    pub rotation_timer: Timer,
+    pub scores: HashMap<TeamID, usize>,
+    // the team alone on the hill and how long it has been there since its last point
+    pub holder: Option<(TeamID, Timer)>,
+}
+
+impl KingOfTheHill {
+    pub fn new(spots: Vec<Position>, teams: impl Iterator<Item = TeamID>) -> Self {
+        Self {
+            manager_state: KingOfTheHillManagerState::Setup,
+            spots,
+            current_spot: 0,
+            rotation_timer: Timer::from_seconds(HILL_ROTATION_SECS, TimerMode::Repeating),
+            scores: teams.map(|t| (t, 0)).collect(),
+            holder: None,
+        }
+    }
+
+    // the team with the highest score, unless it's a tie
+    pub fn leader(&self) -> Option<TeamID> {
+        let high_score = self.scores.values().max()?;
+        let mut leaders = self.scores.iter().filter(|(_, s)| *s == high_score);
+        match (leaders.next(), leaders.next()) {
+            (Some((team_id, _)), None) => Some(*team_id),
+            _ => None,
+        }
+    }
+}
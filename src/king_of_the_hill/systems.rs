@@ -0,0 +1,204 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    battle_mode::{BattleModeContext, BattleModeKind, RoundOutcome},
+    common::{
+        constants::{COLORS, PIXEL_SCALE},
+        resources::Fonts,
+    },
+    game::{
+        components::*,
+        resources::{GameTimer, HUDColors, MapSize},
+        stats::MatchStats,
+    },
+    AppState,
+};
+
+use super::{
+    components::HillScoreDisplay,
+    constants::HILL_WINNING_SCORE,
+    resources::{KingOfTheHill, KingOfTheHillManagerState},
+    utils::*,
+};
+
+pub fn setup_king_of_the_hill(
+    mut commands: Commands,
+    battle_mode_context: Res<BattleModeContext>,
+    map_size: Res<MapSize>,
+) {
+    if battle_mode_context.kind != BattleModeKind::KingOfTheHill {
+        return;
+    }
+
+    let teams: HashSet<TeamID> = battle_mode_context
+        .players
+        .iter()
+        .map(|(_, _, t)| *t)
+        .collect();
+    commands.insert_resource(KingOfTheHill::new(
+        get_hill_spots(*map_size),
+        teams.into_iter(),
+    ));
+}
+
+pub fn king_of_the_hill_manager(
+    mut commands: Commands,
+    (time, fonts, hud_colors): (Res<Time>, Res<Fonts>, Res<HUDColors>),
+    mut king_of_the_hill: ResMut<KingOfTheHill>,
+    query: Query<Entity, With<Hill>>,
+    query2: Query<&Position, (With<Wall>, Without<Destructible>)>,
+    query3: Query<(Entity, &PenguinPortrait)>,
+) {
+    let stone_wall_positions: HashSet<Position> = query2.iter().copied().collect();
+
+    match king_of_the_hill.manager_state {
+        KingOfTheHillManagerState::Setup => {
+            spawn_hill(
+                &mut commands,
+                king_of_the_hill.spots[0],
+                &stone_wall_positions,
+            );
+
+            for (entity, PenguinPortrait(penguin)) in query3.iter() {
+                commands.entity(entity).with_children(|parent| {
+                    parent.spawn((
+                        TextBundle {
+                            text: Text::from_section(
+                                format!("0/{}", HILL_WINNING_SCORE),
+                                TextStyle {
+                                    font: fonts.mono.clone(),
+                                    font_size: 2.0 * PIXEL_SCALE as f32,
+                                    color: COLORS[15].into(),
+                                },
+                            ),
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(0.0),
+                                top: Val::Px(0.0),
+                                ..Default::default()
+                            },
+                            background_color: hud_colors.black_color.into(),
+                            ..Default::default()
+                        },
+                        UIComponent,
+                        HillScoreDisplay(*penguin),
+                    ));
+                });
+            }
+
+            king_of_the_hill.manager_state = KingOfTheHillManagerState::InPlay;
+        }
+        KingOfTheHillManagerState::InPlay => {
+            king_of_the_hill.rotation_timer.tick(time.delta());
+            if king_of_the_hill.rotation_timer.just_finished() {
+                for entity in query.iter() {
+                    commands.entity(entity).despawn();
+                }
+
+                king_of_the_hill.current_spot =
+                    (king_of_the_hill.current_spot + 1) % king_of_the_hill.spots.len();
+                king_of_the_hill.holder = None;
+                spawn_hill(
+                    &mut commands,
+                    king_of_the_hill.spots[king_of_the_hill.current_spot],
+                    &stone_wall_positions,
+                );
+                println!("The hill moved!");
+            }
+        }
+        KingOfTheHillManagerState::Finished => (),
+    }
+}
+
+pub fn hill_update(
+    time: Res<Time>,
+    mut king_of_the_hill: ResMut<KingOfTheHill>,
+    (game_timer, mut battle_mode_context): (Res<GameTimer>, ResMut<BattleModeContext>),
+    mut match_stats: Option<ResMut<MatchStats>>,
+    query: Query<&Position, With<Hill>>,
+    query2: Query<(&Position, &TeamID), With<Player>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !matches!(
+        king_of_the_hill.manager_state,
+        KingOfTheHillManagerState::InPlay
+    ) {
+        return;
+    }
+
+    let hill_positions: HashSet<Position> = query.iter().copied().collect();
+    let teams_on_hill: HashSet<TeamID> = query2
+        .iter()
+        .filter(|(p, _)| hill_positions.contains(*p))
+        .map(|(_, t)| *t)
+        .collect();
+
+    let mut winner = None;
+    // points are only scored while a single team holds the hill
+    if teams_on_hill.len() == 1 {
+        let team_id = *teams_on_hill.iter().next().unwrap();
+        match king_of_the_hill.holder {
+            Some((holder, _)) if holder == team_id => (),
+            _ => {
+                king_of_the_hill.holder =
+                    Some((team_id, Timer::from_seconds(1.0, TimerMode::Repeating)));
+            }
+        }
+
+        let timer = &mut king_of_the_hill.holder.as_mut().unwrap().1;
+        timer.tick(time.delta());
+        if timer.just_finished() {
+            let score = king_of_the_hill.scores.get_mut(&team_id).unwrap();
+            *score += 1;
+            if *score >= HILL_WINNING_SCORE {
+                winner = Some(team_id);
+            }
+        }
+    } else {
+        king_of_the_hill.holder = None;
+    }
+
+    // the highest score wins once the time runs out, the last team standing still wins before that
+    if winner.is_none() && game_timer.0.finished() {
+        winner = king_of_the_hill.leader();
+    }
+
+    if let Some(team_id) = winner {
+        battle_mode_context.round_outcome = Some(RoundOutcome::Winner(team_id));
+        if let Some(ref mut match_stats) = match_stats {
+            match_stats.rounds += 1;
+        }
+        king_of_the_hill.manager_state = KingOfTheHillManagerState::Finished;
+        next_state.set(AppState::BattleModeManager);
+    }
+}
+
+pub fn hill_score_display_update(
+    king_of_the_hill: Res<KingOfTheHill>,
+    battle_mode_context: Res<BattleModeContext>,
+    mut query: Query<(&HillScoreDisplay, &mut Text)>,
+) {
+    for (HillScoreDisplay(penguin), mut text) in query.iter_mut() {
+        let team_id = match battle_mode_context
+            .players
+            .iter()
+            .find(|(p, _, _)| p.0 == penguin.0)
+        {
+            Some((_, _, team_id)) => *team_id,
+            None => continue,
+        };
+
+        let score = king_of_the_hill.scores.get(&team_id).copied().unwrap_or(0);
+        text.sections[0].value = format!("{}/{}", score, HILL_WINNING_SCORE);
+        // highlighted while the team holds the hill
+        text.sections[0].style.color = match king_of_the_hill.holder {
+            Some((holder, _)) if holder == team_id => COLORS[14],
+            _ => COLORS[15],
+        }
+        .into();
+    }
+}
+
+pub fn teardown(mut commands: Commands) {
+    commands.remove_resource::<KingOfTheHill>();
+}
@@ -0,0 +1,83 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    common::constants::COLORS,
+    game::{
+        components::{Hill, Position},
+        constants::{TILE_HEIGHT, TILE_WIDTH},
+        resources::MapSize,
+        utils::{get_x, get_y},
+    },
+};
+
+use super::constants::HILL_ALPHA;
+
+// the middle of the map followed by the middles of its quarters, kept off the stone wall grid
+pub fn get_hill_spots(map_size: MapSize) -> Vec<Position> {
+    let odd = |v: usize, size: usize| ((v.clamp(3, size.max(6) - 3) - 1) | 1) as isize;
+    let spot = |y: usize, x: usize| Position {
+        y: odd(y, map_size.rows),
+        x: odd(x, map_size.columns),
+    };
+    let (rows, columns) = (map_size.rows, map_size.columns);
+
+    vec![
+        spot(rows / 2, columns / 2),
+        spot(rows / 4, columns / 4),
+        spot(rows * 3 / 4, columns * 3 / 4),
+        spot(rows / 4, columns * 3 / 4),
+        spot(rows * 3 / 4, columns / 4),
+    ]
+}
+
+// marks the tiles around the center, apart from the stone walls
+pub fn spawn_hill(
+    commands: &mut Commands,
+    center: Position,
+    stone_wall_positions: &HashSet<Position>,
+) {
+    for y in center.y - 1..=center.y + 1 {
+        for x in center.x - 1..=center.x + 1 {
+            let position = Position { y, x };
+            if stone_wall_positions.contains(&position) {
+                continue;
+            }
+
+            let color: Color = COLORS[14].into();
+            commands.spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 5.0),
+                    sprite: Sprite {
+                        color: color.with_a(HILL_ALPHA),
+                        custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Hill,
+                position,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hill_spots() {
+        let map_size = MapSize {
+            rows: 11,
+            columns: 15,
+        };
+        let spots = get_hill_spots(map_size);
+        assert_eq!(spots[0], Position { y: 5, x: 7 });
+        for spot in spots {
+            // the whole hill fits inside the outer walls
+            assert!(spot.y >= 2 && spot.y <= map_size.rows as isize - 3);
+            assert!(spot.x >= 2 && spot.x <= map_size.columns as isize - 3);
+            assert!(spot.y % 2 == 1 && spot.x % 2 == 1);
+        }
+    }
+}
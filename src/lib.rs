@@ -1,13 +1,20 @@
 mod audio;
 mod battle_mode;
+mod capture_the_flag;
 mod common;
 mod game;
+mod infection;
+mod king_of_the_hill;
 mod loading;
 mod main_menu;
+mod map_editor;
 mod map_transition;
+mod replay;
 mod secret_mode;
 mod splash_screen;
 mod story_mode;
+mod survival_mode;
+mod tournament;
 #[cfg(target_arch = "wasm32")]
 mod web;
 
@@ -18,17 +25,25 @@ use wasm_bindgen::prelude::*;
 use crate::{
     audio::AudioPlugin,
     battle_mode::BattleModePlugin,
+    capture_the_flag::CaptureTheFlagPlugin,
     common::CommonPlugin,
     game::GamePlugin,
+    infection::InfectionPlugin,
+    king_of_the_hill::KingOfTheHillPlugin,
     main_menu::{MainMenuPlugin, MENU_HEIGHT, MENU_WIDTH},
+    map_editor::MapEditorPlugin,
     map_transition::MapTransitionPlugin,
+    replay::ReplayPlugin,
     secret_mode::SecretModePlugin,
     splash_screen::SplashScreenPlugin,
     story_mode::StoryModePlugin,
+    survival_mode::SurvivalModePlugin,
 };
 #[cfg(target_arch = "wasm32")]
 use crate::{loading::LoadingPlugin, web::*};
 
+pub use crate::{game::simulation, tournament::run_tournament};
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, States)]
 pub enum AppState {
     #[cfg(target_arch = "wasm32")]
@@ -49,12 +64,18 @@ pub enum AppState {
     RoundStartFreeze,
     BattleModeInGame,
     LeaderboardDisplay,
+    MatchStatsDisplay,
     BattleModeTeardown,
     Paused,
     SecretModeSetup,
     SecretModeManager,
     SecretModeInGame,
     SecretModeTeardown,
+    SurvivalModeSetup,
+    SurvivalModeManager,
+    SurvivalModeInGame,
+    SurvivalModeTeardown,
+    MapEditor,
 }
 
 impl Default for AppState {
@@ -122,8 +143,14 @@ pub fn run() {
         GamePlugin,
         MapTransitionPlugin,
         StoryModePlugin,
+        SurvivalModePlugin,
         BattleModePlugin,
+        CaptureTheFlagPlugin,
+        KingOfTheHillPlugin,
+        InfectionPlugin,
         SecretModePlugin,
+        ReplayPlugin,
+        MapEditorPlugin,
     ));
 
     app.run();
@@ -2,9 +2,11 @@ use bevy::{ecs as bevy_ecs, prelude::*, utils::HashMap};
 
 use crate::{
     audio::{SoundHandles, SoundID},
+    battle_mode::BattleModeKind,
     common::{constants::COLORS, resources::GameOption},
-    game::types::BotDifficulty,
+    game::{map_generators::MapGeneratorKind, rules::SuddenDeathKind, types::BotDifficulty},
     loading::resources::AssetsLoading,
+    story_mode::{Level, StorySaveSlots},
 };
 
 #[derive(Resource)]
@@ -55,8 +57,19 @@ impl FromWorld for MainMenuSoundEffects {
 #[derive(Clone, Copy)]
 pub enum MenuAction {
     SwitchMenu(usize),
-    LaunchStoryMode,
+    // with the amount of players of a new run, none to continue a saved one
+    OpenStorySaveSlots(Option<usize>),
+    // with the amount of players and the save slot
+    LaunchStoryMode(usize, usize),
+    // asks before a new run takes over the save slot, with the same values as above
+    ConfirmStorySaveOverwrite(usize, usize),
+    // with the save slot
+    ContinueStoryMode(usize),
+    // with the amount of players
+    LaunchSurvivalMode(usize),
     OpenBattleModeSubMenu,
+    LaunchReplay,
+    LaunchMapEditor,
     ToggleOption(GameOption),
     Back,
     Exit,
@@ -143,13 +156,27 @@ pub enum MenuType {
     StaticText(&'static str),
     ControlsScreen(&'static str),
     HallOfFame,
+    BattleHallOfFame,
+    SurvivalHallOfFame,
+    // the descriptions of what each slot holds (or of the overwrite choices) are shown in place of
+    // the item names
+    StorySaveSlots(SelectableItems, Vec<String>),
 }
 
 pub enum BattleModeSubMenuStep {
     AmountOfPlayers,
     AmountOfBots,
+    Kind,
     WinningScore,
     BotDifficulty,
+    Map,
+    Rules,
+    SuddenDeath,
+    RevengeGhosts,
+    Teams,
+    // the team of a penguin
+    Team(usize),
+    FriendlyFire,
 }
 
 pub struct ConfigurationOption<T> {
@@ -299,11 +326,27 @@ impl AmountOfActors {
     }
 }
 
+pub enum MapOption {
+    RandomGenerator,
+    Generator(MapGeneratorKind),
+    Handcrafted(String),
+}
+
 pub struct BattleModeSubMenuState {
     pub amount_of_actors: AmountOfActors,
     pub step: BattleModeSubMenuStep,
+    pub kind: ConfigurationOption<BattleModeKind>,
     pub winning_score: ConfigurationOption<usize>,
     pub difficulty: ConfigurationOption<BotDifficulty>,
+    pub map: ConfigurationOption<MapOption>,
+    // ruleset names
+    pub rules: ConfigurationOption<String>,
+    pub sudden_death: ConfigurationOption<SuddenDeathKind>,
+    pub revenge_ghosts: ConfigurationOption<bool>,
+    // the amount of teams, everyone is on their own if missing
+    pub teams: ConfigurationOption<Option<usize>>,
+    pub team_assignment: Vec<ConfigurationOption<usize>>,
+    pub friendly_fire: ConfigurationOption<bool>,
 }
 
 impl Default for BattleModeSubMenuState {
@@ -311,6 +354,7 @@ impl Default for BattleModeSubMenuState {
         Self {
             step: BattleModeSubMenuStep::AmountOfPlayers,
             amount_of_actors: AmountOfActors::new(1, 3).unwrap(),
+            kind: ConfigurationOption::new(BattleModeKind::LIST.to_vec(), 0).unwrap(),
             winning_score: ConfigurationOption::new((1..=5).collect(), 2).unwrap(),
             difficulty: ConfigurationOption::new(
                 vec![
@@ -321,6 +365,13 @@ impl Default for BattleModeSubMenuState {
                 1,
             )
             .unwrap(),
+            map: ConfigurationOption::new(vec![MapOption::RandomGenerator], 0).unwrap(),
+            rules: ConfigurationOption::new(vec![String::from("classic")], 0).unwrap(),
+            sudden_death: ConfigurationOption::new(SuddenDeathKind::LIST.to_vec(), 0).unwrap(),
+            revenge_ghosts: ConfigurationOption::new(vec![false, true], 0).unwrap(),
+            teams: ConfigurationOption::new(vec![None], 0).unwrap(),
+            team_assignment: vec![],
+            friendly_fire: ConfigurationOption::new(vec![false, true], 0).unwrap(),
         }
     }
 }
@@ -350,8 +401,12 @@ impl Default for MenuState {
                 initial_state.clone(),
                 MenuType::SelectableItems(SelectableItems {
                     items: vec![
-                        ("STORY MODE", MenuAction::LaunchStoryMode),
+                        ("STORY MODE", MenuAction::SwitchMenu(12)),
+                        ("CONTINUE", MenuAction::OpenStorySaveSlots(None)),
                         ("BATTLE MODE", MenuAction::OpenBattleModeSubMenu),
+                        ("SURVIVAL", MenuAction::SwitchMenu(11)),
+                        ("REPLAY", MenuAction::LaunchReplay),
+                        ("MAP EDITOR", MenuAction::LaunchMapEditor),
                     ],
                     cursor_position: 0,
                 }),
@@ -364,7 +419,14 @@ impl Default for MenuState {
                     ],
                     cursor_position: 0,
                 }),
-                MenuType::HallOfFame,
+                MenuType::SelectableItems(SelectableItems {
+                    items: vec![
+                        ("STORY", MenuAction::SwitchMenu(8)),
+                        ("BATTLE", MenuAction::SwitchMenu(9)),
+                        ("SURVIVAL", MenuAction::SwitchMenu(10)),
+                    ],
+                    cursor_position: 0,
+                }),
                 MenuType::StaticText(
                     r"
 You are a penguin. With a top hat.
@@ -387,12 +449,13 @@ Arrow Keys    - P1 movement
 Space Bar     - P1 bomb set
 WASD Keys     - P2 movement
 G Key         - P2 bomb set
+M Key         - P1 detonate
+N Key         - P1 punch
+H Key         - P2 detonate
+J Key         - P2 punch
 Enter Key     - Pause
 ESC Key       - Back
 
-
-
-
 F Key - ???
 "
                     .trim_matches('\n'),
@@ -405,9 +468,30 @@ LIFE UP       - H+
 BOMB PUSHING  - Boot
 WALL CLIMBING - Ladders
 INVINCIBILITY - Top Hat
+DETONATOR     - Remote
+BOMB THROWING - Glove
+PIERCE BOMBS  - Spike
+LINE BOMBS    - Bomb Row
 "
                     .trim_matches('\n'),
                 ),
+                MenuType::HallOfFame,
+                MenuType::BattleHallOfFame,
+                MenuType::SurvivalHallOfFame,
+                MenuType::SelectableItems(SelectableItems {
+                    items: vec![
+                        ("1 PLAYER", MenuAction::LaunchSurvivalMode(1)),
+                        ("2 PLAYERS", MenuAction::LaunchSurvivalMode(2)),
+                    ],
+                    cursor_position: 0,
+                }),
+                MenuType::SelectableItems(SelectableItems {
+                    items: vec![
+                        ("1 PLAYER", MenuAction::OpenStorySaveSlots(Some(1))),
+                        ("2 PLAYERS", MenuAction::OpenStorySaveSlots(Some(2))),
+                    ],
+                    cursor_position: 0,
+                }),
             ],
             menu_stack: vec![initial_state],
             battle_mode_sub_menu_state: None,
@@ -426,11 +510,14 @@ impl MenuState {
 
     pub fn get_enter_action(&self) -> MenuAction {
         match self.get_current_menu() {
-            MenuType::SelectableItems(selectable_items) => selectable_items.get_action(),
+            MenuType::SelectableItems(selectable_items)
+            | MenuType::StorySaveSlots(selectable_items, _) => selectable_items.get_action(),
             MenuType::ToggleableOptions(toggleable_options) => toggleable_options.get_action(),
-            MenuType::StaticText(_) | MenuType::ControlsScreen(_) | MenuType::HallOfFame => {
-                MenuAction::Back
-            }
+            MenuType::StaticText(_)
+            | MenuType::ControlsScreen(_)
+            | MenuType::HallOfFame
+            | MenuType::BattleHallOfFame
+            | MenuType::SurvivalHallOfFame => MenuAction::Back,
         }
     }
 
@@ -438,6 +525,78 @@ impl MenuState {
         self.menu_stack.push(self.menu_types[menu_id].clone());
     }
 
+    // built on the spot since what the slots hold changes with every story run
+    pub fn open_story_save_slots(
+        &mut self,
+        new_run_amount_of_players: Option<usize>,
+        story_save_slots: &StorySaveSlots,
+    ) {
+        let (items, slot_descriptions) = story_save_slots
+            .iter()
+            .enumerate()
+            .map(|(slot, story_save)| {
+                let action = match (new_run_amount_of_players, story_save) {
+                    (Some(amount_of_players), None) => {
+                        MenuAction::LaunchStoryMode(amount_of_players, slot)
+                    }
+                    (Some(amount_of_players), Some(_)) => {
+                        MenuAction::ConfirmStorySaveOverwrite(amount_of_players, slot)
+                    }
+                    (None, _) => MenuAction::ContinueStoryMode(slot),
+                };
+                let slot_description = match story_save {
+                    // a new run would take over the slot
+                    Some(story_save) if new_run_amount_of_players.is_some() => format!(
+                        "SLOT {}  IN USE  WORLD {}-{}",
+                        slot + 1,
+                        story_save.world_id,
+                        match story_save.level {
+                            Level::Regular(num) => num.to_string(),
+                            Level::BossRoom => String::from("BOSS"),
+                        },
+                    ),
+                    Some(story_save) => format!(
+                        "SLOT {}  {}P  WORLD {}-{}  {:>6}",
+                        slot + 1,
+                        story_save.amount_of_players,
+                        story_save.world_id,
+                        match story_save.level {
+                            Level::Regular(num) => num.to_string(),
+                            Level::BossRoom => String::from("BOSS"),
+                        },
+                        story_save.score
+                    ),
+                    None => format!("SLOT {}  EMPTY", slot + 1),
+                };
+                (("", action), slot_description)
+            })
+            .unzip();
+
+        self.menu_stack.push(MenuType::StorySaveSlots(
+            SelectableItems {
+                items,
+                cursor_position: 0,
+            },
+            slot_descriptions,
+        ));
+    }
+
+    pub fn open_story_save_overwrite_prompt(&mut self, amount_of_players: usize, slot: usize) {
+        self.menu_stack.push(MenuType::StorySaveSlots(
+            SelectableItems {
+                items: vec![
+                    ("", MenuAction::Back),
+                    ("", MenuAction::LaunchStoryMode(amount_of_players, slot)),
+                ],
+                cursor_position: 0,
+            },
+            vec![
+                format!("KEEP SLOT {}", slot + 1),
+                format!("OVERWRITE SLOT {}", slot + 1),
+            ],
+        ));
+    }
+
     pub fn back(&mut self) -> Result<(), ()> {
         if self.menu_stack.len() > 1 {
             self.menu_stack.pop();
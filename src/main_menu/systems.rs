@@ -1,16 +1,26 @@
 use bevy::{app::AppExit, prelude::*, utils::HashMap, window::PrimaryWindow};
+use itertools::Itertools;
 
 use crate::{
     audio::Audio,
-    battle_mode::BattleModeConfiguration,
+    battle_mode::{BattleModeConfiguration, BattleModeKind},
     common::{
         constants::{COLORS, PIXEL_SCALE},
         resources::{
-            Fonts, GameOption, GameOptionStore, InputActionStatusTracker, PersistentHighScores,
+            Fonts, GameOption, GameOptionStore, InputActionStatusTracker, PersistentBattleRecords,
+            PersistentHighScores, PersistentSurvivalHighScores,
         },
         types::InputAction,
     },
-    game::types::BotDifficulty,
+    game::{
+        map_generators::MapGeneratorKind,
+        resources::MapLibrary,
+        rules::{GameRules, RulesetLibrary, SuddenDeathKind},
+        types::BotDifficulty,
+    },
+    replay::{setup_playback, Replay},
+    story_mode::{StoryModeConfiguration, StorySaveSlots},
+    survival_mode::SurvivalModeConfiguration,
     AppState,
 };
 
@@ -18,9 +28,10 @@ use super::{
     components::{BattleModeSubMenuContentBox, MenuContentBox},
     constants::{DEMO_MODE_START_TIMER_DURATION_SECS, MENU_HEIGHT, MENU_WIDTH},
     resources::{
-        BattleModeSubMenuState, BattleModeSubMenuStep, DemoModeStartTimer, MainMenuSoundEffects,
-        MenuAction, MenuBackgroundAnimationContext, MenuBackgroundEntityChangeParameters,
-        MenuBackgroundEntityValues, MenuColors, MenuState, MenuType,
+        BattleModeSubMenuState, BattleModeSubMenuStep, ConfigurationOption, DemoModeStartTimer,
+        MainMenuSoundEffects, MapOption, MenuAction, MenuBackgroundAnimationContext,
+        MenuBackgroundEntityChangeParameters, MenuBackgroundEntityValues, MenuColors, MenuState,
+        MenuType,
     },
     utils::{
         spawn_battle_mode_sub_menu_content, spawn_battle_mode_sub_menu_modal, spawn_menu_type,
@@ -41,7 +52,11 @@ pub fn setup_menu(
     menu_colors: Res<MenuColors>,
     menu_state: Res<MenuState>,
     game_option_store: Res<GameOptionStore>,
-    persistent_high_scores: Res<PersistentHighScores>,
+    (persistent_high_scores, persistent_survival_high_scores, persistent_battle_records): (
+        Res<PersistentHighScores>,
+        Res<PersistentSurvivalHighScores>,
+        Res<PersistentBattleRecords>,
+    ),
 ) {
     commands.spawn(Camera2dBundle::default());
 
@@ -262,7 +277,11 @@ __██__
                                 &fonts,
                                 &menu_colors,
                                 &game_option_store,
-                                persistent_high_scores.get_raw_scores(),
+                                (
+                                    persistent_high_scores.get_raw_scores(),
+                                    persistent_survival_high_scores.get_raw_scores(),
+                                ),
+                                persistent_battle_records.get_raw_records(),
                             );
                         });
                 });
@@ -287,7 +306,13 @@ pub fn menu_navigation(
     mut next_state: ResMut<NextState<AppState>>,
     mut menu_state: ResMut<MenuState>,
     mut game_option_store: ResMut<GameOptionStore>,
-    persistent_high_scores: Res<PersistentHighScores>,
+    (persistent_high_scores, persistent_survival_high_scores, persistent_battle_records): (
+        Res<PersistentHighScores>,
+        Res<PersistentSurvivalHighScores>,
+        Res<PersistentBattleRecords>,
+    ),
+    (map_library, ruleset_library): (Res<MapLibrary>, Res<RulesetLibrary>),
+    story_save_slots: Res<StorySaveSlots>,
     inputs: Res<InputActionStatusTracker>,
     mut query: Query<(Entity, &Children), With<MenuContentBox>>,
     mut query3: Query<(Entity, &Children), With<BattleModeSubMenuContentBox>>,
@@ -303,8 +328,16 @@ pub fn menu_navigation(
                 BattleModeSubMenuStep::AmountOfBots => {
                     sub_menu_state.amount_of_actors.decrement_amount_of_bots()
                 }
+                BattleModeSubMenuStep::Kind => sub_menu_state.kind.decrement(),
                 BattleModeSubMenuStep::WinningScore => sub_menu_state.winning_score.decrement(),
                 BattleModeSubMenuStep::BotDifficulty => sub_menu_state.difficulty.decrement(),
+                BattleModeSubMenuStep::Map => sub_menu_state.map.decrement(),
+                BattleModeSubMenuStep::Rules => sub_menu_state.rules.decrement(),
+                BattleModeSubMenuStep::SuddenDeath => sub_menu_state.sudden_death.decrement(),
+                BattleModeSubMenuStep::RevengeGhosts => sub_menu_state.revenge_ghosts.decrement(),
+                BattleModeSubMenuStep::Teams => sub_menu_state.teams.decrement(),
+                BattleModeSubMenuStep::Team(i) => sub_menu_state.team_assignment[i].decrement(),
+                BattleModeSubMenuStep::FriendlyFire => sub_menu_state.friendly_fire.decrement(),
             }
             menu_changed = true;
         }
@@ -317,35 +350,170 @@ pub fn menu_navigation(
                 BattleModeSubMenuStep::AmountOfBots => {
                     sub_menu_state.amount_of_actors.increment_amount_of_bots()
                 }
+                BattleModeSubMenuStep::Kind => sub_menu_state.kind.increment(),
                 BattleModeSubMenuStep::WinningScore => sub_menu_state.winning_score.increment(),
                 BattleModeSubMenuStep::BotDifficulty => sub_menu_state.difficulty.increment(),
+                BattleModeSubMenuStep::Map => sub_menu_state.map.increment(),
+                BattleModeSubMenuStep::Rules => sub_menu_state.rules.increment(),
+                BattleModeSubMenuStep::SuddenDeath => sub_menu_state.sudden_death.increment(),
+                BattleModeSubMenuStep::RevengeGhosts => sub_menu_state.revenge_ghosts.increment(),
+                BattleModeSubMenuStep::Teams => sub_menu_state.teams.increment(),
+                BattleModeSubMenuStep::Team(i) => sub_menu_state.team_assignment[i].increment(),
+                BattleModeSubMenuStep::FriendlyFire => sub_menu_state.friendly_fire.increment(),
             }
             menu_changed = true;
         }
 
         if inputs.is_active(InputAction::Return) || inputs.is_active(InputAction::Space) {
+            let amount_of_actors = sub_menu_state.amount_of_actors.amount_of_players()
+                + sub_menu_state.amount_of_actors.amount_of_bots();
+            let mut launch = false;
             match sub_menu_state.step {
                 BattleModeSubMenuStep::AmountOfPlayers => {
                     sub_menu_state.step = BattleModeSubMenuStep::AmountOfBots
                 }
                 BattleModeSubMenuStep::AmountOfBots => {
+                    sub_menu_state.step = BattleModeSubMenuStep::Kind
+                }
+                BattleModeSubMenuStep::Kind => {
                     sub_menu_state.step = BattleModeSubMenuStep::WinningScore
                 }
                 BattleModeSubMenuStep::WinningScore => {
                     sub_menu_state.step = BattleModeSubMenuStep::BotDifficulty
                 }
                 BattleModeSubMenuStep::BotDifficulty => {
-                    commands.insert_resource(BattleModeConfiguration {
-                        amount_of_players: sub_menu_state.amount_of_actors.amount_of_players(),
-                        amount_of_bots: sub_menu_state.amount_of_actors.amount_of_bots(),
-                        winning_score: *sub_menu_state.winning_score.value(),
-                        bot_difficulty: *sub_menu_state.difficulty.value(),
-                    });
-
-                    menu_state.battle_mode_sub_menu_state = None;
-                    next_state.set(AppState::BattleModeSetup);
-                    return;
+                    // only offer the maps that have a spawn point for everyone
+                    sub_menu_state.map = ConfigurationOption::new(
+                        std::iter::once(MapOption::RandomGenerator)
+                            .chain(MapGeneratorKind::LIST.into_iter().map(MapOption::Generator))
+                            .chain(
+                                map_library
+                                    .iter()
+                                    .filter(|(_, m)| {
+                                        m.penguin_spawn_positions().len() >= amount_of_actors
+                                    })
+                                    .map(|(n, _)| MapOption::Handcrafted(n.to_string())),
+                            )
+                            .collect(),
+                        0,
+                    )
+                    .unwrap();
+                    sub_menu_state.step = BattleModeSubMenuStep::Map
+                }
+                BattleModeSubMenuStep::Map => {
+                    sub_menu_state.rules = ConfigurationOption::new(
+                        ruleset_library.iter().map(|(n, _)| n.to_string()).collect(),
+                        0,
+                    )
+                    .unwrap();
+                    sub_menu_state.step = BattleModeSubMenuStep::Rules
+                }
+                BattleModeSubMenuStep::Rules => {
+                    // the ruleset's sudden death is picked by default
+                    let sudden_death = ruleset_library
+                        .get(sub_menu_state.rules.value())
+                        .unwrap()
+                        .sudden_death;
+                    sub_menu_state.sudden_death = ConfigurationOption::new(
+                        SuddenDeathKind::LIST.to_vec(),
+                        SuddenDeathKind::LIST
+                            .iter()
+                            .position(|k| *k == sudden_death)
+                            .unwrap(),
+                    )
+                    .unwrap();
+                    sub_menu_state.step = BattleModeSubMenuStep::SuddenDeath
+                }
+                BattleModeSubMenuStep::SuddenDeath => {
+                    let revenge_ghosts = ruleset_library
+                        .get(sub_menu_state.rules.value())
+                        .unwrap()
+                        .revenge_ghosts;
+                    sub_menu_state.revenge_ghosts =
+                        ConfigurationOption::new(vec![false, true], revenge_ghosts as usize)
+                            .unwrap();
+                    sub_menu_state.step = BattleModeSubMenuStep::RevengeGhosts
+                }
+                BattleModeSubMenuStep::RevengeGhosts => {
+                    sub_menu_state.teams = ConfigurationOption::new(
+                        std::iter::once(None)
+                            .chain((2..=amount_of_actors.min(4)).map(Some))
+                            .collect(),
+                        0,
+                    )
+                    .unwrap();
+                    sub_menu_state.step = BattleModeSubMenuStep::Teams
                 }
+                BattleModeSubMenuStep::Teams => {
+                    if let Some(amount_of_teams) = *sub_menu_state.teams.value() {
+                        // deal the penguins out to the teams by default
+                        sub_menu_state.team_assignment = (0..amount_of_actors)
+                            .map(|i| {
+                                ConfigurationOption::new(
+                                    (0..amount_of_teams).collect(),
+                                    i % amount_of_teams,
+                                )
+                                .unwrap()
+                            })
+                            .collect();
+                        sub_menu_state.step = BattleModeSubMenuStep::Team(0)
+                    } else {
+                        launch = true;
+                    }
+                }
+                BattleModeSubMenuStep::Team(i) => {
+                    if i + 1 < amount_of_actors {
+                        sub_menu_state.step = BattleModeSubMenuStep::Team(i + 1)
+                    } else if sub_menu_state
+                        .team_assignment
+                        .iter()
+                        .map(|t| t.value())
+                        .all_equal()
+                    {
+                        println!("At least two teams have to take part in the battle.");
+                    } else {
+                        sub_menu_state.step = BattleModeSubMenuStep::FriendlyFire
+                    }
+                }
+                BattleModeSubMenuStep::FriendlyFire => launch = true,
+            }
+
+            if launch {
+                let mut rules = ruleset_library
+                    .get(sub_menu_state.rules.value())
+                    .cloned()
+                    .unwrap();
+                rules.sudden_death = *sub_menu_state.sudden_death.value();
+                rules.revenge_ghosts = *sub_menu_state.revenge_ghosts.value();
+                let teams = sub_menu_state.teams.value().map(|_| {
+                    rules.friendly_fire = *sub_menu_state.friendly_fire.value();
+                    sub_menu_state
+                        .team_assignment
+                        .iter()
+                        .map(|t| *t.value())
+                        .collect()
+                });
+                commands.insert_resource(BattleModeConfiguration {
+                    amount_of_players: sub_menu_state.amount_of_actors.amount_of_players(),
+                    amount_of_bots: sub_menu_state.amount_of_actors.amount_of_bots(),
+                    winning_score: *sub_menu_state.winning_score.value(),
+                    bot_difficulty: *sub_menu_state.difficulty.value(),
+                    map: match sub_menu_state.map.value() {
+                        MapOption::Handcrafted(map_name) => map_library.get(map_name).cloned(),
+                        _ => None,
+                    },
+                    map_generator: match sub_menu_state.map.value() {
+                        MapOption::Generator(map_generator_kind) => Some(*map_generator_kind),
+                        _ => None,
+                    },
+                    rules,
+                    teams,
+                    kind: *sub_menu_state.kind.value(),
+                });
+
+                menu_state.battle_mode_sub_menu_state = None;
+                next_state.set(AppState::BattleModeSetup);
+                return;
             }
             menu_changed = true;
         }
@@ -362,10 +530,53 @@ pub fn menu_navigation(
                     menu_state.switch_menu(menu_id);
                     menu_changed = true;
                 }
-                MenuAction::LaunchStoryMode => {
+                MenuAction::OpenStorySaveSlots(new_run_amount_of_players) => {
+                    menu_state.open_story_save_slots(new_run_amount_of_players, &story_save_slots);
+                    menu_changed = true;
+                }
+                MenuAction::ConfirmStorySaveOverwrite(amount_of_players, save_slot) => {
+                    menu_state.open_story_save_overwrite_prompt(amount_of_players, save_slot);
+                    menu_changed = true;
+                }
+                MenuAction::LaunchStoryMode(amount_of_players, save_slot) => {
+                    commands.insert_resource(StoryModeConfiguration {
+                        amount_of_players,
+                        save_slot: Some(save_slot),
+                        continue_from: None,
+                    });
                     next_state.set(AppState::StoryModeSetup);
                     return;
                 }
+                MenuAction::ContinueStoryMode(save_slot) => {
+                    if let Some(story_save) = story_save_slots.get(save_slot) {
+                        commands.insert_resource(StoryModeConfiguration {
+                            amount_of_players: story_save.amount_of_players,
+                            save_slot: Some(save_slot),
+                            continue_from: Some(story_save.clone()),
+                        });
+                        next_state.set(AppState::StoryModeSetup);
+                        return;
+                    } else {
+                        println!("Save slot {} is empty.", save_slot + 1);
+                    }
+                }
+                MenuAction::LaunchSurvivalMode(amount_of_players) => {
+                    commands.insert_resource(SurvivalModeConfiguration { amount_of_players });
+                    next_state.set(AppState::SurvivalModeSetup);
+                    return;
+                }
+                MenuAction::LaunchMapEditor => {
+                    next_state.set(AppState::MapEditor);
+                    return;
+                }
+                MenuAction::LaunchReplay => {
+                    if let Some(replay) = Replay::load() {
+                        next_state.set(setup_playback(&mut commands, replay, 0));
+                        return;
+                    } else {
+                        println!("No replay to play back.");
+                    }
+                }
                 MenuAction::OpenBattleModeSubMenu => {
                     let sub_menu_state = BattleModeSubMenuState::default();
                     commands.entity(query.single().0).with_children(|parent| {
@@ -422,7 +633,8 @@ pub fn menu_navigation(
 
         if inputs.is_active(InputAction::Down) {
             match menu_state.get_current_menu_mut() {
-                MenuType::SelectableItems(selectable_items) => {
+                MenuType::SelectableItems(selectable_items)
+                | MenuType::StorySaveSlots(selectable_items, _) => {
                     audio.play(sounds.select);
                     selectable_items.cycle_cursor_up();
                     menu_changed = true;
@@ -432,13 +644,18 @@ pub fn menu_navigation(
                     toggleable_options.cycle_cursor_up();
                     menu_changed = true;
                 }
-                MenuType::StaticText(_) | MenuType::ControlsScreen(_) | MenuType::HallOfFame => (),
+                MenuType::StaticText(_)
+                | MenuType::ControlsScreen(_)
+                | MenuType::HallOfFame
+                | MenuType::BattleHallOfFame
+                | MenuType::SurvivalHallOfFame => (),
             }
         }
 
         if inputs.is_active(InputAction::Up) {
             match menu_state.get_current_menu_mut() {
-                MenuType::SelectableItems(selectable_items) => {
+                MenuType::SelectableItems(selectable_items)
+                | MenuType::StorySaveSlots(selectable_items, _) => {
                     audio.play(sounds.select);
                     selectable_items.cycle_cursor_down();
                     menu_changed = true;
@@ -448,7 +665,11 @@ pub fn menu_navigation(
                     toggleable_options.cycle_cursor_down();
                     menu_changed = true;
                 }
-                MenuType::StaticText(_) | MenuType::ControlsScreen(_) | MenuType::HallOfFame => (),
+                MenuType::StaticText(_)
+                | MenuType::ControlsScreen(_)
+                | MenuType::HallOfFame
+                | MenuType::BattleHallOfFame
+                | MenuType::SurvivalHallOfFame => (),
             }
         }
 
@@ -483,7 +704,11 @@ pub fn menu_navigation(
                     &fonts,
                     &menu_colors,
                     &game_option_store,
-                    persistent_high_scores.get_raw_scores(),
+                    (
+                        persistent_high_scores.get_raw_scores(),
+                        persistent_survival_high_scores.get_raw_scores(),
+                    ),
+                    persistent_battle_records.get_raw_records(),
                 );
             });
         }
@@ -511,6 +736,11 @@ pub fn menu_demo_mode_trigger(
                         amount_of_bots: 8,
                         winning_score: 1,
                         bot_difficulty: BotDifficulty::Medium,
+                        map: None,
+                        map_generator: None,
+                        rules: GameRules::default(),
+                        teams: None,
+                        kind: BattleModeKind::LastStanding,
                     });
                     next_state.set(AppState::BattleModeSetup);
                 }
@@ -3,14 +3,14 @@ use bevy::prelude::*;
 use crate::{
     common::{
         constants::PIXEL_SCALE,
-        resources::{Fonts, GameOption, GameOptionStore, HighScores},
+        resources::{BattleRecord, BattleRecords, Fonts, GameOption, GameOptionStore, HighScores},
     },
     game::types::BotDifficulty,
 };
 
 use super::{
     components::{BattleModeSubMenuContentBox, Cursor},
-    resources::{BattleModeSubMenuState, BattleModeSubMenuStep, MenuColors, MenuType},
+    resources::{BattleModeSubMenuState, BattleModeSubMenuStep, MapOption, MenuColors, MenuType},
 };
 
 pub fn spawn_menu_type(
@@ -19,13 +19,19 @@ pub fn spawn_menu_type(
     fonts: &Fonts,
     menu_colors: &MenuColors,
     game_option_store: &GameOptionStore,
-    high_scores: &HighScores,
+    (high_scores, survival_high_scores): (&HighScores, &HighScores),
+    battle_records: &BattleRecords,
 ) {
     match menu_type {
-        MenuType::SelectableItems(selectable_items) => {
+        MenuType::SelectableItems(selectable_items)
+        | MenuType::StorySaveSlots(selectable_items, _) => {
+            let item_names = match menu_type {
+                MenuType::StorySaveSlots(_, slot_descriptions) => slot_descriptions.join("\n\n"),
+                _ => selectable_items.get_item_names().join("\n\n"),
+            };
             parent.spawn(TextBundle {
                 text: Text::from_section(
-                    selectable_items.get_item_names().join("\n\n"),
+                    item_names,
                     TextStyle {
                         font: fonts.mono.clone(),
                         font_size: 2.0 * PIXEL_SCALE as f32,
@@ -181,10 +187,24 @@ pub fn spawn_menu_type(
                     });
                 });
         }
-        MenuType::HallOfFame => {
+        MenuType::HallOfFame | MenuType::BattleHallOfFame | MenuType::SurvivalHallOfFame => {
+            let (title, title_left, text) = match menu_type {
+                MenuType::HallOfFame => ("HIGH-SCORES", 13.0, get_high_scores_text(high_scores)),
+                MenuType::SurvivalHallOfFame => (
+                    "SURVIVAL SCORES",
+                    12.0,
+                    get_high_scores_text(survival_high_scores),
+                ),
+                _ => (
+                    "BATTLE RECORDS",
+                    12.0,
+                    get_battle_records_text(battle_records),
+                ),
+            };
+
             parent.spawn(TextBundle {
                 text: Text::from_section(
-                    "HIGH-SCORES",
+                    title,
                     TextStyle {
                         font: fonts.mono.clone(),
                         font_size: 2.0 * PIXEL_SCALE as f32,
@@ -194,25 +214,15 @@ pub fn spawn_menu_type(
                 style: Style {
                     position_type: PositionType::Absolute,
                     top: Val::Px(2.0 * PIXEL_SCALE as f32),
-                    left: Val::Px(13.0 * PIXEL_SCALE as f32),
+                    left: Val::Px(title_left * PIXEL_SCALE as f32),
                     ..Default::default()
                 },
                 ..Default::default()
             });
 
-            let high_scores_text = (0..HighScores::HIGH_SCORES_MAX_SIZE)
-                .map(|i| {
-                    if let Some(score) = high_scores.0.get(i) {
-                        format!("{:>2}. {:<20}{:>10}", i + 1, score.0, score.1)
-                    } else {
-                        format!("{:>2}. -----                        0", i + 1)
-                    }
-                })
-                .collect::<Vec<String>>()
-                .join("\n");
             parent.spawn(TextBundle {
                 text: Text::from_section(
-                    high_scores_text,
+                    text,
                     TextStyle {
                         font: fonts.mono.clone(),
                         font_size: 2.0 * PIXEL_SCALE as f32,
@@ -265,6 +275,66 @@ pub fn spawn_menu_type(
     }
 }
 
+fn get_high_scores_text(high_scores: &HighScores) -> String {
+    (0..HighScores::HIGH_SCORES_MAX_SIZE)
+        .map(|i| {
+            if let Some(score) = high_scores.0.get(i) {
+                // runs continued from a save are marked
+                format!(
+                    "{:>2}. {:<20}{:>10}{}",
+                    i + 1,
+                    score.0,
+                    score.1,
+                    if score.2 { "*" } else { "" }
+                )
+            } else {
+                format!("{:>2}. -----                        0", i + 1)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn get_battle_records_text(battle_records: &BattleRecords) -> String {
+    let records: Vec<BattleRecord> = (0..BattleRecords::MAX_PLAYERS)
+        .map(|p| battle_records.get(p).cloned().unwrap_or_default())
+        .collect();
+
+    let mut lines = vec![format!("{:<18}{:>8}{:>8}", "", "P1", "P2"), String::new()];
+    let mut add_row = |label: &str, value: fn(&BattleRecord) -> String| {
+        lines.push(format!(
+            "{:<18}{:>8}{:>8}",
+            label,
+            value(&records[0]),
+            value(&records[1])
+        ));
+    };
+    add_row("TOURNAMENTS WON", |r| {
+        format!("{}/{}", r.tournaments.won, r.tournaments.played)
+    });
+    add_row("ROUNDS WON", |r| {
+        format!("{}/{}", r.rounds.won, r.rounds.played)
+    });
+    add_row("WIN STREAK", |r| r.win_streak.to_string());
+    add_row("LONGEST STREAK", |r| r.longest_win_streak.to_string());
+    add_row("", |_| String::new());
+    add_row("VS EASY BOTS", |r| format_win_rate(r, BotDifficulty::Easy));
+    add_row("VS MEDIUM BOTS", |r| {
+        format_win_rate(r, BotDifficulty::Medium)
+    });
+    add_row("VS HARD BOTS", |r| format_win_rate(r, BotDifficulty::Hard));
+
+    lines.join("\n")
+}
+
+fn format_win_rate(battle_record: &BattleRecord, bot_difficulty: BotDifficulty) -> String {
+    battle_record
+        .vs_bots
+        .get(&bot_difficulty)
+        .and_then(|r| r.win_rate())
+        .map_or(String::from("--"), |wr| format!("{:.0}%", wr * 100.0))
+}
+
 pub fn spawn_battle_mode_sub_menu_modal(
     parent: &mut ChildBuilder,
     sub_menu_state: &BattleModeSubMenuState,
@@ -348,10 +418,25 @@ pub fn spawn_battle_mode_sub_menu_content(
     parent.spawn(TextBundle {
         text: Text::from_section(
             match sub_menu_state.step {
-                BattleModeSubMenuStep::AmountOfPlayers => "AMOUNT OF PLAYERS:",
-                BattleModeSubMenuStep::AmountOfBots => "AMOUNT OF BOTS:",
-                BattleModeSubMenuStep::WinningScore => "AMOUNT OF WINS:",
-                BattleModeSubMenuStep::BotDifficulty => "DIFFICULTY:",
+                BattleModeSubMenuStep::AmountOfPlayers => String::from("AMOUNT OF PLAYERS:"),
+                BattleModeSubMenuStep::AmountOfBots => String::from("AMOUNT OF BOTS:"),
+                BattleModeSubMenuStep::Kind => String::from("MODE:"),
+                BattleModeSubMenuStep::WinningScore => String::from("AMOUNT OF WINS:"),
+                BattleModeSubMenuStep::BotDifficulty => String::from("DIFFICULTY:"),
+                BattleModeSubMenuStep::Map => String::from("MAP:"),
+                BattleModeSubMenuStep::Rules => String::from("RULES:"),
+                BattleModeSubMenuStep::SuddenDeath => String::from("SUDDEN DEATH:"),
+                BattleModeSubMenuStep::RevengeGhosts => String::from("REVENGE GHOSTS:"),
+                BattleModeSubMenuStep::Teams => String::from("TEAMS:"),
+                BattleModeSubMenuStep::Team(i) => {
+                    let amount_of_players = sub_menu_state.amount_of_actors.amount_of_players();
+                    if i < amount_of_players {
+                        format!("PLAYER {} TEAM:", i + 1)
+                    } else {
+                        format!("BOT {} TEAM:", i - amount_of_players + 1)
+                    }
+                }
+                BattleModeSubMenuStep::FriendlyFire => String::from("FRIENDLY FIRE:"),
             },
             TextStyle {
                 font: fonts.mono.clone(),
@@ -379,6 +464,7 @@ pub fn spawn_battle_mode_sub_menu_content(
                         .to_string(),
                     BattleModeSubMenuStep::AmountOfBots =>
                         sub_menu_state.amount_of_actors.amount_of_bots().to_string(),
+                    BattleModeSubMenuStep::Kind => String::from(sub_menu_state.kind.value().name()),
                     BattleModeSubMenuStep::WinningScore =>
                         sub_menu_state.winning_score.value().to_string(),
                     BattleModeSubMenuStep::BotDifficulty =>
@@ -387,6 +473,33 @@ pub fn spawn_battle_mode_sub_menu_content(
                             BotDifficulty::Medium => "MEDIUM",
                             BotDifficulty::Hard => "HARD",
                         }),
+                    BattleModeSubMenuStep::Map => match sub_menu_state.map.value() {
+                        MapOption::RandomGenerator => String::from("RANDOM"),
+                        MapOption::Generator(map_generator_kind) =>
+                            String::from(map_generator_kind.name()),
+                        MapOption::Handcrafted(map_name) => map_name.to_uppercase(),
+                    },
+                    BattleModeSubMenuStep::Rules => sub_menu_state.rules.value().to_uppercase(),
+                    BattleModeSubMenuStep::SuddenDeath =>
+                        String::from(sub_menu_state.sudden_death.value().name()),
+                    BattleModeSubMenuStep::RevengeGhosts =>
+                        String::from(if *sub_menu_state.revenge_ghosts.value() {
+                            "ON"
+                        } else {
+                            "OFF"
+                        }),
+                    BattleModeSubMenuStep::Teams => match sub_menu_state.teams.value() {
+                        Some(amount_of_teams) => amount_of_teams.to_string(),
+                        None => String::from("FREE FOR ALL"),
+                    },
+                    BattleModeSubMenuStep::Team(i) =>
+                        (sub_menu_state.team_assignment[i].value() + 1).to_string(),
+                    BattleModeSubMenuStep::FriendlyFire =>
+                        String::from(if *sub_menu_state.friendly_fire.value() {
+                            "ON"
+                        } else {
+                            "OFF"
+                        }),
                 }
             ),
             TextStyle {
@@ -0,0 +1,8 @@
+use bevy::{ecs as bevy_ecs, prelude::Component};
+
+// despawned and respawned on each map change
+#[derive(Component)]
+pub struct MapEditorTile;
+
+#[derive(Component)]
+pub struct MapEditorHUDText;
@@ -0,0 +1,35 @@
+use crate::game::{
+    ascii_map::MapTile,
+    components::Item,
+    types::{Power, Upgrade},
+};
+
+pub const MAP_EDITOR_MIN_ROWS: usize = 5;
+pub const MAP_EDITOR_MAX_ROWS: usize = 13;
+pub const MAP_EDITOR_MIN_COLUMNS: usize = 7;
+pub const MAP_EDITOR_MAX_COLUMNS: usize = 25;
+
+pub const MAP_EDITOR_BRUSHES: [MapTile; 22] = [
+    MapTile::StoneWall,
+    MapTile::DestructibleWall,
+    MapTile::PenguinSpawn(0),
+    MapTile::PenguinSpawn(1),
+    MapTile::PenguinSpawn(2),
+    MapTile::PenguinSpawn(3),
+    MapTile::PenguinSpawn(4),
+    MapTile::PenguinSpawn(5),
+    MapTile::PenguinSpawn(6),
+    MapTile::PenguinSpawn(7),
+    MapTile::MobSpawn,
+    MapTile::Exit,
+    MapTile::Item(Item::Upgrade(Upgrade::BombsUp)),
+    MapTile::Item(Item::Upgrade(Upgrade::RangeUp)),
+    MapTile::Item(Item::Upgrade(Upgrade::LivesUp)),
+    MapTile::Item(Item::Power(Power::WallHack)),
+    MapTile::Item(Item::Power(Power::BombPush)),
+    MapTile::Item(Item::Power(Power::Immortal)),
+    MapTile::Item(Item::Power(Power::RemoteDetonator)),
+    MapTile::Item(Item::Power(Power::Glove)),
+    MapTile::Item(Item::Power(Power::PierceBomb)),
+    MapTile::Item(Item::Power(Power::LineBomb)),
+];
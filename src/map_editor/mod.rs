@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+
+use self::{
+    resources::MapEditorContext,
+    systems::{map_editor_input, map_editor_redraw, setup_map_editor, teardown},
+};
+
+mod components;
+mod constants;
+mod resources;
+mod systems;
+mod utils;
+
+pub struct MapEditorPlugin;
+
+impl Plugin for MapEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::MapEditor), setup_map_editor)
+            .add_systems(
+                Update,
+                (
+                    map_editor_input.after(crate::common::Label::InputMapping),
+                    map_editor_redraw.run_if(resource_exists_and_changed::<MapEditorContext>()),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::MapEditor)),
+            )
+            .add_systems(OnExit(AppState::MapEditor), teardown);
+    }
+}
@@ -0,0 +1,15 @@
+use bevy::{ecs as bevy_ecs, prelude::Resource};
+
+use crate::game::{ascii_map::AsciiMap, components::Position};
+
+// kept around while test playing the map
+#[derive(Resource)]
+pub struct MapEditorContext {
+    pub map_name: String,
+    pub ascii_map: AsciiMap,
+    pub cursor_position: Position,
+    pub brush_index: usize,
+    // the arrow keys resize the map instead of moving the cursor
+    pub resize_mode: bool,
+    pub status: String,
+}
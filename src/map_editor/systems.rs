@@ -0,0 +1,321 @@
+use bevy::{prelude::*, render::camera::Camera, window::PrimaryWindow};
+
+use crate::{
+    battle_mode::{BattleModeConfiguration, BattleModeExitState, BattleModeKind},
+    common::{
+        constants::PIXEL_SCALE,
+        resources::{Fonts, InputActionStatusTracker},
+        types::InputAction,
+    },
+    game::{
+        ascii_map::{AsciiMap, MapTile},
+        components::Position,
+        constants::{HUD_HEIGHT, TILE_HEIGHT, TILE_WIDTH},
+        resources::{GameTextures, HUDColors, MapLibrary, MapSize, WorldID},
+        rules::GameRules,
+        types::{BotDifficulty, Direction},
+        utils::{get_x, get_y},
+    },
+    AppState,
+};
+
+use super::{
+    components::{MapEditorHUDText, MapEditorTile},
+    constants::*,
+    resources::MapEditorContext,
+    utils::{get_brush_name, get_tile_texture},
+};
+
+pub fn setup_map_editor(
+    mut commands: Commands,
+    mut game_textures: ResMut<GameTextures>,
+    hud_colors: Res<HUDColors>,
+    fonts: Res<Fonts>,
+    map_library: Res<MapLibrary>,
+    map_editor_context: Option<ResMut<MapEditorContext>>,
+) {
+    println!("Arrows - move, Space - paint, Backspace - erase, A/D - change brush, G - resize mode, Enter - test play, F - save, ESC - quit");
+
+    let world_id = WorldID(1);
+    game_textures.set_map_textures(world_id);
+
+    if let Some(mut map_editor_context) = map_editor_context {
+        // coming back from a test play, redraw the map
+        map_editor_context.set_changed();
+    } else {
+        let map_name = (1..)
+            .map(|i| format!("custom_{}", i))
+            .find(|n| map_library.get(n).is_none())
+            .unwrap();
+        commands.insert_resource(MapEditorContext {
+            map_name,
+            ascii_map: AsciiMap::new(MapSize {
+                rows: 11,
+                columns: 15,
+            }),
+            cursor_position: Position { y: 1, x: 1 },
+            brush_index: 0,
+            resize_mode: false,
+            status: String::new(),
+        });
+    }
+
+    commands.spawn(Camera2dBundle::default());
+
+    // HUD
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                top: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Px(HUD_HEIGHT as f32),
+                ..Default::default()
+            },
+            background_color: hud_colors.get_background_color(world_id).into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: fonts.mono.clone(),
+                            font_size: 2.0 * PIXEL_SCALE as f32,
+                            color: hud_colors.black_color,
+                        },
+                    ),
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(2.0 * PIXEL_SCALE as f32),
+                        left: Val::Px(PIXEL_SCALE as f32),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                MapEditorHUDText,
+            ));
+        });
+}
+
+pub fn map_editor_input(
+    mut commands: Commands,
+    inputs: Res<InputActionStatusTracker>,
+    mut map_editor_context: ResMut<MapEditorContext>,
+    mut map_library: ResMut<MapLibrary>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // don't trigger a redraw if nothing was pressed
+    if inputs.get_active().is_empty() {
+        return;
+    }
+
+    let map_editor_context = &mut *map_editor_context;
+    map_editor_context.status.clear();
+
+    if inputs.is_active(InputAction::Escape) {
+        commands.remove_resource::<MapEditorContext>();
+        next_state.set(AppState::MainMenu);
+        return;
+    }
+
+    if inputs.is_active(InputAction::Return) {
+        let spawn_point_count = map_editor_context.ascii_map.penguin_spawn_positions().len();
+        if spawn_point_count < 2 {
+            map_editor_context.status = String::from("PLACE 2 SPAWN POINTS TO PLAY");
+        } else {
+            // the player against bots on all the other spawn points
+            commands.insert_resource(BattleModeConfiguration {
+                amount_of_players: 1,
+                amount_of_bots: spawn_point_count - 1,
+                winning_score: 1,
+                bot_difficulty: BotDifficulty::Medium,
+                map: Some(map_editor_context.ascii_map.clone()),
+                map_generator: None,
+                rules: GameRules::default(),
+                teams: None,
+                kind: BattleModeKind::LastStanding,
+            });
+            commands.insert_resource(BattleModeExitState(AppState::MapEditor));
+            next_state.set(AppState::BattleModeSetup);
+            return;
+        }
+    }
+
+    if inputs.is_active(InputAction::F) {
+        map_editor_context.status = match map_library.save(
+            map_editor_context.map_name.clone(),
+            map_editor_context.ascii_map.clone(),
+        ) {
+            Ok(()) => format!("SAVED AS {}", map_editor_context.map_name.to_uppercase()),
+            Err(e) => {
+                eprintln!("Cannot save map file. Error: {}", e);
+                String::from("SAVING FAILED")
+            }
+        };
+    }
+
+    if inputs.is_active(InputAction::G) {
+        map_editor_context.resize_mode = !map_editor_context.resize_mode;
+    }
+
+    if inputs.is_active(InputAction::A) {
+        map_editor_context.brush_index =
+            (map_editor_context.brush_index + MAP_EDITOR_BRUSHES.len() - 1)
+                % MAP_EDITOR_BRUSHES.len();
+    }
+    if inputs.is_active(InputAction::D) {
+        map_editor_context.brush_index =
+            (map_editor_context.brush_index + 1) % MAP_EDITOR_BRUSHES.len();
+    }
+
+    for (input_action, direction) in [
+        (InputAction::Up, Direction::Up),
+        (InputAction::Down, Direction::Down),
+        (InputAction::Left, Direction::Left),
+        (InputAction::Right, Direction::Right),
+    ] {
+        if !inputs.is_active(input_action) {
+            continue;
+        }
+
+        if map_editor_context.resize_mode {
+            let map_size = map_editor_context.ascii_map.map_size();
+            let (rows, columns) = match direction {
+                Direction::Up => (map_size.rows - 1, map_size.columns),
+                Direction::Down => (map_size.rows + 1, map_size.columns),
+                Direction::Left => (map_size.rows, map_size.columns - 1),
+                Direction::Right => (map_size.rows, map_size.columns + 1),
+            };
+            if (MAP_EDITOR_MIN_ROWS..=MAP_EDITOR_MAX_ROWS).contains(&rows)
+                && (MAP_EDITOR_MIN_COLUMNS..=MAP_EDITOR_MAX_COLUMNS).contains(&columns)
+            {
+                map_editor_context
+                    .ascii_map
+                    .resize(MapSize { rows, columns });
+            }
+        } else {
+            map_editor_context.cursor_position =
+                map_editor_context.cursor_position.offset(direction, 1);
+        }
+    }
+
+    // the border is always made of stone walls, so keep the cursor inside of it
+    let map_size = map_editor_context.ascii_map.map_size();
+    let cursor_position = &mut map_editor_context.cursor_position;
+    cursor_position.y = cursor_position.y.clamp(1, map_size.rows as isize - 2);
+    cursor_position.x = cursor_position.x.clamp(1, map_size.columns as isize - 2);
+
+    if inputs.is_active(InputAction::Space) {
+        map_editor_context.ascii_map.set_tile(
+            map_editor_context.cursor_position,
+            MAP_EDITOR_BRUSHES[map_editor_context.brush_index],
+        );
+    }
+    if inputs.is_active(InputAction::Back) {
+        map_editor_context
+            .ascii_map
+            .set_tile(map_editor_context.cursor_position, MapTile::Empty);
+    }
+}
+
+pub fn map_editor_redraw(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    map_editor_context: Res<MapEditorContext>,
+    mut primary_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut query: Query<&mut Transform, With<Camera>>,
+    mut query2: Query<&mut Text, With<MapEditorHUDText>>,
+    query3: Query<Entity, With<MapEditorTile>>,
+) {
+    for entity in query3.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let mut spawn_tile_sprite = |position: Position, sprite_bundle: SpriteBundle, z: f32| {
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_xyz(get_x(position.x), get_y(position.y), z),
+                ..sprite_bundle
+            },
+            MapEditorTile,
+        ));
+    };
+    let tile_size = Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32));
+
+    for (position, tile) in map_editor_context.ascii_map.tiles() {
+        spawn_tile_sprite(
+            position,
+            SpriteBundle {
+                texture: game_textures.get_map_textures().empty.clone(),
+                sprite: Sprite {
+                    custom_size: tile_size,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            0.0,
+        );
+
+        if let Some(texture) = get_tile_texture(&game_textures, tile) {
+            spawn_tile_sprite(
+                position,
+                SpriteBundle {
+                    texture,
+                    sprite: Sprite {
+                        custom_size: tile_size,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                10.0,
+            );
+        }
+    }
+
+    if !map_editor_context.resize_mode {
+        // cursor
+        spawn_tile_sprite(
+            map_editor_context.cursor_position,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.4),
+                    custom_size: tile_size,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            30.0,
+        );
+    }
+
+    let map_size = map_editor_context.ascii_map.map_size();
+    primary_query.single_mut().resolution.set(
+        (map_size.columns * TILE_WIDTH) as f32,
+        (HUD_HEIGHT + map_size.rows * TILE_HEIGHT) as f32,
+    );
+    let mut camera_transform = query.single_mut();
+    camera_transform.translation.x = ((map_size.columns * TILE_WIDTH) as f32) / 2.0;
+    camera_transform.translation.y = -((map_size.rows * TILE_HEIGHT - HUD_HEIGHT) as f32 / 2.0);
+
+    query2.single_mut().sections[0].value = format!(
+        "BRUSH: « {} »\nMAP: {} ({}x{})\n{}",
+        get_brush_name(MAP_EDITOR_BRUSHES[map_editor_context.brush_index]),
+        map_editor_context.map_name.to_uppercase(),
+        map_size.rows,
+        map_size.columns,
+        if map_editor_context.resize_mode {
+            "RESIZING, PRESS G TO STOP"
+        } else {
+            &map_editor_context.status
+        }
+    );
+}
+
+pub fn teardown(mut commands: Commands, query: Query<Entity, Without<Window>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
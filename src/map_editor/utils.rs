@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+
+use crate::game::{
+    ascii_map::MapTile,
+    components::{Item, Penguin},
+    resources::GameTextures,
+    types::{Power, Upgrade},
+};
+
+// the texture drawn over the empty floor tile
+pub fn get_tile_texture(game_textures: &GameTextures, tile: MapTile) -> Option<Handle<Image>> {
+    let texture = match tile {
+        MapTile::Empty => return None,
+        MapTile::StoneWall => &game_textures.get_map_textures().wall,
+        MapTile::DestructibleWall => &game_textures.get_map_textures().destructible_wall,
+        MapTile::PenguinSpawn(penguin) => game_textures.get_penguin_texture(Penguin(penguin)),
+        MapTile::MobSpawn => &game_textures.crook,
+        MapTile::Exit => &game_textures.exit,
+        MapTile::Item(item) => game_textures.get_item_texture(item),
+    };
+
+    Some(texture.clone())
+}
+
+pub fn get_brush_name(tile: MapTile) -> String {
+    match tile {
+        MapTile::Empty => String::from("EMPTY"),
+        MapTile::StoneWall => String::from("STONE WALL"),
+        MapTile::DestructibleWall => String::from("DESTRUCTIBLE WALL"),
+        MapTile::PenguinSpawn(penguin) => format!("SPAWN POINT {}", penguin + 1),
+        MapTile::MobSpawn => String::from("MOB SPAWN POINT"),
+        MapTile::Exit => String::from("EXIT"),
+        MapTile::Item(item) => String::from(match item {
+            Item::Upgrade(Upgrade::BombsUp) => "BOMBS UP",
+            Item::Upgrade(Upgrade::RangeUp) => "RANGE UP",
+            Item::Upgrade(Upgrade::LivesUp) => "LIFE UP",
+            Item::Power(Power::WallHack) => "WALL CLIMBING",
+            Item::Power(Power::BombPush) => "BOMB PUSHING",
+            Item::Power(Power::Immortal) => "INVINCIBILITY",
+            Item::Power(Power::RemoteDetonator) => "REMOTE DETONATOR",
+            Item::Power(Power::Glove) => "BOMB THROWING",
+            Item::Power(Power::PierceBomb) => "PIERCE BOMBS",
+            Item::Power(Power::LineBomb) => "LINE BOMBS",
+        }),
+    }
+}
@@ -0,0 +1,5 @@
+use bevy::{ecs as bevy_ecs, prelude::*};
+
+// entity IDs differ between runs, so recorded actions are tied to these instead
+#[derive(Clone, Copy, Component)]
+pub struct ReplayActor(pub usize);
@@ -0,0 +1,2 @@
+// speed-up of the states between rounds while fast-forwarding
+pub const FAST_FORWARD_SPEED: f32 = 8.0;
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::{game::Set, AppState};
+
+use self::systems::*;
+
+mod components;
+mod constants;
+mod resources;
+mod systems;
+mod utils;
+
+pub use self::{
+    resources::{Replay, ReplayPlayback},
+    utils::setup_playback,
+};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::BattleModeSetup), start_replay_session)
+            .add_systems(OnExit(AppState::StoryModeSetup), start_replay_session)
+            .add_systems(OnEnter(AppState::BattleModeTeardown), finish_replay_session)
+            .add_systems(OnEnter(AppState::StoryModeTeardown), finish_replay_session)
+            .add_systems(
+                Update,
+                (
+                    (assign_replay_actors, apply_deferred)
+                        .chain()
+                        .before(Set::Input)
+                        .run_if(resource_exists::<resources::ReplayActorCounter>()),
+                    (
+                        start_recording_segment.before(Set::TimeUpdate),
+                        record_frame
+                            .after(Set::Input)
+                            .before(Set::PlayerMovement)
+                            .before(Set::BombSpawn),
+                    )
+                        .run_if(resource_exists::<resources::ReplayRecorder>()),
+                    (
+                        playback_controls.after(crate::common::Label::InputMapping),
+                        feed_recorded_actions,
+                    )
+                        .in_set(Set::Input)
+                        .run_if(resource_exists::<ReplayPlayback>()),
+                )
+                    .run_if(
+                        in_state(AppState::BattleModeInGame)
+                            .or_else(in_state(AppState::StoryModeInGame)),
+                    ),
+            )
+            .add_systems(
+                Update,
+                restart_rewound_replay.run_if(
+                    in_state(AppState::MainMenu)
+                        .and_then(resource_exists::<resources::ReplayRewind>()),
+                ),
+            )
+            .add_systems(
+                Last,
+                update_playback_clock.run_if(resource_exists::<ReplayPlayback>()),
+            );
+    }
+}
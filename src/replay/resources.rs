@@ -0,0 +1,263 @@
+use std::{fs, time::Duration};
+
+use bevy::{ecs as bevy_ecs, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    battle_mode::BattleModeConfiguration,
+    game::types::PlayerAction,
+    story_mode::{Level, StoryModeConfiguration},
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ReplayGameMode {
+    Story(Level, StoryModeConfiguration),
+    Battle(BattleModeConfiguration),
+}
+
+// one in-game frame: its time delta (in nanoseconds) and the actions each replay actor took
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReplayFrame(u64, Vec<(usize, PlayerAction)>);
+
+impl ReplayFrame {
+    pub fn new(delta: Duration, actions: Vec<(usize, PlayerAction)>) -> Self {
+        Self(delta.as_nanos() as u64, actions)
+    }
+
+    pub fn delta(&self) -> Duration {
+        Duration::from_nanos(self.0)
+    }
+
+    pub fn actions(&self) -> &[(usize, PlayerAction)] {
+        &self.1
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub game_mode: ReplayGameMode,
+    // in-game frames, split by round (battle mode) or level (story mode)
+    pub segments: Vec<Vec<ReplayFrame>>,
+}
+
+impl Replay {
+    const FILE_PATH: &'static str = "local/last_replay";
+
+    pub fn new(seed: u64, game_mode: ReplayGameMode) -> Self {
+        Self {
+            seed,
+            game_mode,
+            segments: vec![],
+        }
+    }
+
+    pub fn load() -> Option<Self> {
+        let replay_file_path = std::path::Path::new(Self::FILE_PATH);
+        if let Ok(content) = fs::read_to_string(replay_file_path) {
+            serde_json::from_str(&content).ok()
+        } else {
+            None
+        }
+    }
+
+    pub fn save(&self) {
+        let replay_file_path = std::path::Path::new(Self::FILE_PATH);
+        let serialized = serde_json::to_string(self).unwrap();
+        match fs::create_dir_all(replay_file_path.parent().unwrap()) {
+            Ok(()) => fs::write(replay_file_path, serialized).unwrap(),
+            Err(e) => eprintln!("Cannot save replay file. Error: {}", e),
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct ReplayActorCounter(pub usize);
+
+#[derive(Resource)]
+pub struct ReplayRecorder(pub Replay);
+
+// the replay to play again from the start, up to the given round, for going back to rounds
+// already played
+#[derive(Resource)]
+pub struct ReplayRewind {
+    pub replay: Replay,
+    pub segment: usize,
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    segment: usize,
+    frame: usize,
+    target_segment: usize,
+    pub fast_forward: bool,
+    // whether the last frame was played with a recorded time delta
+    pub manual_clock: bool,
+    pub finished: bool,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        let mut replay_playback = Self {
+            replay,
+            segment: 0,
+            frame: 0,
+            target_segment: 0,
+            fast_forward: false,
+            manual_clock: false,
+            finished: false,
+        };
+        replay_playback.skip_finished_segments();
+        replay_playback
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn peek_frame(&self) -> Option<&ReplayFrame> {
+        self.replay
+            .segments
+            .get(self.segment)
+            .and_then(|s| s.get(self.frame))
+    }
+
+    pub fn advance(&mut self) {
+        self.frame += 1;
+        self.skip_finished_segments();
+    }
+
+    fn skip_finished_segments(&mut self) {
+        while self.segment < self.replay.segments.len()
+            && self.frame >= self.replay.segments[self.segment].len()
+        {
+            self.segment += 1;
+            self.frame = 0;
+        }
+    }
+
+    // only segments ahead of the one being played can be jumped to, going back needs a restart
+    pub fn jump_to_segment(&mut self, segment: usize) -> Option<usize> {
+        if segment > self.segment && segment < self.replay.segments.len() {
+            self.target_segment = segment;
+            Some(segment)
+        } else {
+            None
+        }
+    }
+
+    pub fn jump_to_next_segment(&mut self) -> Option<usize> {
+        self.jump_to_segment(self.target_segment.max(self.segment) + 1)
+    }
+
+    pub fn previous_segment(&self) -> Option<usize> {
+        self.target_segment.max(self.segment).checked_sub(1)
+    }
+
+    pub fn is_jumping(&self) -> bool {
+        self.target_segment > self.segment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::game::types::Direction;
+
+    fn test_replay(segment_lengths: &[usize]) -> Replay {
+        let mut replay = Replay::new(
+            0,
+            ReplayGameMode::Story(
+                Level::Regular(1),
+                StoryModeConfiguration {
+                    amount_of_players: 1,
+                    save_slot: None,
+                    continue_from: None,
+                },
+            ),
+        );
+        replay.segments = segment_lengths
+            .iter()
+            .map(|l| {
+                (0..*l)
+                    .map(|_| {
+                        ReplayFrame::new(
+                            Duration::from_millis(16),
+                            vec![(0, PlayerAction::Move(Direction::Left))],
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        replay
+    }
+
+    #[test]
+    fn test_playback_advance() {
+        let mut replay_playback = ReplayPlayback::new(test_replay(&[0, 2, 0, 1]));
+
+        // empty segments should be skipped
+        assert_eq!(replay_playback.segment, 1);
+        replay_playback.advance();
+        assert_eq!(replay_playback.segment, 1);
+        replay_playback.advance();
+        assert_eq!(replay_playback.segment, 3);
+        assert!(replay_playback.peek_frame().is_some());
+
+        // the end of the replay should have no frames left
+        replay_playback.advance();
+        assert!(replay_playback.peek_frame().is_none());
+    }
+
+    #[test]
+    fn test_playback_jump_to_next_segment() {
+        let mut replay_playback = ReplayPlayback::new(test_replay(&[2, 2, 2]));
+        assert!(!replay_playback.is_jumping());
+
+        // if jumping is requested twice...
+        assert_eq!(replay_playback.jump_to_next_segment(), Some(1));
+        assert_eq!(replay_playback.jump_to_next_segment(), Some(2));
+        // ...it should jump past both segments
+        assert!(replay_playback.is_jumping());
+        for _ in 0..4 {
+            replay_playback.advance();
+        }
+        assert!(!replay_playback.is_jumping());
+
+        // there is no segment to jump to after the last one
+        assert_eq!(replay_playback.jump_to_next_segment(), None);
+    }
+
+    #[test]
+    fn test_playback_jump_to_segment() {
+        let mut replay_playback = ReplayPlayback::new(test_replay(&[2, 2, 2, 2]));
+
+        // segments ahead can be jumped to directly...
+        assert_eq!(replay_playback.jump_to_segment(2), Some(2));
+        assert_eq!(replay_playback.previous_segment(), Some(1));
+        assert_eq!(replay_playback.jump_to_segment(1), Some(1));
+        for _ in 0..2 {
+            replay_playback.advance();
+        }
+        assert!(!replay_playback.is_jumping());
+        assert_eq!(replay_playback.segment, 1);
+
+        // ...but not the one being played, the ones behind it or the ones past the end
+        assert_eq!(replay_playback.previous_segment(), Some(0));
+        assert_eq!(replay_playback.jump_to_segment(1), None);
+        assert_eq!(replay_playback.jump_to_segment(0), None);
+        assert_eq!(replay_playback.jump_to_segment(4), None);
+
+        // there is no segment before the first one
+        let replay_playback = ReplayPlayback::new(test_replay(&[2, 2]));
+        assert_eq!(replay_playback.previous_segment(), None);
+    }
+
+    #[test]
+    fn test_replay_frame_delta() {
+        let delta = Duration::from_nanos(16_666_667);
+        assert_eq!(ReplayFrame::new(delta, vec![]).delta(), delta);
+    }
+}
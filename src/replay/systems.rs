@@ -0,0 +1,270 @@
+use bevy::{
+    prelude::*,
+    time::TimeUpdateStrategy,
+    window::{PresentMode, PrimaryWindow},
+};
+
+use crate::{
+    audio::Audio,
+    battle_mode::BattleModeContext,
+    common::{resources::InputActionStatusTracker, types::InputAction},
+    game::{
+        components::{Ghost, Penguin, Player, Position},
+        events::PlayerActionEvent,
+        resources::{GameContext, GameRng, GameTimer, PauseContext, Sounds},
+        rules::GameRules,
+    },
+    story_mode::{StoryModeConfiguration, StoryModeContext},
+    AppState,
+};
+
+use super::{components::ReplayActor, constants::FAST_FORWARD_SPEED, resources::*, utils::*};
+
+pub fn start_replay_session(
+    mut commands: Commands,
+    game_rng: Res<GameRng>,
+    game_rules: Res<GameRules>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    battle_mode_context: Option<Res<BattleModeContext>>,
+    story_mode_context: Option<Res<StoryModeContext>>,
+    story_mode_configuration: Option<Res<StoryModeConfiguration>>,
+) {
+    if replay_playback.is_none() {
+        let game_mode = if let Some(battle_mode_context) = battle_mode_context {
+            let battle_mode_configuration = battle_mode_context.configuration(&game_rules);
+            // don't overwrite the last replay with a demo
+            if battle_mode_configuration.amount_of_players == 0 {
+                return;
+            }
+            ReplayGameMode::Battle(battle_mode_configuration)
+        } else if let (Some(story_mode_context), Some(story_mode_configuration)) =
+            (story_mode_context, story_mode_configuration)
+        {
+            ReplayGameMode::Story(story_mode_context.level, story_mode_configuration.clone())
+        } else {
+            return;
+        };
+
+        commands.insert_resource(ReplayRecorder(Replay::new(game_rng.seed(), game_mode)));
+    }
+
+    commands.insert_resource(ReplayActorCounter::default());
+}
+
+pub fn assign_replay_actors(
+    mut commands: Commands,
+    mut replay_actor_counter: ResMut<ReplayActorCounter>,
+    query: Query<(Entity, &Position, Option<&Penguin>), Added<Player>>,
+    query2: Query<(Entity, &Position, &Penguin), Added<Ghost>>,
+) {
+    // number the new actors in an order that doesn't depend on their entity IDs
+    let mut new_actors: Vec<(Entity, &Position, Option<&Penguin>)> = query
+        .iter()
+        .chain(query2.iter().map(|(e, p, pg)| (e, p, Some(pg))))
+        .collect();
+    new_actors.sort_by_key(|(_, position, penguin)| (penguin.map(|p| p.0), position.y, position.x));
+
+    for (entity, _, _) in new_actors {
+        commands
+            .entity(entity)
+            .insert(ReplayActor(replay_actor_counter.0));
+        replay_actor_counter.0 += 1;
+    }
+}
+
+pub fn start_recording_segment(
+    game_timer: Res<GameTimer>,
+    mut replay_recorder: ResMut<ReplayRecorder>,
+) {
+    // the game timer is fresh only on the first frame of a round/level
+    if game_timer.0.elapsed().is_zero() {
+        replay_recorder.0.segments.push(vec![]);
+    }
+}
+
+pub fn record_frame(
+    time: Res<Time>,
+    mut replay_recorder: ResMut<ReplayRecorder>,
+    query: Query<&ReplayActor>,
+    mut ev_player_action: EventReader<PlayerActionEvent>,
+) {
+    let actions = ev_player_action
+        .iter()
+        .filter_map(|e| query.get(e.player).ok().map(|a| (a.0, e.action)))
+        .collect();
+
+    let segments = &mut replay_recorder.0.segments;
+    if segments.is_empty() {
+        segments.push(vec![]);
+    }
+    segments
+        .last_mut()
+        .unwrap()
+        .push(ReplayFrame::new(time.delta(), actions));
+}
+
+pub fn playback_controls(
+    mut commands: Commands,
+    (audio, sounds): (Res<Audio>, Res<Sounds>),
+    inputs: Res<InputActionStatusTracker>,
+    game_context: Res<GameContext>,
+    mut replay_playback: ResMut<ReplayPlayback>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if inputs.is_active(InputAction::F) {
+        replay_playback.fast_forward = !replay_playback.fast_forward;
+    }
+
+    if inputs.is_active(InputAction::Right) {
+        match replay_playback.jump_to_next_segment() {
+            Some(segment) => println!("Jumping to round {}.", segment + 1),
+            None => println!("Already at the last round."),
+        }
+    }
+
+    if inputs.is_active(InputAction::Left) {
+        match replay_playback.previous_segment() {
+            Some(segment) => {
+                if replay_playback.jump_to_segment(segment).is_some() {
+                    println!("Jumping to round {}.", segment + 1);
+                } else {
+                    // the game can't be rolled back, so play the replay again up to that round
+                    println!("Rewinding to round {}.", segment + 1);
+                    commands.insert_resource(ReplayRewind {
+                        replay: replay_playback.replay().clone(),
+                        segment,
+                    });
+                    next_state.set(game_context.game_mode_manager_state);
+                }
+            }
+            None => println!("Already at the first round."),
+        }
+    }
+
+    if inputs.is_active(InputAction::Return) && game_context.pausable {
+        audio.play(sounds.pause);
+        commands.insert_resource(PauseContext {
+            in_game_state: *state.get(),
+        });
+        next_state.set(AppState::Paused);
+    }
+
+    if inputs.is_active(InputAction::Escape) {
+        next_state.set(game_context.game_mode_manager_state);
+    }
+}
+
+pub fn restart_rewound_replay(
+    mut commands: Commands,
+    replay_rewind: Res<ReplayRewind>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    commands.remove_resource::<ReplayRewind>();
+    next_state.set(setup_playback(
+        &mut commands,
+        replay_rewind.replay.clone(),
+        replay_rewind.segment,
+    ));
+}
+
+pub fn feed_recorded_actions(
+    mut replay_playback: ResMut<ReplayPlayback>,
+    game_context: Res<GameContext>,
+    query: Query<(Entity, &ReplayActor)>,
+    mut ev_player_action: EventWriter<PlayerActionEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let actions = if let Some(frame) = replay_playback.peek_frame() {
+        frame.actions().to_vec()
+    } else {
+        // the recording stopped mid-game, so the match must have been quit
+        println!("Replay finished.");
+        next_state.set(game_context.game_mode_manager_state);
+        return;
+    };
+
+    for (actor, action) in actions {
+        if let Some((entity, _)) = query.iter().find(|(_, ra)| ra.0 == actor) {
+            ev_player_action.send(PlayerActionEvent {
+                player: entity,
+                action,
+            });
+        }
+    }
+
+    replay_playback.advance();
+}
+
+pub fn update_playback_clock(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+    mut replay_playback: ResMut<ReplayPlayback>,
+    state: Res<State<AppState>>,
+    next_state: Res<NextState<AppState>>,
+    mut primary_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let next_frame_state = next_state.0.unwrap_or(*state.get());
+    let recorded_delta = if is_in_game_state(next_frame_state) {
+        replay_playback.peek_frame().map(|f| f.delta())
+    } else {
+        None
+    };
+
+    let mut fast_forward = replay_playback.fast_forward || replay_playback.is_jumping();
+    if let Some(delta) = recorded_delta {
+        // play the frame back with the exact time step it was recorded with
+        time.unpause();
+        time.set_relative_speed(1.0);
+        *time_update_strategy = TimeUpdateStrategy::ManualDuration(delta);
+        replay_playback.manual_clock = true;
+    } else {
+        *time_update_strategy = TimeUpdateStrategy::Automatic;
+        if replay_playback.manual_clock {
+            // the recorded deltas don't add up to the real time that passed,
+            // so freeze the clock for a frame while it catches up
+            time.pause();
+            replay_playback.manual_clock = false;
+        } else {
+            time.unpause();
+            if replay_playback.finished {
+                fast_forward = false;
+                commands.remove_resource::<ReplayPlayback>();
+            }
+            time.set_relative_speed(if fast_forward {
+                FAST_FORWARD_SPEED
+            } else {
+                1.0
+            });
+        }
+    }
+
+    // without vsync the frames, and with them the recorded game, run as fast as possible
+    let present_mode = if fast_forward {
+        PresentMode::AutoNoVsync
+    } else {
+        PresentMode::AutoVsync
+    };
+    let mut window = primary_query.single_mut();
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+pub fn finish_replay_session(
+    mut commands: Commands,
+    replay_recorder: Option<Res<ReplayRecorder>>,
+    replay_playback: Option<ResMut<ReplayPlayback>>,
+) {
+    if let Some(replay_recorder) = replay_recorder {
+        replay_recorder.0.save();
+        commands.remove_resource::<ReplayRecorder>();
+    }
+
+    if let Some(mut replay_playback) = replay_playback {
+        replay_playback.finished = true;
+    }
+
+    commands.remove_resource::<ReplayActorCounter>();
+}
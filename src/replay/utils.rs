@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+
+use crate::{game::resources::NextMatchSeed, AppState};
+
+use super::resources::{Replay, ReplayGameMode, ReplayPlayback};
+
+pub fn is_in_game_state(state: AppState) -> bool {
+    matches!(
+        state,
+        AppState::BattleModeInGame | AppState::StoryModeInGame
+    )
+}
+
+// returns the setup state of the recorded game mode
+pub fn setup_playback(commands: &mut Commands, replay: Replay, start_segment: usize) -> AppState {
+    println!("Playing back a replay with seed {}.", replay.seed);
+    println!("Enter - pause, F - fast-forward, Left/Right - previous/next round, ESC - quit");
+
+    commands.insert_resource(NextMatchSeed(replay.seed));
+    let setup_state = match replay.game_mode {
+        ReplayGameMode::Story(_, ref story_mode_configuration) => {
+            commands.insert_resource(story_mode_configuration.clone());
+            AppState::StoryModeSetup
+        }
+        ReplayGameMode::Battle(ref battle_mode_configuration) => {
+            commands.insert_resource(battle_mode_configuration.clone());
+            AppState::BattleModeSetup
+        }
+    };
+    let mut replay_playback = ReplayPlayback::new(replay);
+    replay_playback.jump_to_segment(start_segment);
+    commands.insert_resource(replay_playback);
+
+    setup_state
+}
@@ -13,8 +13,11 @@ use crate::{
         components::*,
         constants::{TILE_HEIGHT, TILE_WIDTH},
         events::ExplosionEvent,
-        resources::{GameContext, GameTextures, HUDColors, MapSize, WorldID},
-        types::{Cooldown, Direction},
+        loot_tables::{LootTables, MatchLoot},
+        map_generators::{ClassicMapGenerator, MapGenerationParams},
+        resources::{GameContext, GameRng, GameTextures, HUDColors, MapSize, WorldID},
+        rules::GameRules,
+        types::{BombKind, Cooldown, Direction},
         utils::{get_x, get_y, init_hud, spawn_map},
     },
     map_transition::MapTransitionInput,
@@ -53,7 +56,8 @@ pub fn setup_secret_mode(
         columns: 15,
     };
 
-    let world_id = WorldID(rand::thread_rng().gen_range(1..=3));
+    let mut game_rng = GameRng::default();
+    let world_id = WorldID(game_rng.map.gen_range(1..=3));
     game_textures.set_map_textures(world_id);
 
     // spawn HUD
@@ -165,6 +169,8 @@ pub fn setup_secret_mode(
 
     commands.insert_resource(world_id);
     commands.insert_resource(map_size);
+    commands.insert_resource(game_rng);
+    commands.insert_resource(GameRules::default());
 
     commands.insert_resource(SecretModeContext {
         manager_state: SecretModeManagerState::Setup,
@@ -175,8 +181,6 @@ pub fn setup_secret_mode(
     let game_mode_manager_state = AppState::SecretModeManager;
     commands.insert_resource(GameContext {
         pausable: false,
-        // irrelevant in this mode
-        reduced_loot: false,
         game_mode_manager_state,
     });
 
@@ -189,10 +193,17 @@ pub fn secret_mode_manager(
     mut secret_mode_context: ResMut<SecretModeContext>,
     map_size: Res<MapSize>,
     game_option_store: Res<GameOptionStore>,
+    (loot_tables, game_rules, mut game_rng): (Res<LootTables>, Res<GameRules>, ResMut<GameRng>),
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     match secret_mode_context.manager_state {
         SecretModeManagerState::Setup => {
+            commands.insert_resource(MatchLoot::new(
+                &loot_tables,
+                &loot_tables.secret,
+                &game_rules,
+            ));
+
             // map generation //
 
             // spawn player
@@ -227,13 +238,19 @@ pub fn secret_mode_manager(
             let wall_entity_reveal_groups = spawn_map(
                 &mut commands,
                 &game_textures,
-                *map_size,
-                0.0,
-                false,
-                &[player_spawn_position],
-                &[],
-                false,
-            );
+                &ClassicMapGenerator {
+                    spawn_middle_blocks: false,
+                },
+                &MapGenerationParams {
+                    map_size: *map_size,
+                    percent_of_passable_positions_to_fill: 0.0,
+                    penguin_spawn_positions: &[player_spawn_position],
+                    mob_spawn_positions: &[],
+                    spawn_exit: false,
+                },
+                &mut game_rng.map,
+            )
+            .unwrap();
 
             if game_option_store.get(GameOption::Transition) {
                 secret_mode_context.manager_state = SecretModeManagerState::MapTransition;
@@ -264,6 +281,7 @@ pub fn update_secret_mode(
     fonts: Res<Fonts>,
     map_size: Res<MapSize>,
     world_id: Res<WorldID>,
+    game_rules: Res<GameRules>,
     mut secret_mode_context: ResMut<SecretModeContext>,
     mut next_state: ResMut<NextState<AppState>>,
     mut p: ParamSet<(
@@ -344,7 +362,9 @@ pub fn update_secret_mode(
                                     },
                                     Bomb {
                                         owner: None,
+                                        ignited_by: None,
                                         range: 3,
+                                        kind: BombKind::Regular,
                                         timer: Timer::from_seconds(9999.0, TimerMode::Once),
                                     },
                                     position,
@@ -404,7 +424,9 @@ pub fn update_secret_mode(
                         *texture = new_texture.clone();
                         *base_texture = BaseTexture(new_texture.clone());
 
-                        commands.entity(entity).insert(Immortal::default());
+                        commands
+                            .entity(entity)
+                            .insert(Immortal::new(game_rules.immortality_secs));
 
                         let current_duration = move_cooldown.duration();
                         if let Some(duration) =
@@ -467,6 +489,9 @@ pub fn teardown(
 
     commands.remove_resource::<WorldID>();
     commands.remove_resource::<MapSize>();
+    commands.remove_resource::<GameRng>();
+    commands.remove_resource::<GameRules>();
+    commands.remove_resource::<MatchLoot>();
     commands.remove_resource::<SecretModeContext>();
 
     audio.stop();
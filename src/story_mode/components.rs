@@ -1,4 +1,10 @@
 use bevy::{ecs as bevy_ecs, prelude::Component};
 
+use crate::game::components::Penguin;
+
 #[derive(Component)]
 pub struct Protagonist;
+
+// the lives of a protagonist
+#[derive(Component)]
+pub struct LivesDisplay(pub Penguin);
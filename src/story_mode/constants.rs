@@ -1 +1,2 @@
 pub const STORY_MODE_LEVEL_DURATION_SECS: usize = 180;
+pub const STORY_MODE_SAVE_SLOTS: usize = 3;
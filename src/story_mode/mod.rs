@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
 use crate::{
+    common::resources::PersistentHighScores,
     game::{
         common_game_systems,
         systems::{
@@ -12,6 +13,11 @@ use crate::{
 };
 
 use self::systems::*;
+pub use self::{
+    resources::{Level, StoryModeConfiguration, StoryModeContext, StorySave, StorySaveSlots},
+    systems::{high_score_name_input_update, hud_points_indicator_update},
+    utils::{format_hud_points, spawn_story_mode_mob},
+};
 
 mod components;
 mod constants;
@@ -23,37 +29,39 @@ pub struct StoryModePlugin;
 
 impl Plugin for StoryModePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(AppState::StoryModeSetup),
-            (
-                setup_story_mode,
-                apply_deferred,
-                (resize_window, spawn_cameras),
+        app.init_resource::<StorySaveSlots>()
+            .add_systems(
+                OnEnter(AppState::StoryModeSetup),
+                (
+                    setup_story_mode,
+                    apply_deferred,
+                    (resize_window, spawn_cameras),
+                )
+                    .chain(),
             )
-                .chain(),
-        )
-        .add_systems(OnEnter(AppState::StoryModeTeardown), teardown)
-        .add_systems(
-            Update,
-            story_mode_manager.run_if(in_state(AppState::StoryModeManager)),
-        )
-        .add_systems(OnEnter(AppState::BossSpeech), setup_boss_speech)
-        .add_systems(
-            Update,
-            boss_speech_update
-                .after(crate::common::Label::InputMapping)
-                .run_if(in_state(AppState::BossSpeech)),
-        )
-        .add_systems(
-            OnEnter(AppState::HighScoreNameInput),
-            setup_high_score_name_input,
-        )
-        .add_systems(
-            Update,
-            high_score_name_input_update
-                .after(crate::common::Label::InputMapping)
-                .run_if(in_state(AppState::HighScoreNameInput)),
-        );
+            .add_systems(OnEnter(AppState::StoryModeTeardown), teardown)
+            .add_systems(
+                Update,
+                story_mode_manager.run_if(in_state(AppState::StoryModeManager)),
+            )
+            .add_systems(OnEnter(AppState::BossSpeech), setup_boss_speech)
+            .add_systems(
+                Update,
+                boss_speech_update
+                    .after(crate::common::Label::InputMapping)
+                    .run_if(in_state(AppState::BossSpeech)),
+            )
+            .add_systems(
+                OnEnter(AppState::HighScoreNameInput),
+                setup_high_score_name_input,
+            )
+            .add_systems(
+                Update,
+                high_score_name_input_update::<PersistentHighScores>
+                    .after(crate::common::Label::InputMapping)
+                    .run_if(resource_exists::<StoryModeContext>())
+                    .run_if(in_state(AppState::HighScoreNameInput)),
+            );
 
         app.add_systems(OnEnter(AppState::StoryModeInGame), setup_penguin_portraits);
         app.add_systems(
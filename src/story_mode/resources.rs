@@ -1,13 +1,16 @@
-use std::time::Duration;
+use std::{fs, time::Duration};
 
 use bevy::{
     ecs as bevy_ecs,
     prelude::{Entity, Resource},
     time::{Timer, TimerMode},
 };
+use serde::{Deserialize, Serialize};
 
 use crate::game::components::Penguin;
 
+use super::constants::STORY_MODE_SAVE_SLOTS;
+
 pub enum StoryModeState {
     LevelSetup,
     MapTransition,
@@ -17,7 +20,107 @@ pub enum StoryModeState {
     HighScoreNameInput,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Serialize, Deserialize, Resource)]
+pub struct StoryModeConfiguration {
+    // a second player joins in on the WASD/G controls
+    pub amount_of_players: usize,
+    // where the run is saved between levels, replays are never saved
+    #[serde(skip)]
+    pub save_slot: Option<usize>,
+    // the save a continued run picks up from
+    #[serde(default)]
+    pub continue_from: Option<StorySave>,
+}
+
+// where a run stands at the start of a level
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StorySave {
+    pub amount_of_players: usize,
+    pub world_id: usize,
+    pub level: Level,
+    pub score: usize,
+    // the protagonists out of lives are left out
+    pub protagonists: Vec<ProtagonistSave>,
+}
+
+impl StorySave {
+    pub fn new_run(amount_of_players: usize) -> Self {
+        Self {
+            amount_of_players,
+            world_id: 1,
+            level: Level::Regular(1),
+            score: 0,
+            protagonists: (0..amount_of_players)
+                .map(|penguin| ProtagonistSave {
+                    penguin,
+                    lives: 5,
+                    bombs_available: 1,
+                    bomb_range: 1,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProtagonistSave {
+    pub penguin: usize,
+    pub lives: usize,
+    pub bombs_available: usize,
+    pub bomb_range: usize,
+}
+
+#[derive(Resource)]
+pub struct StorySaveSlots(Vec<Option<StorySave>>);
+
+impl Default for StorySaveSlots {
+    fn default() -> Self {
+        let story_save_slots_file_path = std::path::Path::new(Self::SERIALIZED_FILE_PATH);
+        if let Ok(content) = fs::read_to_string(story_save_slots_file_path) {
+            match serde_json::from_str::<Vec<Option<StorySave>>>(&content) {
+                Ok(mut slots) => {
+                    slots.resize(STORY_MODE_SAVE_SLOTS, None);
+                    return Self(slots);
+                }
+                Err(e) => eprintln!(
+                    "Cannot load story mode saves from {}. Error: {}",
+                    Self::SERIALIZED_FILE_PATH,
+                    e
+                ),
+            }
+        }
+
+        Self(vec![None; STORY_MODE_SAVE_SLOTS])
+    }
+}
+
+impl StorySaveSlots {
+    const SERIALIZED_FILE_PATH: &'static str = "local/story_saves";
+
+    pub fn get(&self, slot: usize) -> Option<&StorySave> {
+        self.0[slot].as_ref()
+    }
+
+    pub fn set(&mut self, slot: usize, story_save: Option<StorySave>) {
+        self.0[slot] = story_save;
+        self.save();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Option<&StorySave>> {
+        self.0.iter().map(|s| s.as_ref())
+    }
+
+    fn save(&self) {
+        let story_save_slots_file_path = std::path::Path::new(Self::SERIALIZED_FILE_PATH);
+        let serialized = serde_json::to_string(&self.0).unwrap();
+        match fs::create_dir_all(story_save_slots_file_path.parent().unwrap()) {
+            Ok(()) => fs::write(story_save_slots_file_path, serialized).unwrap(),
+            Err(e) => eprintln!("Cannot save story mode saves file. Error: {}", e),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum Level {
     Regular(usize),
     BossRoom,
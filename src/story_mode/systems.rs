@@ -8,13 +8,25 @@ use crate::{
         },
         types::InputAction,
     },
-    game::{components::*, constants::*, events::*, resources::*, utils::*},
+    game::{
+        components::*,
+        constants::*,
+        events::*,
+        loot_tables::{LootTables, MatchLoot},
+        map_generators::{ClassicMapGenerator, MapGenerationParams},
+        resources::*,
+        rules::GameRules,
+        utils::*,
+    },
     map_transition::MapTransitionInput,
     AppState,
 };
 
 use super::{
-    components::Protagonist, constants::STORY_MODE_LEVEL_DURATION_SECS, resources::*, utils::*,
+    components::{LivesDisplay, Protagonist},
+    constants::STORY_MODE_LEVEL_DURATION_SECS,
+    resources::*,
+    utils::*,
 };
 
 pub fn setup_story_mode(
@@ -22,60 +34,70 @@ pub fn setup_story_mode(
     mut game_textures: ResMut<GameTextures>,
     hud_colors: Res<HUDColors>,
     fonts: Res<Fonts>,
+    story_mode_configuration: Res<StoryModeConfiguration>,
+    next_match_seed: Option<Res<NextMatchSeed>>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     let map_size = MapSize {
         rows: 11,
         columns: 15,
     };
-    let world_id = WorldID(1);
-    let player_lives = 5;
-    let player_points = 0;
+    let amount_of_players = story_mode_configuration.amount_of_players;
+    // a new run starts out like a save of the first level
+    let story_save = story_mode_configuration
+        .continue_from
+        .clone()
+        .unwrap_or_else(|| StorySave::new_run(amount_of_players));
+    let world_id = WorldID(story_save.world_id);
+    let player_points = story_save.score;
 
     game_textures.set_map_textures(world_id);
 
     // map generation //
 
-    // spawn player
-    let player_spawn_position = Position { y: 1, x: 1 };
-    let player_penguin_tag = Penguin(0);
-    let base_texture = game_textures
-        .get_penguin_texture(player_penguin_tag)
-        .clone();
-    let immortal_texture = game_textures.immortal_penguin.clone();
-    commands.spawn((
-        SpriteBundle {
-            texture: base_texture.clone(),
-            transform: Transform::from_xyz(
-                get_x(player_spawn_position.x),
-                get_y(player_spawn_position.y),
-                50.0,
-            ),
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+    // spawn players, they are moved to their spawn points when a level is set up
+    for protagonist_save in &story_save.protagonists {
+        let i = protagonist_save.penguin;
+        let player_spawn_position = Position { y: 1, x: 1 };
+        let player_penguin_tag = Penguin(i);
+        let base_texture = game_textures
+            .get_penguin_texture(player_penguin_tag)
+            .clone();
+        let immortal_texture = game_textures.immortal_penguin.clone();
+        commands.spawn((
+            SpriteBundle {
+                texture: base_texture.clone(),
+                transform: Transform::from_xyz(
+                    get_x(player_spawn_position.x),
+                    get_y(player_spawn_position.y),
+                    50.0,
+                ),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+                    ..Default::default()
+                },
                 ..Default::default()
             },
-            ..Default::default()
-        },
-        BaseTexture(base_texture),
-        ImmortalTexture(immortal_texture),
-        Player,
-        Protagonist,
-        HumanControlled(0),
-        Health {
-            lives: player_lives,
-            max_health: 1,
-            health: 1,
-        },
-        player_spawn_position,
-        SpawnPosition(player_spawn_position),
-        BombSatchel {
-            bombs_available: 1,
-            bomb_range: 1,
-        },
-        player_penguin_tag,
-        TeamID(0),
-    ));
+            BaseTexture(base_texture),
+            ImmortalTexture(immortal_texture),
+            Player,
+            Protagonist,
+            HumanControlled(i),
+            Health {
+                lives: protagonist_save.lives,
+                max_health: 1,
+                health: 1,
+            },
+            player_spawn_position,
+            SpawnPosition(player_spawn_position),
+            BombSatchel {
+                bombs_available: protagonist_save.bombs_available,
+                bomb_range: protagonist_save.bomb_range,
+            },
+            player_penguin_tag,
+            TeamID(0),
+        ));
+    }
 
     // spawn HUD
     commands
@@ -102,30 +124,37 @@ pub fn setup_story_mode(
                 true,
                 true,
                 Some(&|parent: &mut ChildBuilder| {
-                    // lives display
-                    parent.spawn((
-                        TextBundle {
-                            text: Text::from_section(
-                                format_hud_lives(player_lives),
-                                TextStyle {
-                                    font: fonts.mono.clone(),
-                                    font_size: 2.0 * PIXEL_SCALE as f32,
-                                    color: COLORS[0].into(),
+                    // lives displays, under the penguin portraits
+                    for i in 0..amount_of_players {
+                        let lives = story_save
+                            .protagonists
+                            .iter()
+                            .find(|p| p.penguin == i)
+                            .map_or(0, |p| p.lives);
+                        parent.spawn((
+                            TextBundle {
+                                text: Text::from_section(
+                                    format_hud_lives(lives),
+                                    TextStyle {
+                                        font: fonts.mono.clone(),
+                                        font_size: 2.0 * PIXEL_SCALE as f32,
+                                        color: COLORS[0].into(),
+                                    },
+                                ),
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    top: Val::Px(12.0 * PIXEL_SCALE as f32),
+                                    left: Val::Px(((6 + 12 * i) * PIXEL_SCALE) as f32),
+                                    ..Default::default()
                                 },
-                            ),
-                            style: Style {
-                                position_type: PositionType::Absolute,
-                                top: Val::Px(12.0 * PIXEL_SCALE as f32),
-                                left: Val::Px(6.0 * PIXEL_SCALE as f32),
                                 ..Default::default()
                             },
-                            ..Default::default()
-                        },
-                        UIComponent,
-                        BottomLeftDisplay1,
-                    ));
+                            UIComponent,
+                            LivesDisplay(Penguin(i)),
+                        ));
+                    }
 
-                    // points display
+                    // points display, moved past the clock when the second lives display takes its spot
                     parent.spawn((
                         TextBundle {
                             text: Text::from_section(
@@ -139,7 +168,10 @@ pub fn setup_story_mode(
                             style: Style {
                                 position_type: PositionType::Absolute,
                                 top: Val::Px(12.0 * PIXEL_SCALE as f32),
-                                left: Val::Px(16.0 * PIXEL_SCALE as f32),
+                                left: Val::Px(
+                                    if amount_of_players > 1 { 50.0 } else { 16.0 }
+                                        * PIXEL_SCALE as f32,
+                                ),
                                 ..Default::default()
                             },
                             ..Default::default()
@@ -153,7 +185,7 @@ pub fn setup_story_mode(
 
     commands.insert_resource(StoryModeContext {
         state: StoryModeState::LevelSetup,
-        level: Level::Regular(1),
+        level: story_save.level,
         level_outcome: None,
         game_completed: false,
     });
@@ -161,7 +193,6 @@ pub fn setup_story_mode(
     let game_mode_manager_state = AppState::StoryModeManager;
     commands.insert_resource(GameContext {
         pausable: true,
-        reduced_loot: false,
         game_mode_manager_state,
     });
     commands.insert_resource(GameScore(player_points));
@@ -171,6 +202,13 @@ pub fn setup_story_mode(
     )));
     commands.insert_resource(world_id);
     commands.insert_resource(map_size);
+    commands.insert_resource(GameRng::for_new_match(next_match_seed.as_deref()));
+    commands.remove_resource::<NextMatchSeed>();
+    // the protagonists are in this together
+    commands.insert_resource(GameRules {
+        friendly_fire: false,
+        ..Default::default()
+    });
 
     next_state.set(game_mode_manager_state);
 }
@@ -189,7 +227,14 @@ pub fn story_mode_manager(
     mut next_state: ResMut<NextState<AppState>>,
     mut p: ParamSet<(
         Query<
-            (Entity, &mut Handle<Image>, &BaseTexture, &mut BombSatchel),
+            (
+                Entity,
+                &mut Handle<Image>,
+                &BaseTexture,
+                &mut BombSatchel,
+                &Penguin,
+                &Health,
+            ),
             (With<Player>, With<Protagonist>),
         >,
         // doesn't need to be in here, but putting it outside throws errors (possibly because of too many arguments)
@@ -209,6 +254,13 @@ pub fn story_mode_manager(
     mut p2: ParamSet<(
         Query<&mut Text, With<BottomLeftDisplay2>>,
         Query<&mut Text, With<GameTimerDisplay>>,
+        // also here because of the system parameter limit
+        ResMut<GameRng>,
+        Res<MapLibrary>,
+        Res<LootTables>,
+        Res<GameRules>,
+        Res<StoryModeConfiguration>,
+        ResMut<StorySaveSlots>,
     )>,
     query: Query<Entity, With<PenguinPortrait>>,
     query2: Query<
@@ -225,53 +277,91 @@ pub fn story_mode_manager(
     loop {
         match story_mode_context.state {
             StoryModeState::LevelSetup => {
-                let mut tmp = p.p1();
-                let (
+                let level_map = get_story_mode_level_map(
+                    &p2.p3(),
+                    *world_id,
+                    story_mode_context.level,
+                    *map_size,
+                )
+                .cloned();
+
+                let game_rules = p2.p5().clone();
+                let loot_tables = p2.p4();
+                commands.insert_resource(MatchLoot::new(
+                    &loot_tables,
+                    loot_tables.story_world(*world_id),
+                    &game_rules,
+                ));
+
+                let protagonist_spawn_positions = get_story_mode_protagonist_spawn_positions(
+                    level_map.as_ref(),
+                    story_mode_context.level,
+                    *map_size,
+                );
+                let mut penguin_spawn_positions = vec![];
+                let mut penguin_tags = vec![];
+
+                // the protagonists out of lives are gone for the rest of the run
+                for (
                     player_entity,
                     player_penguin_tag,
                     mut player_position,
                     mut player_spawn_position,
                     mut transform,
                     mut sprite,
-                ) = tmp.single_mut();
-
-                // move player to spawn
-                *player_position = match story_mode_context.level {
-                    Level::Regular(_) => Position { y: 1, x: 1 },
-                    Level::BossRoom => Position {
-                        y: map_size.rows as isize - 4,
-                        x: map_size.columns as isize / 2,
-                    },
-                };
-                *player_spawn_position = SpawnPosition(*player_position);
-                let mut penguin_spawn_positions = vec![*player_position];
-                let mut penguin_tags = vec![*player_penguin_tag];
-
-                let translation = &mut transform.translation;
-                translation.x = get_x(player_position.x);
-                translation.y = get_y(player_position.y);
-
-                // face the player right
-                sprite.flip_x = false;
-
-                // make the player temporarily immortal
-                commands.entity(player_entity).insert(Immortal::default());
+                ) in p.p1().iter_mut()
+                {
+                    // move player to spawn
+                    *player_position = protagonist_spawn_positions[player_penguin_tag.0];
+                    *player_spawn_position = SpawnPosition(*player_position);
+                    penguin_spawn_positions.push(*player_position);
+                    penguin_tags.push(*player_penguin_tag);
+
+                    let translation = &mut transform.translation;
+                    translation.x = get_x(player_position.x);
+                    translation.y = get_y(player_position.y);
+
+                    // face the player right
+                    sprite.flip_x = false;
+
+                    // make the player temporarily immortal
+                    commands
+                        .entity(player_entity)
+                        .insert(Immortal::new(game_rules.immortality_secs));
+                }
+                // the first protagonist still standing does the talking
+                let player = *penguin_tags.iter().min_by_key(|p| p.0).unwrap();
 
-                let mob_spawn_positions = spawn_story_mode_mobs(
-                    &mut commands,
-                    &game_textures,
-                    story_mode_context.level,
-                    *world_id,
-                    *map_size,
-                );
+                let mob_spawn_positions = if let Some(ref level_map) = level_map {
+                    level_map.mob_spawn_positions()
+                } else {
+                    get_story_mode_mob_spawn_positions(
+                        story_mode_context.level,
+                        *world_id,
+                        *map_size,
+                        &mut p2.p2().map,
+                    )
+                };
+                spawn_story_mode_mobs(&mut commands, &game_textures, &mob_spawn_positions);
 
                 if let Level::BossRoom = story_mode_context.level {
-                    let (boss_spawn_position, boss_penguin_tag) =
-                        spawn_story_mode_boss(&mut commands, &game_textures, *world_id, *map_size);
+                    let boss_spawn_position = if let Some(ref level_map) = level_map {
+                        level_map.penguin_spawn_positions()[1]
+                    } else {
+                        Position {
+                            y: 3,
+                            x: map_size.columns as isize / 2,
+                        }
+                    };
+                    let boss_penguin_tag = spawn_story_mode_boss(
+                        &mut commands,
+                        &game_textures,
+                        *world_id,
+                        boss_spawn_position,
+                    );
                     penguin_spawn_positions.push(boss_spawn_position);
                     penguin_tags.push(boss_penguin_tag);
 
-                    let player = *player_penguin_tag;
                     let boss = boss_penguin_tag;
                     commands.insert_resource(BossSpeechScript::new(match world_id.0 {
                                     1 => vec![
@@ -306,20 +396,35 @@ pub fn story_mode_manager(
                                 }));
                 }
 
-                let wall_entity_reveal_groups = spawn_map(
-                    &mut commands,
-                    &game_textures,
-                    *map_size,
-                    if let Level::BossRoom = story_mode_context.level {
-                        0.0
-                    } else {
-                        50.0
-                    },
-                    true,
-                    &penguin_spawn_positions,
-                    &mob_spawn_positions,
-                    matches!(story_mode_context.level, Level::Regular(_)),
-                );
+                let wall_entity_reveal_groups = if let Some(ref level_map) = level_map {
+                    spawn_ascii_map(
+                        &mut commands,
+                        &game_textures,
+                        level_map,
+                        matches!(story_mode_context.level, Level::Regular(_)),
+                    )
+                } else {
+                    spawn_map(
+                        &mut commands,
+                        &game_textures,
+                        &ClassicMapGenerator::default(),
+                        &MapGenerationParams {
+                            map_size: *map_size,
+                            percent_of_passable_positions_to_fill: if let Level::BossRoom =
+                                story_mode_context.level
+                            {
+                                0.0
+                            } else {
+                                50.0
+                            },
+                            penguin_spawn_positions: &penguin_spawn_positions,
+                            mob_spawn_positions: &mob_spawn_positions,
+                            spawn_exit: matches!(story_mode_context.level, Level::Regular(_)),
+                        },
+                        &mut p2.p2().map,
+                    )
+                    .unwrap()
+                };
 
                 game_timer.0.reset();
                 // update HUD clock
@@ -377,6 +482,11 @@ pub fn story_mode_manager(
                                 story_mode_context.game_completed = true;
                                 println!("Game completed! Final score: {}", game_score.0);
 
+                                // a completed run has nothing left to continue
+                                if let Some(save_slot) = p2.p6().save_slot {
+                                    p2.p7().set(save_slot, None);
+                                }
+
                                 story_mode_context.state = StoryModeState::ScoreCheck;
                                 continue;
                             }
@@ -396,17 +506,56 @@ pub fn story_mode_manager(
                             }
                         };
 
-                        let mut tmp = p.p0();
-                        let (player_entity, mut player_texture, base_texture, mut bomb_satchel) =
-                            tmp.single_mut();
-
-                        // reset the player's texture (clears immortality animation effects)
-                        *player_texture = base_texture.0.clone();
+                        let mut protagonist_saves = vec![];
+                        for (
+                            player_entity,
+                            mut player_texture,
+                            base_texture,
+                            mut bomb_satchel,
+                            player_penguin_tag,
+                            health,
+                        ) in p.p0().iter_mut()
+                        {
+                            // reset the player's texture (clears immortality animation effects)
+                            *player_texture = base_texture.0.clone();
+
+                            // bomb refill
+                            bomb_satchel.bombs_available += query3
+                                .iter()
+                                .filter(
+                                    |b| matches!(b.owner, Some(entity) if entity == player_entity),
+                                )
+                                .count();
+
+                            protagonist_saves.push(ProtagonistSave {
+                                penguin: player_penguin_tag.0,
+                                lives: health.lives,
+                                bombs_available: bomb_satchel.bombs_available,
+                                bomb_range: bomb_satchel.bomb_range,
+                            });
+                        }
 
-                        let unexploded_player_bombs = query3
-                            .iter()
-                            .filter(|b| matches!(b.owner, Some(entity) if entity == player_entity))
-                            .count();
+                        // save the run so it can be continued from the next level
+                        let (save_slot, amount_of_players) = {
+                            let story_mode_configuration = p2.p6();
+                            (
+                                story_mode_configuration.save_slot,
+                                story_mode_configuration.amount_of_players,
+                            )
+                        };
+                        if let Some(save_slot) = save_slot {
+                            protagonist_saves.sort_by_key(|p| p.penguin);
+                            p2.p7().set(
+                                save_slot,
+                                Some(StorySave {
+                                    amount_of_players,
+                                    world_id: world_id.0,
+                                    level: story_mode_context.level,
+                                    score: game_score.0,
+                                    protagonists: protagonist_saves,
+                                }),
+                            );
+                        }
 
                         for entity in query2.iter() {
                             commands.entity(entity).despawn();
@@ -417,14 +566,17 @@ pub fn story_mode_manager(
                             commands.entity(entity).despawn_recursive();
                         }
 
-                        // bomb refill
-                        bomb_satchel.bombs_available += unexploded_player_bombs;
-
                         story_mode_context.state = StoryModeState::LevelSetup;
                         continue;
                     }
                     Some(LevelOutcome::Loss) => {
                         println!("Game over! Final score: {}", game_score.0);
+
+                        // the run is over, so it can't be continued (and its score counted) again
+                        if let Some(save_slot) = p2.p6().save_slot {
+                            p2.p7().set(save_slot, None);
+                        }
+
                         story_mode_context.state = StoryModeState::ScoreCheck;
                         continue;
                     }
@@ -452,12 +604,19 @@ pub fn story_mode_manager(
 }
 
 pub fn hud_lives_indicator_update(
-    mut query: Query<&mut Text, With<BottomLeftDisplay1>>,
-    query2: Query<&Health, (With<Protagonist>, Changed<Health>)>,
+    mut query: Query<(&mut Text, &LivesDisplay)>,
+    query2: Query<(&Penguin, &Health), With<Protagonist>>,
 ) {
-    // need to be careful around death edge cases here
-    if let Ok(player) = query2.get_single() {
-        query.single_mut().sections[0].value = format_hud_lives(player.lives);
+    for (mut text, LivesDisplay(penguin)) in query.iter_mut() {
+        // the protagonists out of lives are despawned
+        let lives = query2
+            .iter()
+            .find(|(p, _)| p.0 == penguin.0)
+            .map_or(0, |(_, h)| h.lives);
+        let value = format_hud_lives(lives);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
     }
 }
 
@@ -827,20 +986,31 @@ pub fn setup_high_score_name_input(
     });
 }
 
-pub fn high_score_name_input_update(
+// the name goes into the high score table `T` of the game mode
+pub fn high_score_name_input_update<T: Resource + AsMut<PersistentHighScores>>(
     mut commands: Commands,
     context: Res<HighScoreNameInputContext>,
+    game_context: Res<GameContext>,
     inputs: Res<InputActionStatusTracker>,
     mut char_input_events: EventReader<ReceivedCharacter>,
-    mut persistent_high_scores: ResMut<PersistentHighScores>,
+    persistent_high_scores: ResMut<T>,
     game_score: Res<GameScore>,
+    story_mode_configuration: Option<Res<StoryModeConfiguration>>,
     mut query: Query<&mut Text>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
+    let persistent_high_scores = persistent_high_scores.into_inner().as_mut();
+    // story mode runs continued from a save are marked in the table
+    let continued = story_mode_configuration.map_or(false, |c| c.continue_from.is_some());
+
     if inputs.is_active(InputAction::Escape) {
-        persistent_high_scores.insert_score(String::from("<unnamed_player>"), game_score.0);
+        persistent_high_scores.insert_score(
+            String::from("<unnamed_player>"),
+            game_score.0,
+            continued,
+        );
         commands.remove_resource::<HighScoreNameInputContext>();
-        next_state.set(AppState::StoryModeManager);
+        next_state.set(game_context.game_mode_manager_state);
         return;
     }
 
@@ -863,9 +1033,9 @@ pub fn high_score_name_input_update(
             name.clone()
         };
 
-        persistent_high_scores.insert_score(name, game_score.0);
+        persistent_high_scores.insert_score(name, game_score.0, continued);
         commands.remove_resource::<HighScoreNameInputContext>();
-        next_state.set(AppState::StoryModeManager);
+        next_state.set(game_context.game_mode_manager_state);
     }
 }
 
@@ -893,11 +1063,15 @@ pub fn teardown(
     // clear resources
     // common
     commands.remove_resource::<GameContext>();
+    commands.remove_resource::<MatchLoot>();
     commands.remove_resource::<GameTimer>();
     commands.remove_resource::<WorldID>();
     commands.remove_resource::<MapSize>();
+    commands.remove_resource::<GameRng>();
+    commands.remove_resource::<GameRules>();
 
     // story mode
+    commands.remove_resource::<StoryModeConfiguration>();
     commands.remove_resource::<StoryModeContext>();
     commands.remove_resource::<GameScore>();
     commands.remove_resource::<ExitPosition>();
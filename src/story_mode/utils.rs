@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use rand::Rng;
 
 use crate::game::{
+    ascii_map::AsciiMap,
     components::*,
     constants::{TILE_HEIGHT, TILE_WIDTH},
     resources::*,
@@ -19,14 +20,40 @@ pub fn format_hud_points(points: usize) -> String {
     format!("Points:{}", points)
 }
 
-pub fn spawn_story_mode_mobs(
-    commands: &mut Commands,
-    game_textures: &GameTextures,
+// where each protagonist starts a level, on handcrafted maps without a spot for the second
+// protagonist both start from the same one
+pub fn get_story_mode_protagonist_spawn_positions(
+    level_map: Option<&AsciiMap>,
+    level: Level,
+    map_size: MapSize,
+) -> [Position; 2] {
+    if let Some(level_map) = level_map {
+        let penguin_spawn_positions = level_map.penguin_spawn_positions();
+        let first = penguin_spawn_positions[0];
+        // the boss takes the second spot in boss rooms
+        let second = match level {
+            Level::Regular(_) => penguin_spawn_positions.get(1),
+            Level::BossRoom => penguin_spawn_positions.get(2),
+        };
+        [first, second.copied().unwrap_or(first)]
+    } else {
+        match level {
+            Level::Regular(_) => [Position { y: 1, x: 1 }, Position { y: 1, x: 3 }],
+            Level::BossRoom => {
+                let y = map_size.rows as isize - 4;
+                let x = map_size.columns as isize / 2;
+                [Position { y, x }, Position { y, x: x - 2 }]
+            }
+        }
+    }
+}
+
+pub fn get_story_mode_mob_spawn_positions(
     level: Level,
     world_id: WorldID,
     map_size: MapSize,
+    rng: &mut impl Rng,
 ) -> Vec<Position> {
-    // spawn mobs
     let mob_number = if let Level::Regular(num) = level {
         num + 1
     } else {
@@ -54,94 +81,111 @@ pub fn spawn_story_mode_mobs(
         map_size.rows - 4,
         7,
     ];
-    let mut rng = rand::thread_rng();
     let bias = rng.gen::<usize>() % 20;
 
-    let mut mob_spawn_positions = vec![];
-    for i in 0..mob_number {
-        let (base_texture, immortal_texture, wall_hack, health, point_value) = if i > 3 {
+    (0..mob_number)
+        .map(|i| Position {
+            x: x[(i + bias) % 6] as isize,
+            y: y[(i + bias) % 6] as isize,
+        })
+        .collect()
+}
+
+pub fn spawn_story_mode_mobs(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    mob_spawn_positions: &[Position],
+) {
+    for (i, mob_spawn_position) in mob_spawn_positions.iter().copied().enumerate() {
+        let mob_type = if i > 3 {
             if i > 5 {
-                (
-                    game_textures.bat.clone(),
-                    game_textures.immortal_bat.clone(),
-                    true,
-                    3,
-                    90,
-                )
+                MobType::Bat
             } else {
-                (
-                    game_textures.hatter.clone(),
-                    game_textures.immortal_hatter.clone(),
-                    false,
-                    2,
-                    70,
-                )
+                MobType::Hatter
             }
         } else {
-            (
-                game_textures.crook.clone(),
-                game_textures.immortal_crook.clone(),
-                false,
-                1,
-                50,
-            )
+            MobType::Crook
         };
 
-        let mob_spawn_position = Position {
-            x: x[(i + bias) % 6] as isize,
-            y: y[(i + bias) % 6] as isize,
-        };
-        mob_spawn_positions.push(mob_spawn_position);
-
-        let mut ec = commands.spawn((
-            SpriteBundle {
-                texture: base_texture.clone(),
-                transform: Transform::from_xyz(
-                    get_x(mob_spawn_position.x),
-                    get_y(mob_spawn_position.y),
-                    50.0,
-                ),
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
-                    ..Default::default()
-                },
+        spawn_story_mode_mob(commands, game_textures, mob_type, mob_spawn_position);
+    }
+}
+
+pub fn spawn_story_mode_mob(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    mob_type: MobType,
+    mob_spawn_position: Position,
+) -> Entity {
+    let (base_texture, immortal_texture, wall_hack, health, point_value) = match mob_type {
+        MobType::Crook => (
+            game_textures.crook.clone(),
+            game_textures.immortal_crook.clone(),
+            false,
+            1,
+            50,
+        ),
+        MobType::Hatter => (
+            game_textures.hatter.clone(),
+            game_textures.immortal_hatter.clone(),
+            false,
+            2,
+            70,
+        ),
+        MobType::Bat => (
+            game_textures.bat.clone(),
+            game_textures.immortal_bat.clone(),
+            true,
+            3,
+            90,
+        ),
+    };
+
+    let mut ec = commands.spawn((
+        SpriteBundle {
+            texture: base_texture.clone(),
+            transform: Transform::from_xyz(
+                get_x(mob_spawn_position.x),
+                get_y(mob_spawn_position.y),
+                50.0,
+            ),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
                 ..Default::default()
             },
-            BaseTexture(base_texture),
-            ImmortalTexture(immortal_texture),
-            Player,
-            MobAI::default(),
-            MoveCooldown(Cooldown::from_seconds(0.4)),
-            Health {
-                lives: 1,
-                max_health: health,
-                health,
-            },
-            mob_spawn_position,
-            SpawnPosition(mob_spawn_position),
-            MeleeAttacker,
-            TeamID(1),
-            PointValue(point_value),
-        ));
-
-        if wall_hack {
-            ec.insert(WallHack);
-        }
+            ..Default::default()
+        },
+        BaseTexture(base_texture),
+        ImmortalTexture(immortal_texture),
+        Player,
+        MobAI::default(),
+        mob_type,
+        MoveCooldown(Cooldown::from_seconds(0.4)),
+        Health {
+            lives: 1,
+            max_health: health,
+            health,
+        },
+        mob_spawn_position,
+        SpawnPosition(mob_spawn_position),
+        MeleeAttacker,
+        TeamID(1),
+        PointValue(point_value),
+    ));
+
+    if wall_hack {
+        ec.insert(WallHack);
     }
 
-    mob_spawn_positions
+    ec.id()
 }
 
 pub fn spawn_story_mode_boss(
     commands: &mut Commands,
     game_textures: &GameTextures,
     world_id: WorldID,
-    map_size: MapSize,
-) -> (Position, Penguin) {
-    let boss_spawn_position = Position {
-        y: 3,
-        x: map_size.columns as isize / 2,
-    };
+    boss_spawn_position: Position,
+) -> Penguin {
     let boss_penguin_tag = Penguin(3 + world_id.0);
     let base_texture = game_textures.get_penguin_texture(boss_penguin_tag).clone();
     let immortal_texture = game_textures.immortal_penguin.clone();
@@ -182,5 +226,40 @@ pub fn spawn_story_mode_boss(
         PointValue(200),
     ));
 
-    (boss_spawn_position, boss_penguin_tag)
+    boss_penguin_tag
+}
+
+// handcrafted story levels are picked up by name, e.g. "story_1_2" or "story_1_boss"
+pub fn get_story_mode_level_map(
+    map_library: &MapLibrary,
+    world_id: WorldID,
+    level: Level,
+    map_size: MapSize,
+) -> Option<&AsciiMap> {
+    let map_name = match level {
+        Level::Regular(num) => format!("story_{}_{}", world_id.0, num),
+        Level::BossRoom => format!("story_{}_boss", world_id.0),
+    };
+    let ascii_map = map_library.get(&map_name)?;
+
+    // the player and the boss need a spawn point, regular levels also need an exit
+    let level_map_size = ascii_map.map_size();
+    let usable = level_map_size.rows == map_size.rows
+        && level_map_size.columns == map_size.columns
+        && match level {
+            Level::Regular(_) => {
+                !ascii_map.penguin_spawn_positions().is_empty()
+                    && ascii_map.exit_position().is_some()
+            }
+            Level::BossRoom => ascii_map.penguin_spawn_positions().len() >= 2,
+        };
+    if usable {
+        Some(ascii_map)
+    } else {
+        println!(
+            "Map {} is not a valid {}x{} story level, generating one instead.",
+            map_name, map_size.rows, map_size.columns
+        );
+        None
+    }
 }
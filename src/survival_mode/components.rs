@@ -0,0 +1,5 @@
+use bevy::{ecs as bevy_ecs, prelude::Component};
+
+// a tile the mobs of the waves come out of
+#[derive(Component)]
+pub struct MobSpawner;
@@ -0,0 +1,7 @@
+pub const SURVIVAL_MODE_PLAYER_LIVES: usize = 3;
+// the calm before each wave
+pub const WAVE_BREAK_SECS: f32 = 3.0;
+pub const MOB_SPAWN_INTERVAL_SECS: f32 = 1.0;
+pub const MAX_WAVE_SIZE: usize = 30;
+// multiplied by the number of the survived wave
+pub const WAVE_SURVIVED_POINTS: usize = 100;
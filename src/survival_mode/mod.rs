@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::resources::PersistentSurvivalHighScores,
+    game::{
+        common_game_systems,
+        systems::{resize_window, setup_penguin_portraits, spawn_cameras},
+        Set,
+    },
+    story_mode::{high_score_name_input_update, hud_points_indicator_update},
+    AppState,
+};
+
+pub use self::resources::SurvivalModeConfiguration;
+use self::{resources::SurvivalModeContext, systems::*};
+
+mod components;
+mod constants;
+mod resources;
+mod systems;
+mod utils;
+
+// endless waves of story mode mobs for one or two players
+pub struct SurvivalModePlugin;
+
+impl Plugin for SurvivalModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::SurvivalModeSetup),
+            (
+                setup_survival_mode,
+                apply_deferred,
+                (resize_window, spawn_cameras),
+            )
+                .chain(),
+        )
+        .add_systems(OnEnter(AppState::SurvivalModeTeardown), teardown)
+        .add_systems(
+            Update,
+            survival_mode_manager.run_if(in_state(AppState::SurvivalModeManager)),
+        )
+        .add_systems(
+            Update,
+            high_score_name_input_update::<PersistentSurvivalHighScores>
+                .after(crate::common::Label::InputMapping)
+                .run_if(resource_exists::<SurvivalModeContext>())
+                .run_if(in_state(AppState::HighScoreNameInput)),
+        );
+
+        app.add_systems(
+            OnEnter(AppState::SurvivalModeInGame),
+            setup_penguin_portraits,
+        );
+        app.add_systems(
+            Update,
+            (
+                common_game_systems(),
+                (wave_update, apply_deferred)
+                    .chain()
+                    .after(Set::PlayerDeathEvent),
+                // game end check
+                finish_survival
+                    .after(Set::PlayerMovement)
+                    .after(Set::PlayerDeathEvent),
+                // update HUD
+                (survival_hud_update, hud_wave_indicator_update)
+                    .after(Set::PlayerDeathEvent)
+                    .after(wave_update),
+                hud_points_indicator_update
+                    .after(Set::PlayerDeathEvent)
+                    .after(wave_update),
+            )
+                .run_if(in_state(AppState::SurvivalModeInGame)),
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use bevy::{ecs as bevy_ecs, prelude::*};
+
+use crate::game::components::{MobType, Position};
+
+use super::constants::{MOB_SPAWN_INTERVAL_SECS, WAVE_BREAK_SECS};
+
+#[derive(Resource)]
+pub struct SurvivalModeConfiguration {
+    pub amount_of_players: usize,
+}
+
+pub enum SurvivalModeState {
+    MapTransition,
+    InGame,
+    ScoreCheck,
+    HighScoreNameInput,
+}
+
+pub enum WaveState {
+    // the calm before the next wave
+    Break(Timer),
+    // the mobs still waiting to come out of the spawners
+    Spawning(Vec<MobType>),
+    // every mob of the wave is out
+    Fighting,
+}
+
+#[derive(Resource)]
+pub struct SurvivalModeContext {
+    pub state: SurvivalModeState,
+    pub wave: usize,
+    pub wave_state: WaveState,
+    pub spawn_timer: Timer,
+    pub spawners: Vec<Position>,
+    // the spawners take turns
+    pub next_spawner: usize,
+    pub time_survived: Duration,
+}
+
+impl SurvivalModeContext {
+    pub fn new(state: SurvivalModeState, spawners: Vec<Position>) -> Self {
+        Self {
+            state,
+            wave: 0,
+            wave_state: WaveState::Break(Timer::from_seconds(WAVE_BREAK_SECS, TimerMode::Once)),
+            spawn_timer: Timer::from_seconds(MOB_SPAWN_INTERVAL_SECS, TimerMode::Repeating),
+            spawners,
+            next_spawner: 0,
+            time_survived: Duration::ZERO,
+        }
+    }
+}
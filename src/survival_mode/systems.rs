@@ -0,0 +1,383 @@
+use bevy::{ecs::event::Events, prelude::*, utils::HashSet};
+
+use crate::{
+    common::{
+        constants::{COLORS, PIXEL_SCALE},
+        resources::{Fonts, GameOption, GameOptionStore, PersistentSurvivalHighScores},
+    },
+    game::{
+        components::*,
+        constants::*,
+        events::*,
+        loot_tables::{LootTables, MatchLoot},
+        map_generators::{ClassicMapGenerator, MapGenerationParams},
+        resources::*,
+        rules::GameRules,
+        utils::*,
+    },
+    map_transition::MapTransitionInput,
+    story_mode::{format_hud_points, spawn_story_mode_mob},
+    AppState,
+};
+
+use super::{constants::*, resources::*, utils::*};
+
+pub fn setup_survival_mode(
+    mut commands: Commands,
+    mut game_textures: ResMut<GameTextures>,
+    (hud_colors, fonts): (Res<HUDColors>, Res<Fonts>),
+    (survival_mode_configuration, game_option_store): (
+        Res<SurvivalModeConfiguration>,
+        Res<GameOptionStore>,
+    ),
+    (loot_tables, next_match_seed): (Res<LootTables>, Option<Res<NextMatchSeed>>),
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let map_size = MapSize {
+        rows: 11,
+        columns: 15,
+    };
+    let world_id = WorldID(1);
+    let mut game_rng = GameRng::for_new_match(next_match_seed.as_deref());
+    // the players are in this together
+    let game_rules = GameRules {
+        friendly_fire: false,
+        ..Default::default()
+    };
+
+    game_textures.set_map_textures(world_id);
+
+    // spawn players
+    let player_spawn_positions =
+        get_player_spawn_positions(survival_mode_configuration.amount_of_players, map_size);
+    for (i, player_spawn_position) in player_spawn_positions.iter().copied().enumerate() {
+        let penguin_tag = Penguin(i);
+        let base_texture = game_textures.get_penguin_texture(penguin_tag).clone();
+        let immortal_texture = game_textures.immortal_penguin.clone();
+        commands.spawn((
+            SpriteBundle {
+                texture: base_texture.clone(),
+                transform: Transform::from_xyz(
+                    get_x(player_spawn_position.x),
+                    get_y(player_spawn_position.y),
+                    50.0,
+                ),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            BaseTexture(base_texture),
+            ImmortalTexture(immortal_texture),
+            Player,
+            HumanControlled(i),
+            Health {
+                lives: SURVIVAL_MODE_PLAYER_LIVES,
+                max_health: 1,
+                health: 1,
+            },
+            player_spawn_position,
+            SpawnPosition(player_spawn_position),
+            BombSatchel {
+                bombs_available: game_rules.starting_bombs,
+                bomb_range: game_rules.starting_bomb_range,
+            },
+            penguin_tag,
+            TeamID(0),
+        ));
+    }
+
+    // spawn the map and the spawners in its corners
+    let spawner_positions = get_spawner_positions(map_size);
+    let wall_entity_reveal_groups = spawn_map(
+        &mut commands,
+        &game_textures,
+        &ClassicMapGenerator::default(),
+        &MapGenerationParams {
+            map_size,
+            percent_of_passable_positions_to_fill: 40.0,
+            penguin_spawn_positions: &player_spawn_positions,
+            mob_spawn_positions: &spawner_positions,
+            spawn_exit: false,
+        },
+        &mut game_rng.map,
+    )
+    .unwrap();
+    for spawner_position in spawner_positions.iter().copied() {
+        spawn_mob_spawner(&mut commands, &game_textures, spawner_position);
+    }
+
+    // spawn HUD
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..Default::default()
+                },
+                background_color: Color::NONE.into(),
+                ..Default::default()
+            },
+            UIRoot,
+            UIComponent,
+        ))
+        .with_children(|parent| {
+            init_hud(
+                parent,
+                &hud_colors,
+                &fonts,
+                (map_size.columns * TILE_WIDTH) as f32,
+                world_id,
+                true,
+                true,
+                Some(&|parent: &mut ChildBuilder| {
+                    // wave display
+                    parent.spawn((
+                        TextBundle {
+                            text: Text::from_section(
+                                format_hud_wave(0),
+                                TextStyle {
+                                    font: fonts.mono.clone(),
+                                    font_size: 2.0 * PIXEL_SCALE as f32,
+                                    color: COLORS[0].into(),
+                                },
+                            ),
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                top: Val::Px(12.0 * PIXEL_SCALE as f32),
+                                left: Val::Px(6.0 * PIXEL_SCALE as f32),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        UIComponent,
+                        BottomLeftDisplay1,
+                    ));
+
+                    // points display
+                    parent.spawn((
+                        TextBundle {
+                            text: Text::from_section(
+                                format_hud_points(0),
+                                TextStyle {
+                                    font: fonts.mono.clone(),
+                                    font_size: 2.0 * PIXEL_SCALE as f32,
+                                    color: COLORS[0].into(),
+                                },
+                            ),
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                top: Val::Px(12.0 * PIXEL_SCALE as f32),
+                                left: Val::Px(16.0 * PIXEL_SCALE as f32),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        UIComponent,
+                        BottomLeftDisplay2,
+                    ));
+                }),
+            );
+        });
+
+    let state = if game_option_store.get(GameOption::Transition) {
+        commands.insert_resource(MapTransitionInput {
+            wall_entity_reveal_groups,
+            next_state: AppState::SurvivalModeManager,
+        });
+        next_state.set(AppState::MapTransition);
+        SurvivalModeState::MapTransition
+    } else {
+        next_state.set(AppState::SurvivalModeInGame);
+        SurvivalModeState::InGame
+    };
+    commands.insert_resource(SurvivalModeContext::new(state, spawner_positions));
+
+    commands.insert_resource(GameContext {
+        pausable: true,
+        game_mode_manager_state: AppState::SurvivalModeManager,
+    });
+    commands.insert_resource(MatchLoot::new(
+        &loot_tables,
+        loot_tables.story_world(world_id),
+        &game_rules,
+    ));
+    commands.insert_resource(GameScore(0));
+    commands.insert_resource(world_id);
+    commands.insert_resource(map_size);
+    commands.insert_resource(game_rng);
+    commands.remove_resource::<NextMatchSeed>();
+    commands.insert_resource(game_rules);
+}
+
+pub fn survival_mode_manager(
+    mut survival_mode_context: ResMut<SurvivalModeContext>,
+    game_score: Res<GameScore>,
+    persistent_survival_high_scores: Res<PersistentSurvivalHighScores>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    loop {
+        match survival_mode_context.state {
+            SurvivalModeState::MapTransition => {
+                survival_mode_context.state = SurvivalModeState::InGame;
+                next_state.set(AppState::SurvivalModeInGame);
+            }
+            SurvivalModeState::InGame => {
+                println!(
+                    "Game over in wave {}! Final score: {}",
+                    survival_mode_context.wave, game_score.0
+                );
+                survival_mode_context.state = SurvivalModeState::ScoreCheck;
+                continue;
+            }
+            SurvivalModeState::ScoreCheck => {
+                survival_mode_context.state = SurvivalModeState::HighScoreNameInput;
+                if game_score.0 > persistent_survival_high_scores.entry_threshold() {
+                    next_state.set(AppState::HighScoreNameInput);
+                } else {
+                    // skip to the step below where we choose the next state
+                    continue;
+                }
+            }
+            SurvivalModeState::HighScoreNameInput => {
+                next_state.set(AppState::SurvivalModeTeardown);
+            }
+        }
+        break;
+    }
+}
+
+pub fn wave_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    (game_textures, game_rules): (Res<GameTextures>, Res<GameRules>),
+    mut survival_mode_context: ResMut<SurvivalModeContext>,
+    mut game_score: ResMut<GameScore>,
+    query: Query<(), With<MobAI>>,
+    query2: Query<&Position, With<HumanControlled>>,
+) {
+    let survival_mode_context = &mut *survival_mode_context;
+    survival_mode_context.time_survived += time.delta();
+
+    match &mut survival_mode_context.wave_state {
+        WaveState::Break(timer) => {
+            timer.tick(time.delta());
+            if timer.finished() {
+                survival_mode_context.wave += 1;
+                println!("Wave {} incoming!", survival_mode_context.wave);
+                survival_mode_context.spawn_timer.reset();
+                survival_mode_context.wave_state =
+                    WaveState::Spawning(get_wave_mobs(survival_mode_context.wave));
+            }
+        }
+        WaveState::Spawning(mobs) => {
+            survival_mode_context.spawn_timer.tick(time.delta());
+            if survival_mode_context.spawn_timer.just_finished() {
+                // the spawners a player is standing on are skipped
+                let player_positions: HashSet<Position> = query2.iter().copied().collect();
+                let spawner_count = survival_mode_context.spawners.len();
+                let free_spawner = (0..spawner_count)
+                    .map(|i| (survival_mode_context.next_spawner + i) % spawner_count)
+                    .find(|i| !player_positions.contains(&survival_mode_context.spawners[*i]));
+                if let Some(spawner) = free_spawner {
+                    let mob = spawn_story_mode_mob(
+                        &mut commands,
+                        &game_textures,
+                        mobs.remove(0),
+                        survival_mode_context.spawners[spawner],
+                    );
+                    commands
+                        .entity(mob)
+                        .insert(Immortal::new(game_rules.immortality_secs));
+                    survival_mode_context.next_spawner = spawner + 1;
+                }
+            }
+
+            if mobs.is_empty() {
+                survival_mode_context.wave_state = WaveState::Fighting;
+            }
+        }
+        WaveState::Fighting => {
+            if query.is_empty() {
+                println!("Wave {} survived!", survival_mode_context.wave);
+                game_score.0 += WAVE_SURVIVED_POINTS * survival_mode_context.wave;
+                survival_mode_context.wave_state =
+                    WaveState::Break(Timer::from_seconds(WAVE_BREAK_SECS, TimerMode::Once));
+            }
+        }
+    }
+}
+
+pub fn survival_hud_update(
+    mut commands: Commands,
+    survival_mode_context: Res<SurvivalModeContext>,
+    mut query: Query<&mut Text, With<GameTimerDisplay>>,
+    query2: Query<&Penguin>,
+    query3: Query<(Entity, &PenguinPortrait)>,
+) {
+    // the clock counts the time survived
+    query.single_mut().sections[0].value =
+        format_hud_time(survival_mode_context.time_survived.as_secs() as usize);
+
+    // remove the portraits of fallen players
+    for (entity, PenguinPortrait(penguin)) in query3.iter() {
+        if !query2.iter().any(|p| p.0 == penguin.0) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn hud_wave_indicator_update(
+    survival_mode_context: Res<SurvivalModeContext>,
+    mut query: Query<&mut Text, With<BottomLeftDisplay1>>,
+) {
+    query.single_mut().sections[0].value = format_hud_wave(survival_mode_context.wave);
+}
+
+pub fn finish_survival(
+    query: Query<(), With<HumanControlled>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if query.is_empty() {
+        next_state.set(AppState::SurvivalModeManager);
+    }
+}
+
+pub fn teardown(
+    mut commands: Commands,
+    query: Query<Entity, Without<Window>>,
+    mut player_action_events: ResMut<Events<PlayerActionEvent>>,
+    mut explosion_events: ResMut<Events<ExplosionEvent>>,
+    mut burn_events: ResMut<Events<BurnEvent>>,
+    mut damage_events: ResMut<Events<DamageEvent>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // clear entities
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // clear events
+    player_action_events.clear();
+    explosion_events.clear();
+    burn_events.clear();
+    damage_events.clear();
+
+    // clear resources
+    // common
+    commands.remove_resource::<GameContext>();
+    commands.remove_resource::<MatchLoot>();
+    commands.remove_resource::<WorldID>();
+    commands.remove_resource::<MapSize>();
+    commands.remove_resource::<GameRng>();
+    commands.remove_resource::<GameRules>();
+
+    // survival mode
+    commands.remove_resource::<SurvivalModeConfiguration>();
+    commands.remove_resource::<SurvivalModeContext>();
+    commands.remove_resource::<GameScore>();
+
+    next_state.set(AppState::MainMenu);
+}
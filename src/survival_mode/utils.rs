@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+
+use crate::game::{
+    components::{MobType, Position},
+    constants::{TILE_HEIGHT, TILE_WIDTH},
+    resources::{GameTextures, MapSize},
+    utils::{get_x, get_y},
+};
+
+use super::{components::MobSpawner, constants::MAX_WAVE_SIZE};
+
+pub fn format_hud_wave(wave: usize) -> String {
+    format!("Wave:{}", wave)
+}
+
+// the players start out in the middle of the map
+pub fn get_player_spawn_positions(amount_of_players: usize, map_size: MapSize) -> Vec<Position> {
+    let y = map_size.rows as isize / 2;
+    let x = map_size.columns as isize / 2;
+    if amount_of_players == 1 {
+        vec![Position { y, x }]
+    } else {
+        vec![Position { y, x: x - 2 }, Position { y, x: x + 2 }]
+    }
+}
+
+// the mobs come out of the corners
+pub fn get_spawner_positions(map_size: MapSize) -> Vec<Position> {
+    let bottom = map_size.rows as isize - 2;
+    let right = map_size.columns as isize - 2;
+    vec![
+        Position { y: 1, x: 1 },
+        Position { y: 1, x: right },
+        Position { y: bottom, x: 1 },
+        Position {
+            y: bottom,
+            x: right,
+        },
+    ]
+}
+
+// every wave brings two more mobs, mixing in hatters from the third wave and bats from the sixth
+pub fn get_wave_mobs(wave: usize) -> Vec<MobType> {
+    let wave_size = (1 + 2 * wave).min(MAX_WAVE_SIZE);
+    let hatters = wave.saturating_sub(2).min(wave_size / 2);
+    let bats = wave.saturating_sub(5).min(wave_size / 3);
+
+    std::iter::repeat(MobType::Crook)
+        .take(wave_size - hatters - bats)
+        .chain(std::iter::repeat(MobType::Hatter).take(hatters))
+        .chain(std::iter::repeat(MobType::Bat).take(bats))
+        .collect()
+}
+
+pub fn spawn_mob_spawner(
+    commands: &mut Commands,
+    game_textures: &GameTextures,
+    position: Position,
+) {
+    commands.spawn((
+        SpriteBundle {
+            texture: game_textures.exit.clone(),
+            transform: Transform::from_xyz(get_x(position.x), get_y(position.y), 10.0),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(TILE_WIDTH as f32, TILE_HEIGHT as f32)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        position,
+        MobSpawner,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wave_mobs() {
+        assert_eq!(get_wave_mobs(1), vec![MobType::Crook; 3]);
+
+        let count = |wave, mob_type| {
+            get_wave_mobs(wave)
+                .into_iter()
+                .filter(|m| *m == mob_type)
+                .count()
+        };
+        assert_eq!(count(2, MobType::Hatter), 0);
+        assert_eq!(count(3, MobType::Hatter), 1);
+        assert_eq!(count(5, MobType::Bat), 0);
+        assert_eq!(count(6, MobType::Bat), 1);
+
+        // the waves keep growing up to a cap, with the stronger mobs taking over
+        for wave in 1..50 {
+            assert!(get_wave_mobs(wave + 1).len() >= get_wave_mobs(wave).len());
+            assert!(get_wave_mobs(wave).len() <= MAX_WAVE_SIZE);
+            assert!(count(wave + 1, MobType::Crook) <= count(wave, MobType::Crook) + 2);
+        }
+        assert!(count(50, MobType::Crook) < count(50, MobType::Hatter));
+    }
+}
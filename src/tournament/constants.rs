@@ -0,0 +1,6 @@
+// the rounds are simulated with a fixed time step instead of the real time
+pub const TOURNAMENT_FRAME_DURATION_SECS: f32 = 1.0 / 60.0;
+
+pub const DEFAULT_TOURNAMENT_PLAYER_COUNT: usize = 4;
+pub const DEFAULT_TOURNAMENT_ROUND_COUNT: usize = 100;
+pub const DEFAULT_TOURNAMENT_REPORT_FILE_PATH: &str = "local/tournament_report.json";
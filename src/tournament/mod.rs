@@ -0,0 +1,101 @@
+mod constants;
+mod resources;
+mod systems;
+mod types;
+
+use std::time::Duration;
+
+use bevy::{ecs::schedule::ExecutorKind, prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    audio::{Audio, SoundHandles},
+    battle_mode::{finish_round, on_death_item_pinata, revenge_ghost_systems},
+    common::resources::{Fonts, InputActionStatusTracker},
+    game::{
+        common_game_systems,
+        resources::{GameTextures, Sounds},
+        systems::{game_timer_tick, wall_of_death_update},
+        GamePlugin, Set,
+    },
+    AppState,
+};
+
+use self::{constants::TOURNAMENT_FRAME_DURATION_SECS, resources::*, systems::*};
+
+/// Runs battle mode rounds between bots without a window or audio and saves the results as JSON.
+pub fn run_tournament() {
+    let tournament_configuration =
+        match TournamentConfiguration::from_args(std::env::args().skip(1)) {
+            Ok(tournament_configuration) => tournament_configuration,
+            Err(e) => {
+                eprintln!("{}\n{}", e, TournamentConfiguration::USAGE);
+                std::process::exit(1);
+            }
+        };
+
+    let mut app = App::new();
+
+    // placeholders for the resources that are normally loaded from the assets
+    let mut sound_handles = SoundHandles::default();
+    app.insert_resource(GameTextures::placeholder())
+        .insert_resource(Sounds {
+            boom: sound_handles.add_handle(Handle::default()),
+            pause: sound_handles.add_handle(Handle::default()),
+        })
+        .insert_resource(Fonts {
+            mono: Handle::default(),
+        })
+        .init_resource::<Audio>()
+        .init_resource::<InputActionStatusTracker>();
+
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            TOURNAMENT_FRAME_DURATION_SECS,
+        )))
+        .add_state::<AppState>()
+        .add_plugins(GamePlugin)
+        .insert_resource(tournament_configuration)
+        .add_systems(Startup, setup_tournament)
+        .add_systems(
+            Update,
+            (
+                record_round_outcome,
+                apply_deferred,
+                (
+                    setup_tournament_round.run_if(tournament_in_progress),
+                    finish_tournament.run_if(not(tournament_in_progress)),
+                ),
+            )
+                .chain()
+                .run_if(in_state(AppState::BattleModeManager)),
+        )
+        .add_systems(
+            Update,
+            (
+                common_game_systems(),
+                revenge_ghost_systems(),
+                game_timer_tick.in_set(Set::TimeUpdate),
+                (wall_of_death_update, apply_deferred)
+                    .chain()
+                    .in_set(Set::PlayerDeathEvent)
+                    .in_set(Set::BombRestockEvent)
+                    .in_set(Set::ItemDespawn),
+                (
+                    (on_death_item_pinata, apply_deferred)
+                        .chain()
+                        .in_set(Set::ItemSpawn),
+                    finish_round.after(Set::TimeUpdate),
+                    track_kills,
+                )
+                    .after(Set::PlayerDeathEvent),
+            )
+                .run_if(in_state(AppState::BattleModeInGame)),
+        );
+
+    // the game systems are too small to benefit from running in parallel
+    app.edit_schedule(Update, |schedule| {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    });
+
+    app.run();
+}
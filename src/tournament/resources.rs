@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use bevy::{ecs as bevy_ecs, prelude::*, utils::HashMap};
+
+use crate::game::{
+    components::Penguin,
+    map_generators::MapGeneratorKind,
+    rules::{GameRules, RulesetLibrary},
+    types::BotDifficulty,
+};
+
+use super::{constants::*, types::PenguinStats};
+
+#[derive(Resource)]
+pub struct TournamentConfiguration {
+    // one entry per penguin
+    pub bot_difficulties: Vec<BotDifficulty>,
+    pub rounds: usize,
+    // `None` picks a random generator each round
+    pub map_generator: Option<MapGeneratorKind>,
+    pub seed: Option<u64>,
+    pub rules: GameRules,
+    pub report_file_path: String,
+}
+
+impl TournamentConfiguration {
+    pub const USAGE: &'static str = "Usage: tournament [--players <2-8>] [--difficulties <easy|medium|hard>,...] [--rounds <count>] [--generator <classic|mirrored|rotational|caves|maze|random>] [--seed <number>] [--rules <ruleset>] [--output <path>]";
+
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut player_count = DEFAULT_TOURNAMENT_PLAYER_COUNT;
+        let mut bot_difficulties = vec![BotDifficulty::Medium];
+        let mut rounds = DEFAULT_TOURNAMENT_ROUND_COUNT;
+        let mut map_generator = Some(MapGeneratorKind::Classic);
+        let mut seed = None;
+        let mut rules = GameRules::default();
+        let mut report_file_path = DEFAULT_TOURNAMENT_REPORT_FILE_PATH.to_string();
+
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for {}.", flag))?;
+            match flag.as_str() {
+                "--players" => {
+                    player_count = value
+                        .parse()
+                        .map_err(|_| format!("Invalid player count: {}.", value))?
+                }
+                "--difficulties" => {
+                    bot_difficulties = value
+                        .split(',')
+                        .map(|d| match d.trim().to_lowercase().as_str() {
+                            "easy" => Ok(BotDifficulty::Easy),
+                            "medium" => Ok(BotDifficulty::Medium),
+                            "hard" => Ok(BotDifficulty::Hard),
+                            _ => Err(format!("Invalid bot difficulty: {}.", d)),
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "--rounds" => {
+                    rounds = value
+                        .parse()
+                        .map_err(|_| format!("Invalid round count: {}.", value))?
+                }
+                "--generator" => {
+                    map_generator = match value.to_lowercase().as_str() {
+                        "random" => None,
+                        name => Some(
+                            MapGeneratorKind::LIST
+                                .into_iter()
+                                .find(|k| k.name().to_lowercase() == name)
+                                .ok_or_else(|| format!("Invalid map generator: {}.", value))?,
+                        ),
+                    }
+                }
+                "--seed" => {
+                    seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid seed: {}.", value))?,
+                    )
+                }
+                "--rules" => {
+                    rules = RulesetLibrary::default()
+                        .get(&value.to_lowercase())
+                        .cloned()
+                        .ok_or_else(|| format!("Invalid ruleset: {}.", value))?
+                }
+                "--output" => report_file_path = value,
+                _ => return Err(format!("Unknown argument: {}.", flag)),
+            }
+        }
+
+        if !(2..=8).contains(&player_count) {
+            return Err("The player count must be between 2 and 8.".to_string());
+        }
+        if rounds == 0 {
+            return Err("At least one round has to be played.".to_string());
+        }
+
+        Ok(Self {
+            // the difficulty list is repeated if there are fewer entries than penguins
+            bot_difficulties: bot_difficulties
+                .iter()
+                .cycle()
+                .take(player_count)
+                .copied()
+                .collect(),
+            rounds,
+            map_generator,
+            seed,
+            rules,
+            report_file_path,
+        })
+    }
+}
+
+#[derive(Resource)]
+pub struct TournamentContext {
+    pub rounds_played: usize,
+    pub ties: usize,
+    pub total_round_duration: Duration,
+    pub penguin_stats: Vec<PenguinStats>,
+    // kept so that kills can be credited to bombers who already died
+    pub player_penguins: HashMap<Entity, Penguin>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<TournamentConfiguration, String> {
+        TournamentConfiguration::from_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_difficulties_repeat_for_all_penguins() {
+        let configuration = parse(&["--players", "5", "--difficulties", "easy,hard"]).unwrap();
+
+        assert_eq!(configuration.bot_difficulties.len(), 5);
+        assert!(matches!(
+            configuration.bot_difficulties[..],
+            [
+                BotDifficulty::Easy,
+                BotDifficulty::Hard,
+                BotDifficulty::Easy,
+                BotDifficulty::Hard,
+                BotDifficulty::Easy
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(parse(&["--players", "9"]).is_err());
+        assert!(parse(&["--difficulties", "impossible"]).is_err());
+        assert!(parse(&["--rounds", "0"]).is_err());
+        assert!(parse(&["--generator", "spiral"]).is_err());
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--rules", "slow-motion"]).is_err());
+        assert!(parse(&["--unknown", "1"]).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use std::time::Duration;
+
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use rand::Rng;
+
+use crate::{
+    battle_mode::{
+        get_battle_mode_map_size_fill, get_battle_mode_player_spawn_positions,
+        spawn_battle_mode_map, spawn_battle_mode_players, BattleModeContext, BattleModeKind,
+        BattleModeState, Leaderboard, PenguinControlType, RoundOutcome,
+        BATTLE_MODE_ROUND_DURATION_SECS,
+    },
+    game::{
+        components::*,
+        events::{DamageSource, PlayerDeathEvent},
+        loot_tables::{LootTables, MatchLoot},
+        resources::*,
+        rules::GameRules,
+    },
+    AppState,
+};
+
+use super::{
+    resources::{TournamentConfiguration, TournamentContext},
+    types::{PenguinStats, TournamentReport},
+};
+
+pub fn setup_tournament(
+    mut commands: Commands,
+    mut game_textures: ResMut<GameTextures>,
+    tournament_configuration: Res<TournamentConfiguration>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut game_rng =
+        GameRng::for_new_match(tournament_configuration.seed.map(NextMatchSeed).as_ref());
+
+    let world_id = WorldID(game_rng.map.gen_range(1..=3));
+    game_textures.set_map_textures(world_id);
+
+    let bot_difficulties = &tournament_configuration.bot_difficulties;
+    let (map_size, percent_of_passable_positions_to_fill) =
+        get_battle_mode_map_size_fill(bot_difficulties.len());
+
+    let players: Vec<(Penguin, PenguinControlType, TeamID)> = bot_difficulties
+        .iter()
+        .enumerate()
+        .map(|(i, bot_difficulty)| {
+            (
+                Penguin(i),
+                PenguinControlType::Bot(*bot_difficulty),
+                TeamID(i),
+            )
+        })
+        .collect();
+
+    let leaderboard = Leaderboard {
+        scores: players.iter().map(|(_, _, t)| (*t, 0)).collect(),
+        winning_score: tournament_configuration.rounds,
+    };
+    commands.insert_resource(BattleModeContext {
+        state: BattleModeState::RoundSetup,
+        players,
+        leaderboard,
+        round_outcome: None,
+        percent_of_passable_positions_to_fill,
+        map: None,
+        map_generator: tournament_configuration.map_generator,
+        kind: BattleModeKind::LastStanding,
+    });
+    commands.insert_resource(TournamentContext {
+        rounds_played: 0,
+        ties: 0,
+        total_round_duration: Duration::ZERO,
+        penguin_stats: bot_difficulties
+            .iter()
+            .enumerate()
+            .map(|(i, bot_difficulty)| PenguinStats::new(i, *bot_difficulty))
+            .collect(),
+        player_penguins: HashMap::default(),
+    });
+
+    let game_mode_manager_state = AppState::BattleModeManager;
+    commands.insert_resource(GameContext {
+        pausable: false,
+        game_mode_manager_state,
+    });
+    commands.insert_resource(world_id);
+    commands.insert_resource(map_size);
+    commands.insert_resource(game_rng);
+    commands.insert_resource(tournament_configuration.rules.clone());
+
+    next_state.set(game_mode_manager_state);
+}
+
+pub fn record_round_outcome(
+    mut commands: Commands,
+    game_timer: Option<Res<GameTimer>>,
+    mut battle_mode_context: ResMut<BattleModeContext>,
+    mut tournament_context: ResMut<TournamentContext>,
+    query: Query<Entity>,
+    query2: Query<&Penguin, With<Player>>,
+) {
+    if let Some(round_outcome) = battle_mode_context.round_outcome.take() {
+        tournament_context.rounds_played += 1;
+        if let Some(game_timer) = game_timer {
+            tournament_context.total_round_duration += game_timer.0.elapsed();
+        }
+
+        match round_outcome {
+            RoundOutcome::Tie => {
+                println!(
+                    "Round {} over with no winners!",
+                    tournament_context.rounds_played
+                );
+                tournament_context.ties += 1;
+            }
+            // every penguin is a team of its own here
+            RoundOutcome::Winner(_) | RoundOutcome::Winners(_) => {
+                for team_id in round_outcome.winners() {
+                    println!(
+                        "Round {} won by player {:?}!",
+                        tournament_context.rounds_played, team_id.0
+                    );
+                    tournament_context.penguin_stats[team_id.0].wins += 1;
+                }
+            }
+        }
+
+        let survivors: HashSet<Penguin> = query2.iter().copied().collect();
+        for penguin_stats in tournament_context
+            .penguin_stats
+            .iter_mut()
+            .filter(|ps| !survivors.contains(&Penguin(ps.penguin)))
+        {
+            penguin_stats.deaths += 1;
+        }
+        tournament_context.player_penguins.clear();
+
+        for entity in query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn tournament_in_progress(
+    tournament_configuration: Res<TournamentConfiguration>,
+    tournament_context: Res<TournamentContext>,
+) -> bool {
+    tournament_context.rounds_played < tournament_configuration.rounds
+}
+
+pub fn setup_tournament_round(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    map_size: Res<MapSize>,
+    battle_mode_context: Res<BattleModeContext>,
+    (loot_tables, game_rules): (Res<LootTables>, Res<GameRules>),
+    mut game_rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let player_spawn_positions = spawn_battle_mode_players(
+        &mut commands,
+        &game_textures,
+        &get_battle_mode_player_spawn_positions(*map_size),
+        &battle_mode_context.players,
+        &game_rules,
+    );
+
+    if let Err(e) = spawn_battle_mode_map(
+        &mut commands,
+        &game_textures,
+        battle_mode_context.map_generator,
+        *map_size,
+        battle_mode_context.percent_of_passable_positions_to_fill,
+        &player_spawn_positions,
+        &mut game_rng.map,
+    ) {
+        eprintln!("Cannot generate the tournament map. Error: {}", e);
+        std::process::exit(1);
+    }
+
+    commands.insert_resource(MatchLoot::new(
+        &loot_tables,
+        &loot_tables.battle,
+        &game_rules,
+    ));
+    commands.insert_resource(GameTimer(Timer::from_seconds(
+        BATTLE_MODE_ROUND_DURATION_SECS as f32,
+        TimerMode::Once,
+    )));
+    commands.insert_resource(WallOfDeath::new(&game_rules));
+
+    next_state.set(AppState::BattleModeInGame);
+}
+
+pub fn finish_tournament(
+    tournament_configuration: Res<TournamentConfiguration>,
+    tournament_context: Res<TournamentContext>,
+    game_rng: Res<GameRng>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    let report = TournamentReport {
+        seed: game_rng.seed(),
+        rounds: tournament_context.rounds_played,
+        ties: tournament_context.ties,
+        average_round_length_secs: tournament_context.total_round_duration.as_secs_f32()
+            / tournament_context.rounds_played as f32,
+        penguins: &tournament_context.penguin_stats,
+    };
+    report.save(&tournament_configuration.report_file_path);
+
+    ev_exit.send(AppExit);
+}
+
+pub fn track_kills(
+    mut tournament_context: ResMut<TournamentContext>,
+    // revenge ghosts included
+    query: Query<(Entity, &Penguin)>,
+    mut ev_player_death_event: EventReader<PlayerDeathEvent>,
+) {
+    for (entity, penguin) in query.iter() {
+        tournament_context.player_penguins.insert(entity, *penguin);
+    }
+
+    for PlayerDeathEvent { player, source, .. } in ev_player_death_event.iter() {
+        let killer = match source {
+            DamageSource::Bomb(bomber) => *bomber,
+            DamageSource::SelfKill => *player,
+            _ => continue,
+        };
+        if let Some(killer) = tournament_context.player_penguins.get(&killer).copied() {
+            let killer_stats = &mut tournament_context.penguin_stats[killer.0];
+            if matches!(source, DamageSource::SelfKill) {
+                killer_stats.self_kills += 1;
+            } else {
+                killer_stats.kills += 1;
+            }
+        }
+    }
+}
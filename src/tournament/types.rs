@@ -0,0 +1,51 @@
+use std::fs;
+
+use serde::Serialize;
+
+use crate::game::types::BotDifficulty;
+
+#[derive(Serialize)]
+pub struct PenguinStats {
+    pub penguin: usize,
+    pub bot_difficulty: BotDifficulty,
+    pub wins: usize,
+    pub kills: usize,
+    pub self_kills: usize,
+    pub deaths: usize,
+}
+
+impl PenguinStats {
+    pub fn new(penguin: usize, bot_difficulty: BotDifficulty) -> Self {
+        Self {
+            penguin,
+            bot_difficulty,
+            wins: 0,
+            kills: 0,
+            self_kills: 0,
+            deaths: 0,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TournamentReport<'a> {
+    pub seed: u64,
+    pub rounds: usize,
+    pub ties: usize,
+    pub average_round_length_secs: f32,
+    pub penguins: &'a [PenguinStats],
+}
+
+impl TournamentReport<'_> {
+    pub fn save(&self, report_file_path: &str) {
+        let report_file_path = std::path::Path::new(report_file_path);
+        let serialized = serde_json::to_string_pretty(self).unwrap();
+        let parent_dir = report_file_path
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new(""));
+        match fs::create_dir_all(parent_dir).and_then(|_| fs::write(report_file_path, serialized)) {
+            Ok(()) => println!("Tournament report saved to {}.", report_file_path.display()),
+            Err(e) => eprintln!("Cannot save tournament report. Error: {}", e),
+        }
+    }
+}